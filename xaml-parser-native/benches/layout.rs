@@ -0,0 +1,87 @@
+// Сравнение раскладки результата: дерево из отдельных выделений (parse_xaml_utf8)
+// и дерево-арена (parse_xaml_arena). Вызовы идут через C ABI, как из хоста.
+//
+//     cargo bench --bench layout [-- file.xaml ...]
+//
+// Без аргументов используется сгенерированный документ.
+
+use std::ffi::c_void;
+use std::time::{Duration, Instant};
+
+use xaml_parser_native as _;
+
+unsafe extern "C" {
+    fn parse_xaml_utf8(xml: *const u8, len: usize, flags: u32, result: *mut *mut c_void, error: *mut *mut c_void) -> i32;
+    fn free_xaml_element(element: *mut c_void) -> i32;
+    fn parse_xaml_arena(
+        xml: *const u8,
+        len: usize,
+        flags: u32,
+        result: *mut *mut c_void,
+        root: *mut *const c_void,
+        error: *mut *mut c_void,
+    ) -> i32;
+    fn free_xaml_arena(arena: *mut c_void) -> i32;
+}
+
+fn generate(rows: usize) -> String {
+    let mut xaml = String::from(
+        "<Window xmlns=\"http://schemas.microsoft.com/winfx/2006/xaml/presentation\" \
+         xmlns:x=\"http://schemas.microsoft.com/winfx/2006/xaml\" x:Class=\"Bench.Main\">\n<Grid>\n",
+    );
+    for i in 0..rows {
+        xaml.push_str(&format!(
+            "  <StackPanel Grid.Row=\"{i}\" Orientation=\"Horizontal\" Margin=\"4,2\">\n    \
+             <TextBlock Text=\"{{Binding Items[{i}].Name}}\" Width=\"120\"/>\n    \
+             <TextBox x:Name=\"Box{i}\" Text=\"{{Binding Items[{i}].Value, Mode=TwoWay}}\"/>\n    \
+             <Button Content=\"Edit &amp; save\" Command=\"{{Binding EditCommand}}\"><!-- row {i} --></Button>\n  \
+             </StackPanel>\n"
+        ));
+    }
+    xaml.push_str("</Grid>\n</Window>\n");
+    xaml
+}
+
+fn measure(name: &str, xaml: &str, iterations: u32, mut run: impl FnMut()) -> Duration {
+    run();
+    let start = Instant::now();
+    for _ in 0..iterations {
+        run();
+    }
+    let per_iter = start.elapsed() / iterations;
+    let mb_per_s = xaml.len() as f64 / per_iter.as_secs_f64() / 1e6;
+    println!("  {name:<8} {per_iter:>12.2?} / parse+free  ({mb_per_s:.1} MB/s)");
+    per_iter
+}
+
+fn bench(label: &str, xaml: &str) {
+    let iterations = (50_000_000 / xaml.len().max(1)).clamp(3, 200) as u32;
+    println!("{label}: {} KB, {iterations} iterations", xaml.len() / 1024);
+
+    let tree = measure("tree", xaml, iterations, || unsafe {
+        let mut result = std::ptr::null_mut();
+        assert_eq!(parse_xaml_utf8(xaml.as_ptr(), xaml.len(), 0, &mut result, std::ptr::null_mut()), 0);
+        free_xaml_element(result);
+    });
+    let arena = measure("arena", xaml, iterations, || unsafe {
+        let mut result = std::ptr::null_mut();
+        let mut root = std::ptr::null();
+        assert_eq!(parse_xaml_arena(xaml.as_ptr(), xaml.len(), 0, &mut result, &mut root, std::ptr::null_mut()), 0);
+        free_xaml_arena(result);
+    });
+    println!("  arena/tree {:.2}x\n", arena.as_secs_f64() / tree.as_secs_f64());
+}
+
+fn main() {
+    // cargo bench передаёт --bench; остальные аргументы — пути к файлам
+    let files: Vec<String> = std::env::args().skip(1).filter(|a| !a.starts_with("--")).collect();
+    if files.is_empty() {
+        for rows in [100, 2_000, 20_000] {
+            bench(&format!("generated {rows} rows"), &generate(rows));
+        }
+    }
+    for path in files {
+        let xaml = std::fs::read_to_string(&path).expect("read XAML file");
+        bench(&path, &xaml);
+    }
+}
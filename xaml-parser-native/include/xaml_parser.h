@@ -0,0 +1,563 @@
+// Сгенерировано тестом header_is_up_to_date (src/parser/abi.rs), не редактировать.
+#ifndef XAML_PARSER_H
+#define XAML_PARSER_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// Вид дочернего узла элемента. Значения совпадают с полем `kind` в FFI.
+typedef int32_t XamlNodeKind;
+#define XAML_NODE_KIND_ELEMENT 0
+#define XAML_NODE_KIND_TEXT 1
+#define XAML_NODE_KIND_CDATA 2
+#define XAML_NODE_KIND_COMMENT 3
+#define XAML_NODE_KIND_PROCESSING_INSTRUCTION 4
+
+// Вид атрибута. Значения совпадают с полем `kind` в FFI.
+typedef int32_t XamlAttributeKind;
+#define XAML_ATTRIBUTE_KIND_PROPERTY 0
+#define XAML_ATTRIBUTE_KIND_ATTACHED_PROPERTY 1
+#define XAML_ATTRIBUTE_KIND_DIRECTIVE 2
+
+// Категория ошибки разбора, передаётся через FFI как i32.
+typedef int32_t XamlErrorKind;
+#define XAML_ERROR_KIND_SYNTAX 1
+#define XAML_ERROR_KIND_UNCLOSED_TAG 2
+#define XAML_ERROR_KIND_NO_ROOT_ELEMENT 3
+#define XAML_ERROR_KIND_INVALID_ENTITY 4
+#define XAML_ERROR_KIND_DUPLICATE_ATTRIBUTE 5
+#define XAML_ERROR_KIND_UNKNOWN_NAMESPACE 6
+#define XAML_ERROR_KIND_INVALID_NAMESPACE 7
+#define XAML_ERROR_KIND_DTD_NOT_ALLOWED 8
+#define XAML_ERROR_KIND_LIMIT_EXCEEDED 9
+#define XAML_ERROR_KIND_MARKUP_EXTENSION 10
+#define XAML_ERROR_KIND_ENCODING 11
+#define XAML_ERROR_KIND_IO 12
+#define XAML_ERROR_KIND_MARKUP_COMPATIBILITY 13
+#define XAML_ERROR_KIND_DIRECTIVE 14
+#define XAML_ERROR_KIND_ACCESS_DENIED 15
+
+// Кодировка входных байтов. Значения совпадают с кодом, который
+// `parse_xaml_bytes` возвращает через FFI.
+typedef int32_t XamlEncoding;
+#define XAML_ENCODING_UTF8 0
+#define XAML_ENCODING_UTF16LE 1
+#define XAML_ENCODING_UTF16BE 2
+#define XAML_ENCODING_LATIN1 3
+#define XAML_ENCODING_WINDOWS1252 4
+#define XAML_ENCODING_WINDOWS1251 5
+
+// Коды возврата экспортируемых функций
+#define XAML_OK 0
+#define XAML_ERROR_NULL_ARGUMENT (-1)
+
+// Некорректный UTF-8 (или UTF-16 для parse_xaml_utf16) во входных данных
+#define XAML_ERROR_INVALID_UTF8 (-2)
+#define XAML_ERROR_PARSE (-3)
+
+// Строку результата нельзя отдать как C-строку: внутри есть NUL
+#define XAML_ERROR_UNREPRESENTABLE_STRING (-4)
+
+// Внутренняя ошибка; паника перехвачена на границе FFI
+#define XAML_ERROR_PANIC (-5)
+
+// Файл не удалось прочитать (parse_xaml_file); kind в XamlParseError — Io или AccessDenied
+#define XAML_ERROR_IO (-6)
+
+// Индекс узла или дочернего узла вне диапазона (аксессоры XamlDocHandle)
+// или нет элемента с таким смещением (xaml_lossless_*_attribute)
+#define XAML_ERROR_OUT_OF_RANGE (-7)
+
+// Хост собран под другую версию ABI или раскладку структур (xaml_parser_check_layout)
+#define XAML_ERROR_ABI_MISMATCH (-8)
+
+// Обработчик parse_xaml_stream вернул ненулевое значение
+#define XAML_ERROR_CANCELLED (-9)
+
+// Документ превысил ограничение XamlParseOptions или глубину вложенности
+// по умолчанию; подробности в XamlParseError с kind LimitExceeded
+#define XAML_ERROR_LIMIT_EXCEEDED (-10)
+
+// Имя атрибута не является QName XML (xaml_lossless_set_attribute)
+#define XAML_ERROR_INVALID_NAME (-11)
+
+// Недопустимое значение аргумента, например неизвестный kind в XamlNode
+// дерева хоста (write_xaml)
+#define XAML_ERROR_INVALID_ARGUMENT (-12)
+
+// Не ошибка: xaml_lossless_remove_attribute не нашёл атрибута, документ не изменён
+#define XAML_ATTRIBUTE_NOT_FOUND 1
+
+// Флаги parse_xaml_with_flags.
+// Все строки результата хранят свою длину перед первым байтом (см. xaml_string_length)
+// и завершаются NUL. С этим флагом строки могут содержать NUL и читаются по длине;
+// без него такая строка приводит к XAML_ERROR_UNREPRESENTABLE_STRING.
+#define XAML_FLAG_LENGTH_PREFIXED_STRINGS 1u
+
+// Проверять размещение директив x: (x:Class только на корне и т.п.);
+// нарушение даёт XAML_ERROR_PARSE с kind Directive.
+#define XAML_FLAG_VALIDATE_DIRECTIVES 2u
+
+// Версия ABI: меняется при любом несовместимом изменении экспортируемых
+// структур или сигнатур. Новые функции и новые индексы раскладки её не меняют.
+#define XAML_ABI_VERSION 4u
+
+// Индексы структур в массиве размеров xaml_parser_check_layout.
+// Список только дополняется.
+#define XAML_LAYOUT_TEXT_SPAN 0
+#define XAML_LAYOUT_ATTRIBUTE 1
+#define XAML_LAYOUT_NODE 2
+#define XAML_LAYOUT_ELEMENT 3
+#define XAML_LAYOUT_PROPERTY_ELEMENT 4
+#define XAML_LAYOUT_PARSE_ERROR 5
+#define XAML_LAYOUT_MARKUP_VALUE 6
+#define XAML_LAYOUT_MARKUP_ARGUMENT 7
+#define XAML_LAYOUT_MARKUP_EXTENSION 8
+#define XAML_LAYOUT_WRITE_OPTIONS 9
+#define XAML_LAYOUT_BATCH_RESULT 10
+#define XAML_LAYOUT_STR 11
+#define XAML_LAYOUT_FLAT_NODE 12
+#define XAML_LAYOUT_FLAT_ATTRIBUTE 13
+#define XAML_LAYOUT_FLAT_DOCUMENT 14
+#define XAML_LAYOUT_STREAM_EVENT 15
+#define XAML_LAYOUT_EVENT_HANDLER 16
+#define XAML_LAYOUT_PARSE_OPTIONS 17
+#define XAML_LAYOUT_NAMESPACE 18
+#define XAML_LAYOUT_DIRECTIVES 19
+#define XAML_LAYOUT_COUNT 20
+
+// Индекс отсутствующего узла (parent корня, first_child листа и т.п.)
+#define XAML_FLAT_NONE UINT32_MAX
+
+typedef struct XamlTextSpan XamlTextSpan;
+typedef struct XamlAttribute XamlAttribute;
+typedef struct XamlNode XamlNode;
+typedef struct XamlDirectives XamlDirectives;
+typedef struct XamlElement XamlElement;
+typedef struct XamlNamespace XamlNamespace;
+typedef struct XamlParseOptions XamlParseOptions;
+typedef struct XamlPropertyElement XamlPropertyElement;
+typedef struct XamlParseError XamlParseError;
+typedef struct XamlMarkupValue XamlMarkupValue;
+typedef struct XamlMarkupArgument XamlMarkupArgument;
+typedef struct XamlMarkupExtension XamlMarkupExtension;
+typedef struct XamlWriteOptions XamlWriteOptions;
+typedef struct XamlBatchResult XamlBatchResult;
+typedef struct XamlLosslessDocument XamlLosslessDocument;
+typedef struct XamlArena XamlArena;
+typedef struct XamlStreamEvent XamlStreamEvent;
+typedef struct XamlEventHandler XamlEventHandler;
+typedef struct XamlStr XamlStr;
+typedef struct XamlFlatNode XamlFlatNode;
+typedef struct XamlFlatAttribute XamlFlatAttribute;
+typedef struct XamlFlatDocument XamlFlatDocument;
+typedef struct XamlDocHandle XamlDocHandle;
+
+// Диапазон в исходном тексте: байтовые смещения `start..end`
+// и строка/столбец обеих границ (с 1, столбец в символах).
+struct XamlTextSpan {
+    size_t start;
+    size_t end;
+    uint32_t start_line;
+    uint32_t start_column;
+    uint32_t end_line;
+    uint32_t end_column;
+};
+
+struct XamlAttribute {
+    char *key;
+    char *value;
+    char *namespace_;
+    char *prefix;
+    XamlTextSpan span;
+    XamlTextSpan value_span;
+    int32_t kind;
+    char *owner_type;
+    char *member;
+};
+
+// Дочерний узел в порядке документа. Для элемента поле element указывает
+// на тот же объект, что и массив children, и отдельно не освобождается.
+struct XamlNode {
+    int32_t kind;
+    XamlElement *element;
+    char *text;
+    char *target;
+};
+
+// Директивы языка XAML элемента (x:Class, x:Name, ...); строки равны null,
+// если директивы нет. Они же остаются в attributes с kind
+// XAML_ATTRIBUTE_KIND_DIRECTIVE. shared: -1 — x:Shared нет, 0 — false, 1 — true.
+struct XamlDirectives {
+    char *class_;
+    char *subclass;
+    char *name;
+    char *key;
+    char *uid;
+    char *field_modifier;
+    char *type_arguments;
+    int32_t shared;
+};
+
+struct XamlElement {
+    char *name;
+    char *namespace_;
+    XamlAttribute *attributes;
+    size_t attributes_len;
+    XamlElement **children;
+    size_t children_len;
+    char *text_content;
+    char *prefix;
+    XamlNode *nodes;
+    size_t nodes_len;
+    XamlTextSpan span;
+    XamlTextSpan start_tag_span;
+    XamlPropertyElement *properties;
+    size_t properties_len;
+    XamlNamespace *namespaces;
+    size_t namespaces_len;
+    XamlDirectives directives;
+};
+
+// Объявление xmlns на элементе; prefix равен null для пространства имён по умолчанию.
+// clr_namespace и assembly разобраны из URI вида clr-namespace:Foo;assembly=Bar
+// или using:Foo и равны null для остальных URI; assembly равен null и тогда,
+// когда сборка не указана.
+struct XamlNamespace {
+    char *prefix;
+    char *uri;
+    char *clr_namespace;
+    char *assembly;
+};
+
+// Параметры parse_xaml_with_options. size — sizeof(XamlParseOptions) у хоста:
+// поля за его пределами берутся по умолчанию, поэтому новые поля в конце
+// не ломают хосты, собранные раньше. Нулевое поле — значение по умолчанию:
+// для max_depth это 1024, для остальных ограничений — их отсутствие.
+// Функции разбора без XamlParseOptions ограничивают глубину только стеком
+// разбора: около 130 тысяч уровней.
+// Превышение ограничения даёт XAML_ERROR_LIMIT_EXCEEDED.
+struct XamlParseOptions {
+    size_t size;
+    // Флаги XAML_FLAG_*
+    uint32_t flags;
+    // Наибольшая глубина вложенности элементов
+    size_t max_depth;
+    // Наибольший размер текста в байтах UTF-8
+    size_t max_input_size;
+    // Наибольшее число узлов: элементов, текста, комментариев, инструкций
+    uint32_t max_nodes;
+    // Наибольшее число атрибутов одного элемента, включая xmlns
+    size_t max_attributes;
+    // Наибольшая длина значения атрибута в байтах после раскрытия сущностей
+    size_t max_attribute_value_len;
+    // На сколько байт текст и значения атрибутов после раскрытия сущностей
+    // могут в сумме превысить размер входа
+    size_t max_entity_expansion;
+    // Разрешить DTD с объявлениями сущностей; иначе DOCTYPE — ошибка разбора
+    bool allow_dtd;
+    // Обработка Markup Compatibility: mc:Ignorable, mc:AlternateContent,
+    // mc:ProcessContent. Нарушение правил даёт ошибку XAML_ERROR_PARSE
+    // с kind MarkupCompatibility
+    bool markup_compatibility;
+    // Понятные пространства имён для markup_compatibility: массив URI
+    // в UTF-8 длиной understood_namespaces_len; может быть null при нулевой длине
+    const char *const *understood_namespaces;
+    size_t understood_namespaces_len;
+};
+
+// Элемент свойства (Grid.RowDefinitions): тип-владелец, имя члена и сам элемент.
+// Место в документе: перед nodes[node_index] и перед children[child_index]
+// владельца; индекс, равный длине массива, — после всего содержимого.
+struct XamlPropertyElement {
+    char *owner_type;
+    char *member;
+    XamlElement *element;
+    size_t node_index;
+    size_t child_index;
+};
+
+struct XamlParseError {
+    int32_t kind;
+    uint32_t line;
+    uint32_t column;
+    size_t offset;
+    char *message;
+    // Путь к файлу для ошибок parse_xaml_file, иначе null
+    char *file_path;
+};
+
+// Значение расширения разметки: либо text, либо extension
+struct XamlMarkupValue {
+    char *text;
+    XamlMarkupExtension *extension;
+};
+
+// Аргумент расширения разметки; name равен null для позиционных аргументов
+struct XamlMarkupArgument {
+    char *name;
+    XamlMarkupValue value;
+};
+
+struct XamlMarkupExtension {
+    char *prefix;
+    char *name;
+    XamlMarkupArgument *arguments;
+    size_t arguments_len;
+};
+
+// Параметры write_xaml; indent равен null — узлы пишутся как есть
+struct XamlWriteOptions {
+    const char *indent;
+    bool self_close_empty;
+    bool xml_declaration;
+};
+
+// Результат разбора одного файла пакета: element при code == 0, иначе error.
+// encoding равен -1, если файл не удалось прочитать или декодировать.
+struct XamlBatchResult {
+    char *path;
+    int32_t code;
+    int32_t encoding;
+    XamlElement *element;
+    XamlParseError *error;
+};
+
+// Данные события потокового разбора. Строки — (указатель, длина) UTF-8 без NUL,
+// действительны только во время вызова; null — значения нет.
+// value — текст, значение атрибута или инструкции обработки; name — цель инструкции.
+// start..end — байтовый диапазон разметки во входном буфере, depth — число
+// открытых элементов (для start_element и attribute — включая сам элемент,
+// для end_element — уже без него).
+struct XamlStreamEvent {
+    const uint8_t *prefix;
+    size_t prefix_len;
+    const uint8_t *name;
+    size_t name_len;
+    const uint8_t *namespace_;
+    size_t namespace_len;
+    const uint8_t *value;
+    size_t value_len;
+    size_t start;
+    size_t end;
+    uint32_t depth;
+};
+
+// Таблица обработчиков parse_xaml_stream. Любой указатель может быть null —
+// такие события пропускаются. Ненулевой результат обработчика прерывает разбор.
+struct XamlEventHandler {
+    void *user_data;
+    int32_t (*start_element)(void *, const XamlStreamEvent *);
+    int32_t (*attribute)(void *, const XamlStreamEvent *);
+    int32_t (*end_element)(void *, const XamlStreamEvent *);
+    int32_t (*text)(void *, const XamlStreamEvent *);
+    int32_t (*cdata)(void *, const XamlStreamEvent *);
+    int32_t (*comment)(void *, const XamlStreamEvent *);
+    int32_t (*processing_instruction)(void *, const XamlStreamEvent *);
+};
+
+// Срез строки. offset == usize::MAX — значения нет (аналог null).
+struct XamlStr {
+    size_t offset;
+    size_t len;
+};
+
+// Узел в порядке документа. Для элементов name/namespace/prefix и атрибуты
+// first_attribute..first_attribute + attributes_len; для элементов свойств
+// заполнены owner_type и member. text — содержимое текста, CDATA и комментария,
+// значение инструкции обработки (name — её цель) или текст элемента до первого
+// дочернего узла другого вида.
+struct XamlFlatNode {
+    int32_t kind;
+    uint32_t parent;
+    uint32_t first_child;
+    uint32_t next_sibling;
+    uint32_t first_attribute;
+    uint32_t attributes_len;
+    XamlStr name;
+    XamlStr namespace_;
+    XamlStr prefix;
+    XamlStr text;
+    XamlStr owner_type;
+    XamlStr member;
+    XamlTextSpan span;
+};
+
+struct XamlFlatAttribute {
+    uint32_t element;
+    int32_t kind;
+    XamlStr name;
+    XamlStr value;
+    XamlStr namespace_;
+    XamlStr prefix;
+    XamlStr owner_type;
+    XamlStr member;
+    XamlTextSpan span;
+    XamlTextSpan value_span;
+};
+
+// Заголовок плоского документа; корень — nodes[0]
+struct XamlFlatDocument {
+    const XamlFlatNode *nodes;
+    size_t nodes_len;
+    const XamlFlatAttribute *attributes;
+    size_t attributes_len;
+    size_t input_len;
+    const uint8_t *extra;
+    size_t extra_len;
+};
+
+
+// NativeXamlParser::ParseXaml - парсинг XAML документа
+int32_t parse_xaml(const char *xml, XamlElement **result);
+
+// Парсинг с подробной информацией об ошибке; error может быть null
+int32_t parse_xaml_ex(const char *xml, XamlElement **result, XamlParseError **error);
+
+// Парсинг с флагами XAML_FLAG_*; error может быть null
+int32_t parse_xaml_with_flags(const char *xml, uint32_t flags, XamlElement **result, XamlParseError **error);
+
+// Парсинг UTF-8 текста длиной len байт; NUL в конце не нужен
+int32_t parse_xaml_utf8(const uint8_t *xml, size_t len, uint32_t flags, XamlElement **result, XamlParseError **error);
+
+// Парсинг UTF-16 текста длиной len кодовых единиц (строки .NET без перекодирования
+// на стороне хоста). Дерево то же, что и для UTF-8: смещения в span — байтовые
+// смещения в UTF-8 представлении текста.
+int32_t parse_xaml_utf16(const uint16_t *xml, size_t len, uint32_t flags, XamlElement **result, XamlParseError **error);
+
+// Парсинг UTF-8 текста длиной len байт с параметрами; options может быть null
+int32_t parse_xaml_with_options(const uint8_t *xml, size_t len, const XamlParseOptions *options, XamlElement **result, XamlParseError **error);
+
+// Парсинг байтов файла с определением кодировки по BOM и XML-объявлению.
+// encoding может быть null; при успешном декодировании в него пишется
+// значение Encoding (0 — UTF-8, 1 — UTF-16LE, ...), даже если разбор затем не удался.
+int32_t parse_xaml_bytes(const uint8_t *bytes, size_t len, uint32_t flags, XamlElement **result, int32_t *encoding, XamlParseError **error);
+
+// Чтение и парсинг файла без передачи текста через хост. path — путь в UTF-8;
+// ошибки чтения и разбора содержат путь в file_path. encoding как в parse_xaml_bytes.
+int32_t parse_xaml_file(const char *path, uint32_t flags, XamlElement **result, int32_t *encoding, XamlParseError **error);
+
+// Пакетный разбор списка файлов на пуле потоков. threads равен 0 — по числу ядер.
+// Результаты идут в порядке paths; ошибка одного файла не прерывает остальные.
+// Массив освобождается через free_xaml_batch.
+int32_t parse_xaml_files(const char *const *paths, size_t paths_len, uint32_t flags, size_t threads, XamlBatchResult **results, size_t *results_len);
+
+// Пакетный разбор файлов каталога по шаблону имени с * и ? (null — "*.xaml").
+// XAML_ERROR_IO, если каталог не удалось прочитать.
+int32_t parse_xaml_directory(const char *dir, const char *pattern, bool recursive, uint32_t flags, size_t threads, XamlBatchResult **results, size_t *results_len);
+int32_t free_xaml_batch(XamlBatchResult *results, size_t results_len);
+
+// Длина строки, полученной из библиотеки, в байтах без завершающего NUL.
+// Нужна для строк с NUL внутри (XAML_FLAG_LENGTH_PREFIXED_STRINGS).
+int32_t xaml_string_length(const char *s, size_t *result);
+
+// Освобождение ошибки, полученной из parse_xaml_ex
+int32_t free_xaml_parse_error(XamlParseError *error);
+
+// Разбор значения атрибута как расширения разметки ({Binding ...} и т.п.)
+int32_t parse_xaml_markup_extension(const char *text, XamlMarkupValue **result, XamlParseError **error);
+
+// Освобождение результата parse_xaml_markup_extension
+int32_t free_xaml_markup_value(XamlMarkupValue *value);
+
+// Запись дерева XamlElement (в том числе построенного на стороне C#) в XAML.
+// options может быть null; результат освобождается через free_xaml_string
+int32_t write_xaml(const XamlElement *element, const XamlWriteOptions *options, char **result);
+
+// Освобождение строки, полученной из write_xaml или xaml_lossless_to_string
+int32_t free_xaml_string(char *s);
+
+// Разбор в режиме без потерь для точечных правок с сохранением форматирования
+int32_t parse_xaml_lossless(const char *xml, XamlLosslessDocument **result, XamlParseError **error);
+
+// Установка атрибута у элемента, заданного смещением его начала (span.start)
+int32_t xaml_lossless_set_attribute(XamlLosslessDocument *doc, size_t element_offset, const char *name, const char *value);
+
+// Удаление атрибута; XAML_ATTRIBUTE_NOT_FOUND, если атрибута не было
+int32_t xaml_lossless_remove_attribute(XamlLosslessDocument *doc, size_t element_offset, const char *name);
+
+// Текст документа; результат освобождается через free_xaml_string
+int32_t xaml_lossless_to_string(const XamlLosslessDocument *doc, char **result);
+int32_t free_xaml_lossless(XamlLosslessDocument *doc);
+
+// NativeXamlParser::FreeXamlElement - освобождение памяти
+int32_t free_xaml_element(XamlElement *element);
+uint32_t xaml_parser_abi_version(void);
+
+// Проверка при запуске хоста: версия ABI, под которую собран хост, и размеры
+// его копий структур по индексам XAML_LAYOUT_*. Ноль — структура не используется.
+// len может быть меньше XAML_LAYOUT_COUNT, если хост собран со старым заголовком.
+int32_t xaml_parser_check_layout(uint32_t abi_version, const size_t *sizes, size_t len);
+
+// Парсинг UTF-8 текста в дерево-арену. В root пишется корневой элемент,
+// он действителен до free_xaml_arena; free_xaml_element для него не вызывается.
+int32_t parse_xaml_arena(const uint8_t *xml, size_t len, uint32_t flags, XamlArena **result, const XamlElement **root, XamlParseError **error);
+
+// То же для UTF-16 текста длиной len кодовых единиц
+int32_t parse_xaml_arena_utf16(const uint16_t *xml, size_t len, uint32_t flags, XamlArena **result, const XamlElement **root, XamlParseError **error);
+int32_t free_xaml_arena(XamlArena *arena);
+
+// Потоковый разбор UTF-8 текста с вызовом обработчиков по мере чтения;
+// дерево не строится. XAML_ERROR_CANCELLED, если обработчик вернул не 0.
+int32_t parse_xaml_stream(const uint8_t *xml, size_t len, const XamlEventHandler *handler, XamlParseError **error);
+
+// Парсинг UTF-8 текста в плоское представление. Срезы строк указывают в xml,
+// поэтому буфер должен жить, пока используется результат.
+// Освобождается через free_xaml_flat.
+int32_t parse_xaml_flat(const uint8_t *xml, size_t len, XamlFlatDocument **result, XamlParseError **error);
+
+// То же с параметрами разбора; options может быть null
+int32_t parse_xaml_flat_with_options(const uint8_t *xml, size_t len, const XamlParseOptions *options, XamlFlatDocument **result, XamlParseError **error);
+int32_t free_xaml_flat(XamlFlatDocument *doc);
+
+// Разбор UTF-8 текста в XamlDocHandle. Текст копируется, буфер можно освободить сразу.
+int32_t parse_xaml_handle(const uint8_t *xml, size_t len, XamlDocHandle **result, XamlParseError **error);
+
+// То же с ограничениями из options; options может быть null.
+// markup_compatibility и флаги к дескриптору не применяются.
+int32_t parse_xaml_handle_with_options(const uint8_t *xml, size_t len, const XamlParseOptions *options, XamlDocHandle **result, XamlParseError **error);
+
+// То же для UTF-16 текста длиной len кодовых единиц
+int32_t parse_xaml_handle_utf16(const uint16_t *xml, size_t len, XamlDocHandle **result, XamlParseError **error);
+int32_t parse_xaml_handle_utf16_with_options(const uint16_t *xml, size_t len, const XamlParseOptions *options, XamlDocHandle **result, XamlParseError **error);
+int32_t free_xaml_doc_handle(XamlDocHandle *doc);
+int32_t xaml_doc_root(const XamlDocHandle *doc, uint32_t *root);
+
+// Вид узла — значение NodeKind
+int32_t xaml_node_kind(const XamlDocHandle *doc, uint32_t node, int32_t *kind);
+
+// Родитель узла; у корня — XAML_FLAT_NONE
+int32_t xaml_node_parent(const XamlDocHandle *doc, uint32_t node, uint32_t *parent);
+
+// Локальное имя элемента или цель инструкции обработки
+int32_t xaml_node_name(const XamlDocHandle *doc, uint32_t node, const uint8_t **name, size_t *len);
+
+// Префикс имени элемента в записи документа; null без префикса и у других узлов
+int32_t xaml_node_prefix(const XamlDocHandle *doc, uint32_t node, const uint8_t **prefix, size_t *len);
+
+// URI пространства имён элемента; null без пространства имён и у других узлов
+int32_t xaml_node_namespace(const XamlDocHandle *doc, uint32_t node, const uint8_t **uri, size_t *len);
+
+// Число дочерних узлов всех видов, включая элементы свойств
+int32_t xaml_node_child_count(const XamlDocHandle *doc, uint32_t node, size_t *count);
+int32_t xaml_node_child(const XamlDocHandle *doc, uint32_t node, size_t index, uint32_t *child);
+
+// Значение атрибута по имени в записи документа ("Width", "x:Name", "Grid.Row").
+// Если атрибута нет, value — null.
+int32_t xaml_node_attribute(const XamlDocHandle *doc, uint32_t node, const char *name, const uint8_t **value, size_t *len);
+
+// Текст текстового узла, CDATA, комментария, значение инструкции обработки
+// или текст элемента до первого дочернего узла другого вида
+int32_t xaml_node_text(const XamlDocHandle *doc, uint32_t node, const uint8_t **text, size_t *len);
+int32_t xaml_node_span(const XamlDocHandle *doc, uint32_t node, XamlTextSpan *span);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,156 @@
+use crate::document::XamlDocument;
+use crate::encoding::Encoding;
+use crate::error::ParseError;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Разбирает файлы параллельно. `threads` равен 0 — по числу доступных ядер.
+/// Результаты идут в порядке `paths`.
+pub fn parse_files(
+    paths: &[PathBuf],
+    threads: usize,
+) -> Vec<Result<(XamlDocument, Encoding), ParseError>> {
+    run(paths.len(), threads, |i| XamlDocument::parse_file(&paths[i]))
+}
+
+/// Файлы каталога, имена которых подходят под шаблон с `*` и `?`
+/// (без учёта регистра ASCII, как `Directory.GetFiles` в Windows).
+/// Результат отсортирован.
+pub fn find_files(dir: impl AsRef<Path>, pattern: &str, recursive: bool) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.as_ref().to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                if recursive {
+                    dirs.push(entry.path());
+                }
+            } else if glob_match(pattern, &entry.file_name().to_string_lossy()) {
+                files.push(entry.path());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+// Выполняет f(0..len) на пуле потоков; потоки берут следующий индекс из общего счётчика,
+// поэтому крупные файлы не задерживают остальные
+pub(crate) fn run<T: Send>(len: usize, threads: usize, f: impl Fn(usize) -> T + Sync) -> Vec<T> {
+    let threads = match threads {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+    .min(len);
+    if threads <= 1 {
+        return (0..len).map(f).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let mut done: Vec<(usize, T)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        if i >= len {
+                            return done;
+                        }
+                        done.push((i, f(i)));
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+            .collect()
+    });
+    done.sort_by_key(|(i, _)| *i);
+    done.into_iter().map(|(_, result)| result).collect()
+}
+
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().map(|c| c.to_ascii_lowercase()).collect();
+    let name: Vec<char> = name.chars().map(|c| c.to_ascii_lowercase()).collect();
+
+    // Жадный перебор с возвратом к последней `*`
+    let (mut p, mut n) = (0, 0);
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn matches_glob_patterns() {
+        assert!(glob_match("*.xaml", "MainWindow.XAML"));
+        assert!(glob_match("Page?.xaml", "Page1.xaml"));
+        assert!(glob_match("*Window*.xaml", "MainWindow.g.xaml"));
+        assert!(!glob_match("*.xaml", "MainWindow.xaml.cs"));
+        assert!(!glob_match("Page?.xaml", "Page10.xaml"));
+    }
+
+    #[test]
+    fn parses_directory_in_parallel() {
+        let dir = std::env::temp_dir().join(format!("xaml-parser-batch-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("Views")).unwrap();
+        for i in 0..20 {
+            std::fs::write(dir.join(format!("Page{i:02}.xaml")), format!("<Page Tag=\"{i}\"/>")).unwrap();
+        }
+        std::fs::write(dir.join("Broken.xaml"), "<Page>").unwrap();
+        std::fs::write(dir.join("Views").join("View.xaml"), "<UserControl/>").unwrap();
+        std::fs::write(dir.join("Page00.xaml.cs"), "class Page {}").unwrap();
+
+        assert_eq!(find_files(&dir, "*.xaml", false).unwrap().len(), 21);
+        let files = find_files(&dir, "*.xaml", true).unwrap();
+        assert_eq!(files.len(), 22);
+
+        let results = parse_files(&files, 4);
+        assert_eq!(results.len(), files.len());
+        for (path, result) in files.iter().zip(&results) {
+            let name = path.file_name().unwrap().to_str().unwrap();
+            match name.strip_prefix("Page") {
+                Some(rest) => {
+                    let (doc, _) = result.as_ref().unwrap();
+                    let tag = rest.trim_end_matches(".xaml").parse::<u32>().unwrap().to_string();
+                    assert_eq!(doc.root.attribute("Tag"), Some(tag.as_str()));
+                }
+                None if name == "Broken.xaml" => {
+                    let error = result.as_ref().unwrap_err();
+                    assert_eq!(error.kind, ErrorKind::UnclosedTag);
+                    assert_eq!(error.file.as_deref(), Some(path.as_path()));
+                }
+                None => assert_eq!(result.as_ref().unwrap().0.root.name, "UserControl"),
+            }
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
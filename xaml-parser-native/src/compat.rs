@@ -0,0 +1,255 @@
+use crate::document::XamlElement;
+use crate::error::{self, ErrorKind, ParseError};
+use roxmltree::Node;
+use std::rc::Rc;
+
+// Правила Markup Compatibility (ECMA-376, часть 3) при построении модели:
+// элементы и атрибуты непонятых пространств имён из mc:Ignorable удаляются,
+// из mc:AlternateContent остаётся содержимое выбранной ветки, элементы
+// из mc:ProcessContent заменяются своим содержимым.
+
+pub(crate) const MC_NAMESPACE: &str = "http://schemas.openxmlformats.org/markup-compatibility/2006";
+
+pub(crate) struct Compat<'a> {
+    understood: &'a [String],
+}
+
+// Правила, действующие на элементе и его потомках
+#[derive(Default)]
+pub(crate) struct Scope {
+    ignorable: Vec<String>,
+    // (URI, локальное имя или "*")
+    process_content: Vec<(String, String)>,
+}
+
+// Что делать с дочерним элементом
+pub(crate) enum Action<'a, 'input> {
+    Keep,
+    Skip,
+    // Элемент убирается, а содержимое узла встаёт на его место
+    Unwrap(Node<'a, 'input>),
+}
+
+impl<'a> Compat<'a> {
+    pub(crate) fn new(understood: &'a [String]) -> Self {
+        Compat { understood }
+    }
+
+    fn understands(&self, uri: &str) -> bool {
+        uri == MC_NAMESPACE || self.understood.iter().any(|u| u == uri)
+    }
+
+    // Правила элемента с учётом его атрибутов mc:; если их нет, остаются правила родителя
+    pub(crate) fn scope(&self, node: Node, parent: &Rc<Scope>) -> Result<Rc<Scope>, ParseError> {
+        let ignorable = node.attribute((MC_NAMESPACE, "Ignorable"));
+        let process_content = node.attribute((MC_NAMESPACE, "ProcessContent"));
+        if let Some(value) = node.attribute((MC_NAMESPACE, "MustUnderstand")) {
+            for uri in resolve(node, value)? {
+                if !self.understands(uri) {
+                    return Err(error(node, format!("namespace '{uri}' must be understood")));
+                }
+            }
+        }
+        if ignorable.is_none() && process_content.is_none() {
+            return Ok(parent.clone());
+        }
+
+        let mut scope = Scope {
+            ignorable: parent.ignorable.clone(),
+            process_content: parent.process_content.clone(),
+        };
+        for uri in resolve(node, ignorable.unwrap_or_default())? {
+            scope.ignorable.push(uri.to_string());
+        }
+        for qname in process_content.unwrap_or_default().split_ascii_whitespace() {
+            let (prefix, local) = qname.split_once(':').unwrap_or(("", qname));
+            let uri = lookup(node, prefix)?;
+            scope.process_content.push((uri.to_string(), local.to_string()));
+        }
+        Ok(Rc::new(scope))
+    }
+
+    fn ignored(&self, scope: &Scope, uri: Option<&str>) -> bool {
+        uri.is_some_and(|uri| scope.ignorable.iter().any(|i| i == uri) && !self.understands(uri))
+    }
+
+    // scope — правила самого элемента
+    pub(crate) fn action<'n, 'input>(
+        &self,
+        node: Node<'n, 'input>,
+        scope: &Scope,
+    ) -> Result<Action<'n, 'input>, ParseError> {
+        let name = node.tag_name();
+        if name.namespace() == Some(MC_NAMESPACE) {
+            return match name.name() {
+                "AlternateContent" => self.select(node),
+                _ => Err(error(node, format!("unexpected element mc:{}", name.name()))),
+            };
+        }
+        if !self.ignored(scope, name.namespace()) {
+            return Ok(Action::Keep);
+        }
+        let processed = scope
+            .process_content
+            .iter()
+            .any(|(uri, local)| Some(uri.as_str()) == name.namespace() && (local == "*" || local == name.name()));
+        Ok(if processed { Action::Unwrap(node) } else { Action::Skip })
+    }
+
+    // Первая mc:Choice, все пространства имён Requires которой понятны, иначе mc:Fallback
+    fn select<'n, 'input>(&self, node: Node<'n, 'input>) -> Result<Action<'n, 'input>, ParseError> {
+        let mut fallback = None;
+        for branch in node.children().filter(|n| n.is_element()) {
+            match (branch.tag_name().namespace(), branch.tag_name().name()) {
+                (Some(MC_NAMESPACE), "Choice") => {
+                    let requires = branch
+                        .attribute("Requires")
+                        .ok_or_else(|| error(branch, "mc:Choice without Requires".to_string()))?;
+                    if resolve(branch, requires)?.into_iter().all(|uri| self.understands(uri)) {
+                        return Ok(Action::Unwrap(branch));
+                    }
+                }
+                (Some(MC_NAMESPACE), "Fallback") => fallback = fallback.or(Some(branch)),
+                _ => {
+                    let message = "mc:AlternateContent may only contain mc:Choice and mc:Fallback";
+                    return Err(error(branch, message.to_string()));
+                }
+            }
+        }
+        Ok(fallback.map_or(Action::Skip, Action::Unwrap))
+    }
+
+    // Убирает атрибуты mc: и игнорируемых пространств имён и их объявления xmlns
+    pub(crate) fn clean(&self, element: &mut XamlElement, scope: &Scope) {
+        let keep = |uri: Option<&str>| uri != Some(MC_NAMESPACE) && !self.ignored(scope, uri);
+        element.attributes.retain(|attr| keep(attr.namespace.as_deref()));
+        element.namespaces.retain(|namespace| keep(Some(&namespace.uri)));
+    }
+}
+
+// URI пространств имён для списка префиксов через пробел
+fn resolve<'a>(node: Node<'a, '_>, prefixes: &str) -> Result<Vec<&'a str>, ParseError> {
+    prefixes.split_ascii_whitespace().map(|prefix| lookup(node, prefix)).collect()
+}
+
+fn lookup<'a>(node: Node<'a, '_>, prefix: &str) -> Result<&'a str, ParseError> {
+    let prefix = (!prefix.is_empty()).then_some(prefix);
+    node.lookup_namespace_uri(prefix)
+        .ok_or_else(|| error(node, format!("unknown namespace prefix '{}'", prefix.unwrap_or_default())))
+}
+
+pub(crate) fn error(node: Node, message: String) -> ParseError {
+    let text = node.document().input_text();
+    let offset = node.range().start;
+    let (line, column) = error::position_of(text, offset);
+    ParseError {
+        kind: ErrorKind::MarkupCompatibility,
+        line,
+        column,
+        offset,
+        message,
+        file: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::{XamlDocument, XamlNode};
+    use crate::options::ParseOptions;
+
+    const HEADER: &str = "xmlns=\"urn:ui\" xmlns:mc=\"http://schemas.openxmlformats.org/markup-compatibility/2006\" \
+                          xmlns:d=\"urn:designer\" xmlns:v2=\"urn:ui-v2\"";
+
+    fn parse(body: &str, understood: &[&str]) -> Result<XamlDocument, ParseError> {
+        let text = format!("<Window {HEADER} mc:Ignorable=\"d v2\" d:DesignWidth=\"100\" Title=\"T\">{body}</Window>");
+        let options = ParseOptions {
+            understood_namespaces: Some(understood.iter().map(|s| s.to_string()).collect()),
+            ..Default::default()
+        };
+        XamlDocument::parse_with_options(&text, &options)
+    }
+
+    fn names(element: &XamlElement) -> Vec<String> {
+        element.elements().map(XamlElement::qualified_name).collect()
+    }
+
+    #[test]
+    fn drops_ignorable_namespaces() {
+        let doc = parse("<d:Data/><Button d:Tag=\"x\" Content=\"OK\"/><v2:Card/>", &[]).unwrap();
+        let root = &doc.root;
+        assert_eq!(names(root), ["Button"]);
+        assert_eq!(root.attributes.iter().map(|a| a.qualified_name()).collect::<Vec<_>>(), ["Title"]);
+        let prefixes: Vec<_> = root.namespaces.iter().map(|n| n.prefix.as_deref()).collect();
+        assert_eq!(prefixes, [None]);
+        assert_eq!(root.elements().next().unwrap().attributes.len(), 1);
+
+        // Понятное пространство имён остаётся, хотя и объявлено игнорируемым
+        let doc = parse("<v2:Card/>", &["urn:ui-v2"]).unwrap();
+        assert_eq!(names(&doc.root), ["v2:Card"]);
+        assert_eq!(doc.root.namespaces.len(), 2);
+
+        // Без understood_namespaces разметка не меняется
+        let text = format!("<Window {HEADER} mc:Ignorable=\"d\"><d:Data/></Window>");
+        let doc = XamlDocument::parse(&text).unwrap();
+        assert_eq!(names(&doc.root), ["d:Data"]);
+        assert_eq!(doc.root.namespaces.len(), 4);
+    }
+
+    #[test]
+    fn selects_alternate_content_and_processes_content() {
+        let body = "<mc:AlternateContent>\
+                    <mc:Choice Requires=\"v2\"><v2:Card/></mc:Choice>\
+                    <mc:Choice Requires=\"d v2\"><d:Card/></mc:Choice>\
+                    <mc:Fallback><Border/>text<Window.Resources/></mc:Fallback>\
+                    </mc:AlternateContent><Grid/>";
+        let doc = parse(body, &["urn:ui-v2"]).unwrap();
+        assert_eq!(names(&doc.root), ["v2:Card", "Grid"]);
+
+        let doc = parse(body, &[]).unwrap();
+        assert_eq!(names(&doc.root), ["Border", "Grid"]);
+        assert_eq!(doc.root.children[1], XamlNode::Text("text".to_string()));
+        assert!(doc.root.property("Resources").is_some());
+
+        let doc = parse("<mc:AlternateContent><mc:Choice Requires=\"d\"/></mc:AlternateContent>", &[]).unwrap();
+        assert!(doc.root.children.is_empty());
+
+        let body = "<Grid mc:ProcessContent=\"d:Wrapper\">\
+                    <d:Wrapper><Button/></d:Wrapper><d:Other><Label/></d:Other></Grid>";
+        let doc = parse(body, &[]).unwrap();
+        assert_eq!(names(doc.root.elements().next().unwrap()), ["Button"]);
+    }
+
+    #[test]
+    fn reports_violations() {
+        let error = |body: &str| {
+            let error = parse(body, &[]).unwrap_err();
+            assert_eq!(error.kind, ErrorKind::MarkupCompatibility);
+            error.message
+        };
+        assert_eq!(error("<Grid mc:MustUnderstand=\"v2\"/>"), "namespace 'urn:ui-v2' must be understood");
+        assert!(parse("<Grid mc:MustUnderstand=\"v2\"/>", &["urn:ui-v2"]).is_ok());
+        assert_eq!(
+            error("<mc:AlternateContent><mc:Choice Requires=\"zz\"/></mc:AlternateContent>"),
+            "unknown namespace prefix 'zz'"
+        );
+        assert_eq!(
+            error("<mc:AlternateContent><mc:Choice/></mc:AlternateContent>"),
+            "mc:Choice without Requires"
+        );
+        assert_eq!(error("<mc:Choice Requires=\"d\"/>"), "unexpected element mc:Choice");
+        assert_eq!(
+            error("<mc:AlternateContent><Grid/></mc:AlternateContent>"),
+            "mc:AlternateContent may only contain mc:Choice and mc:Fallback"
+        );
+
+        let options = ParseOptions {
+            understood_namespaces: Some(Vec::new()),
+            ..Default::default()
+        };
+        let text = format!("<d:Root {HEADER} mc:Ignorable=\"d\"/>");
+        let error = XamlDocument::parse_with_options(&text, &options).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::MarkupCompatibility);
+        assert_eq!(error.message, "root element cannot be ignored");
+    }
+}
@@ -0,0 +1,386 @@
+use crate::error::{self, ErrorKind, ParseError};
+use crate::lexer::{Lexer, TokenKind};
+use crate::options::{self, ParseOptions};
+use crate::text;
+use std::fmt;
+
+/// Конкретное синтаксическое дерево, сохраняющее исходный текст без потерь:
+/// пробелы, комментарии, порядок атрибутов, кавычки и запись сущностей.
+/// `to_string()` для неизменённого дерева возвращает исходный текст байт в байт.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CstDocument {
+    /// Узлы верхнего уровня: пролог, корневой элемент и всё, что после него.
+    pub nodes: Vec<CstNode>,
+}
+
+/// Узел дерева. Строки хранят исходную разметку целиком, например `<!-- x -->`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CstNode {
+    Element(CstElement),
+    Text(String),
+    CData(String),
+    Comment(String),
+    ProcessingInstruction(String),
+    Doctype(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CstElement {
+    /// Имя с префиксом, как записано в документе.
+    pub name: String,
+    pub attributes: Vec<CstAttribute>,
+    /// Пробелы перед `>` или `/>` открывающего тега.
+    pub tag_trailing: String,
+    pub self_closing: bool,
+    pub children: Vec<CstNode>,
+    /// Закрывающий тег как записан, например `</Grid >`.
+    pub end_tag: String,
+    /// Смещение `<` в исходном тексте; совпадает с `XamlElement::span.start`.
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CstAttribute {
+    /// Пробелы перед именем.
+    pub leading: String,
+    pub name: String,
+    /// Знак `=` с окружающими пробелами.
+    pub separator: String,
+    pub quote: char,
+    /// Значение между кавычками без раскрытия сущностей.
+    pub raw_value: String,
+}
+
+impl CstDocument {
+    /// Разбирает документ. Корректность проверяется тем же разбором,
+    /// что и в `XamlDocument::parse`, поэтому ошибки совпадают. Глубина
+    /// ограничена `ParseOptions::DEFAULT_MAX_DEPTH`: запись и освобождение
+    /// дерева рекурсивны.
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        options::parse_xml(text, &ParseOptions::with_default_depth())?;
+
+        let lex_error = |offset: usize, message: String| {
+            let (line, column) = error::position_of(text, offset);
+            ParseError {
+                kind: ErrorKind::Syntax,
+                line,
+                column,
+                offset,
+                message,
+                file: None,
+            }
+        };
+
+        let mut top = Vec::new();
+        let mut stack: Vec<CstElement> = Vec::new();
+        for token in Lexer::new(text) {
+            let token = token.map_err(|e| lex_error(e.offset, e.message))?;
+            let raw = &text[token.range.clone()];
+            let node = match token.kind {
+                TokenKind::Text => CstNode::Text(raw.to_string()),
+                TokenKind::CData { .. } => CstNode::CData(raw.to_string()),
+                TokenKind::Comment { .. } => CstNode::Comment(raw.to_string()),
+                TokenKind::ProcessingInstruction { .. } => CstNode::ProcessingInstruction(raw.to_string()),
+                TokenKind::Doctype => CstNode::Doctype(raw.to_string()),
+                TokenKind::StartTag(tag) => {
+                    let element = CstElement {
+                        name: tag.name.to_string(),
+                        attributes: tag
+                            .attributes
+                            .iter()
+                            .map(|a| CstAttribute {
+                                leading: a.leading.to_string(),
+                                name: a.name.to_string(),
+                                separator: a.separator.to_string(),
+                                quote: a.quote,
+                                raw_value: a.value.to_string(),
+                            })
+                            .collect(),
+                        tag_trailing: tag.trailing.to_string(),
+                        self_closing: tag.self_closing,
+                        offset: token.range.start,
+                        ..Default::default()
+                    };
+                    if !tag.self_closing {
+                        stack.push(element);
+                        continue;
+                    }
+                    CstNode::Element(element)
+                }
+                TokenKind::EndTag { name } => {
+                    let mut element = match stack.pop() {
+                        Some(element) if element.name == name => element,
+                        _ => return Err(lex_error(token.range.start, format!("unexpected close tag '{name}'"))),
+                    };
+                    element.end_tag = raw.to_string();
+                    CstNode::Element(element)
+                }
+            };
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(node),
+                None => top.push(node),
+            }
+        }
+
+        Ok(CstDocument { nodes: top })
+    }
+
+    pub fn root(&self) -> Option<&CstElement> {
+        self.nodes.iter().find_map(|node| match node {
+            CstNode::Element(element) => Some(element),
+            _ => None,
+        })
+    }
+
+    pub fn root_mut(&mut self) -> Option<&mut CstElement> {
+        self.nodes.iter_mut().find_map(|node| match node {
+            CstNode::Element(element) => Some(element),
+            _ => None,
+        })
+    }
+
+    /// Элемент по смещению его `<` в исходном тексте.
+    pub fn element_at_mut(&mut self, offset: usize) -> Option<&mut CstElement> {
+        let mut element = self.root_mut()?;
+        loop {
+            if element.offset == offset {
+                return Some(element);
+            }
+            // Дочерние элементы идут по возрастанию смещений: берём последний, начинающийся не позже
+            element = element
+                .children
+                .iter_mut()
+                .filter_map(|node| match node {
+                    CstNode::Element(child) if child.offset <= offset => Some(child),
+                    _ => None,
+                })
+                .last()?;
+        }
+    }
+}
+
+impl CstElement {
+    pub fn attribute(&self, name: &str) -> Option<&CstAttribute> {
+        self.attributes.iter().find(|a| a.name == name)
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item = &CstElement> {
+        self.children.iter().filter_map(|node| match node {
+            CstNode::Element(element) => Some(element),
+            _ => None,
+        })
+    }
+
+    pub fn elements_mut(&mut self) -> impl Iterator<Item = &mut CstElement> {
+        self.children.iter_mut().filter_map(|node| match node {
+            CstNode::Element(element) => Some(element),
+            _ => None,
+        })
+    }
+
+    /// Меняет значение атрибута, сохраняя его кавычки и окружение,
+    /// или добавляет новый атрибут в конец открывающего тега.
+    /// Имя должно быть QName XML, иначе тег не изменится.
+    pub fn set_attribute(&mut self, name: &str, value: &str) -> Result<(), InvalidNameError> {
+        if !is_qname(name) {
+            return Err(InvalidNameError { name: name.to_string() });
+        }
+        match self.attributes.iter_mut().find(|a| a.name == name) {
+            Some(attr) => attr.set_value(value),
+            None => {
+                let mut attr = CstAttribute {
+                    leading: " ".to_string(),
+                    name: name.to_string(),
+                    separator: "=".to_string(),
+                    quote: '"',
+                    raw_value: String::new(),
+                };
+                attr.set_value(value);
+                self.attributes.push(attr);
+            }
+        }
+        Ok(())
+    }
+
+    /// Удаляет атрибут вместе с пробелами перед ним.
+    pub fn remove_attribute(&mut self, name: &str) -> bool {
+        let len = self.attributes.len();
+        self.attributes.retain(|a| a.name != name);
+        self.attributes.len() != len
+    }
+}
+
+/// Имя атрибута для `CstElement::set_attribute`, не являющееся QName XML.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidNameError {
+    pub name: String,
+}
+
+impl fmt::Display for InvalidNameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' is not a valid XML attribute name", self.name)
+    }
+}
+
+impl std::error::Error for InvalidNameError {}
+
+// QName из Namespaces in XML: NCName или NCName:NCName
+fn is_qname(name: &str) -> bool {
+    match name.split_once(':') {
+        Some((prefix, local)) => is_ncname(prefix) && is_ncname(local),
+        None => is_ncname(name),
+    }
+}
+
+fn is_ncname(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(is_name_start_char) && chars.all(is_name_char)
+}
+
+// NameStartChar из XML 1.0 без двоеточия
+fn is_name_start_char(c: char) -> bool {
+    matches!(c,
+        'A'..='Z' | '_' | 'a'..='z' | '\u{C0}'..='\u{D6}' | '\u{D8}'..='\u{F6}' | '\u{F8}'..='\u{2FF}'
+        | '\u{370}'..='\u{37D}' | '\u{37F}'..='\u{1FFF}' | '\u{200C}'..='\u{200D}' | '\u{2070}'..='\u{218F}'
+        | '\u{2C00}'..='\u{2FEF}' | '\u{3001}'..='\u{D7FF}' | '\u{F900}'..='\u{FDCF}' | '\u{FDF0}'..='\u{FFFD}'
+        | '\u{10000}'..='\u{EFFFF}')
+}
+
+fn is_name_char(c: char) -> bool {
+    is_name_start_char(c)
+        || matches!(c, '-' | '.' | '0'..='9' | '\u{B7}' | '\u{300}'..='\u{36F}' | '\u{203F}'..='\u{2040}')
+}
+
+impl CstAttribute {
+    /// Значение с раскрытыми сущностями.
+    pub fn value(&self) -> String {
+        text::unescape(&self.raw_value).unwrap_or_else(|| self.raw_value.clone())
+    }
+
+    pub fn set_value(&mut self, value: &str) {
+        let mut raw = String::with_capacity(value.len());
+        for c in value.chars() {
+            match c {
+                '&' => raw.push_str("&amp;"),
+                '<' => raw.push_str("&lt;"),
+                '"' if self.quote == '"' => raw.push_str("&quot;"),
+                '\'' if self.quote == '\'' => raw.push_str("&apos;"),
+                '\t' => raw.push_str("&#x9;"),
+                '\n' => raw.push_str("&#xA;"),
+                '\r' => raw.push_str("&#xD;"),
+                _ => raw.push(c),
+            }
+        }
+        self.raw_value = raw;
+    }
+}
+
+impl fmt::Display for CstDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.nodes.iter().try_for_each(|node| node.fmt(f))
+    }
+}
+
+impl fmt::Display for CstNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CstNode::Element(element) => element.fmt(f),
+            CstNode::Text(raw)
+            | CstNode::CData(raw)
+            | CstNode::Comment(raw)
+            | CstNode::ProcessingInstruction(raw)
+            | CstNode::Doctype(raw) => f.write_str(raw),
+        }
+    }
+}
+
+impl fmt::Display for CstElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{}", self.name)?;
+        for attr in &self.attributes {
+            write!(
+                f,
+                "{}{}{}{}{}{}",
+                attr.leading, attr.name, attr.separator, attr.quote, attr.raw_value, attr.quote
+            )?;
+        }
+        f.write_str(&self.tag_trailing)?;
+        if self.self_closing {
+            return f.write_str("/>");
+        }
+        f.write_str(">")?;
+        self.children.iter().try_for_each(|node| node.fmt(f))?;
+        f.write_str(&self.end_tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "\u{feff}<?xml version=\"1.0\" encoding=\"utf-8\"?>\r\n\
+        <!-- header -->\r\n\
+        <Window xmlns=\"urn:ui\"\r\n        Title='A &amp; B'   Width = \"10\" >\r\n\
+        \t<TextBlock Text=\"&#x41;&lt;\"><![CDATA[<raw>]]>&quot;x&quot;</TextBlock >\r\n\
+        \t<Button   />\r\n\
+        </Window>\r\n<!-- trailer -->\r\n";
+
+    #[test]
+    fn round_trips_byte_identical() {
+        let doc = CstDocument::parse(SOURCE).unwrap();
+        assert_eq!(doc.to_string(), SOURCE);
+    }
+
+    #[test]
+    fn edits_one_attribute_in_place() {
+        let mut doc = CstDocument::parse(SOURCE).unwrap();
+        let window = doc.root_mut().unwrap();
+        assert_eq!(window.attribute("Title").unwrap().value(), "A & B");
+        window.set_attribute("Title", "It's <new>").unwrap();
+        window.set_attribute("Height", "20").unwrap();
+        assert!(window.remove_attribute("Width"));
+
+        assert_eq!(
+            doc.to_string(),
+            SOURCE.replace(
+                "Title='A &amp; B'   Width = \"10\" >",
+                "Title='It&apos;s &lt;new>' Height=\"20\" >"
+            )
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_attribute_names() {
+        let mut doc = CstDocument::parse(SOURCE).unwrap();
+        let window = doc.root_mut().unwrap();
+        for name in ["", "1a", "a b", "a=\"x\" b", ":a", "a:", "a:b:c", "a>"] {
+            let error = window.set_attribute(name, "x").unwrap_err();
+            assert_eq!(error.name, name);
+        }
+        assert_eq!(
+            window.set_attribute("a b", "x").unwrap_err().to_string(),
+            "'a b' is not a valid XML attribute name"
+        );
+        for name in ["x:Name", "Grid.Row", "d:DesignWidth", "_a-1", "Größe"] {
+            window.set_attribute(name, "1").unwrap();
+        }
+        assert_eq!(doc.to_string().matches("=\"1\"").count(), 5);
+    }
+
+    #[test]
+    fn finds_elements_by_source_offset() {
+        let mut doc = CstDocument::parse(SOURCE).unwrap();
+        let offset = SOURCE.find("<Button").unwrap();
+        let button = doc.element_at_mut(offset).unwrap();
+        assert_eq!(button.name, "Button");
+        button.set_attribute("Content", "OK").unwrap();
+        assert!(doc.to_string().contains("<Button Content=\"OK\"   />"));
+        assert!(doc.element_at_mut(offset + 1).is_none());
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let error = CstDocument::parse("<a><b></a>").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::UnclosedTag);
+    }
+}
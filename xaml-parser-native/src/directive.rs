@@ -0,0 +1,234 @@
+use crate::document::{XamlAttribute, XamlElement};
+use crate::error::{ErrorKind, ParseError};
+
+/// URI пространства имён языка XAML, обычно с префиксом `x:`.
+pub const XAML_LANGUAGE_NAMESPACE: &str = "http://schemas.microsoft.com/winfx/2006/xaml";
+
+/// Директивы языка XAML на элементе. Атрибуты директив остаются и в
+/// `XamlElement::attributes` с видом `AttributeKind::Directive`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct XamlDirectives {
+    /// `x:Class`: полное имя класса code-behind, допустимо только на корне.
+    pub class: Option<String>,
+    /// `x:Subclass`: допустим только вместе с `x:Class`.
+    pub subclass: Option<String>,
+    /// `x:Name`.
+    pub name: Option<String>,
+    /// `x:Key`: ключ в словаре ресурсов.
+    pub key: Option<String>,
+    /// `x:Uid`: идентификатор для локализации.
+    pub uid: Option<String>,
+    /// `x:FieldModifier`: видимость поля для `x:Name`, например `public`.
+    pub field_modifier: Option<String>,
+    /// `x:TypeArguments` в записи документа, см. `type_argument_list`.
+    pub type_arguments: Option<String>,
+    /// `x:Shared`.
+    pub shared: Option<bool>,
+}
+
+// Директивы элемента без них, см. XamlElement::directives()
+pub(crate) static NO_DIRECTIVES: XamlDirectives = XamlDirectives {
+    class: None,
+    subclass: None,
+    name: None,
+    key: None,
+    uid: None,
+    field_modifier: None,
+    type_arguments: None,
+    shared: None,
+};
+
+impl XamlDirectives {
+    /// Директивы из атрибутов пространства имён `x:`. Неразобранное значение
+    /// `x:Shared` даёт `None`.
+    pub fn from_attributes(attributes: &[XamlAttribute]) -> Self {
+        let mut directives = XamlDirectives::default();
+        for attr in attributes.iter().filter(|a| is_directive(a)) {
+            let value = Some(attr.value.clone());
+            match attr.name.as_str() {
+                "Class" => directives.class = value,
+                "Subclass" => directives.subclass = value,
+                "Name" => directives.name = value,
+                "Key" => directives.key = value,
+                "Uid" => directives.uid = value,
+                "FieldModifier" => directives.field_modifier = value,
+                "TypeArguments" => directives.type_arguments = value,
+                "Shared" => directives.shared = parse_bool(&attr.value),
+                _ => {}
+            }
+        }
+        directives
+    }
+
+    // Для поля XamlElement::directives: None, если директив нет
+    pub(crate) fn boxed(attributes: &[XamlAttribute]) -> Option<Box<Self>> {
+        let directives = XamlDirectives::from_attributes(attributes);
+        (directives != NO_DIRECTIVES).then(|| Box::new(directives))
+    }
+
+    /// Аргументы типа из `x:TypeArguments`, разделённые запятыми вне скобок:
+    /// `["x:String", "scg:List(x:Int32)"]`.
+    pub fn type_argument_list(&self) -> Vec<&str> {
+        let Some(value) = self.type_arguments.as_deref() else {
+            return Vec::new();
+        };
+        let mut arguments = Vec::new();
+        let (mut depth, mut start) = (0usize, 0);
+        for (i, c) in value.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth = depth.saturating_sub(1),
+                ',' if depth == 0 => {
+                    arguments.push(value[start..i].trim());
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        arguments.push(value[start..].trim());
+        arguments.retain(|a| !a.is_empty());
+        arguments
+    }
+}
+
+pub(crate) fn is_directive(attr: &XamlAttribute) -> bool {
+    attr.namespace.as_deref() == Some(XAML_LANGUAGE_NAMESPACE)
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim() {
+        v if v.eq_ignore_ascii_case("true") => Some(true),
+        v if v.eq_ignore_ascii_case("false") => Some(false),
+        _ => None,
+    }
+}
+
+// Правила размещения директив. Элемент свойства (с точкой в имени) директив
+// не принимает, как и других атрибутов.
+pub(crate) fn validate(element: &XamlElement, root: bool) -> Result<(), ParseError> {
+    let directive = |name: &str| element.attributes.iter().find(|a| is_directive(a) && a.name == name);
+    if element.name.contains('.')
+        && let Some(attr) = element.attributes.iter().find(|a| is_directive(a))
+    {
+        let name = attr.qualified_name();
+        return Err(error(attr, format!("directive {name} is not allowed on property element '{}'", element.name)));
+    }
+    if let Some(attr) = directive("Class")
+        && !root
+    {
+        return Err(error(attr, format!("{} is only allowed on the root element", attr.qualified_name())));
+    }
+    if let Some(attr) = directive("Subclass")
+        && element.directives().class.is_none()
+    {
+        return Err(error(attr, format!("{} requires x:Class", attr.qualified_name())));
+    }
+    // Вместо x:Name может стоять свойство Name, которое WPF считает тем же именем
+    if let Some(attr) = directive("FieldModifier")
+        && element.directives().name.is_none()
+        && element.attribute("Name").is_none()
+    {
+        return Err(error(attr, format!("{} requires x:Name", attr.qualified_name())));
+    }
+    if let Some(attr) = directive("Shared")
+        && element.directives().shared.is_none()
+    {
+        let message = format!("value '{}' of {} is not a boolean", attr.value, attr.qualified_name());
+        return Err(error(attr, message));
+    }
+    Ok(())
+}
+
+fn error(attr: &XamlAttribute, message: String) -> ParseError {
+    ParseError {
+        kind: ErrorKind::Directive,
+        line: attr.span.start_line,
+        column: attr.span.start_column,
+        offset: attr.span.start,
+        message,
+        file: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::{AttributeKind, XamlDocument};
+    use crate::options::ParseOptions;
+
+    fn validated(text: &str) -> Result<XamlDocument, ParseError> {
+        let options = ParseOptions {
+            validate_directives: true,
+            ..ParseOptions::default()
+        };
+        XamlDocument::parse_with_options(text, &options)
+    }
+
+    fn parse(body: &str) -> Result<XamlDocument, ParseError> {
+        let root = "x:Class=\"App.MainWindow\" x:Subclass=\"App.Base\"";
+        validated(&format!("<Window xmlns:x=\"{XAML_LANGUAGE_NAMESPACE}\" {root}>{body}</Window>"))
+    }
+
+    #[test]
+    fn lifts_directives() {
+        let doc = parse(
+            "<Window.Resources><Style x:Key=\"Accent\" x:Shared=\" False \"/></Window.Resources>\
+             <Button x:Name=\"ok\" x:FieldModifier=\"public\" x:Uid=\"OkButton\" Content=\"OK\"/>\
+             <Collection x:TypeArguments=\"x:String, scg:Dictionary(x:String, x:Int32),\"/>",
+        )
+        .unwrap();
+        let root = &doc.root;
+        assert_eq!(root.directives().class.as_deref(), Some("App.MainWindow"));
+        assert_eq!(root.directives().subclass.as_deref(), Some("App.Base"));
+        assert!(root.attributes.iter().all(|a| a.kind == AttributeKind::Directive));
+
+        let style = root.property("Resources").unwrap().element.elements().next().unwrap();
+        assert_eq!(style.directives().key.as_deref(), Some("Accent"));
+        assert_eq!(style.directives().shared, Some(false));
+
+        let mut elements = root.elements();
+        let button = elements.next().unwrap();
+        let expected = XamlDirectives {
+            name: Some("ok".to_string()),
+            field_modifier: Some("public".to_string()),
+            uid: Some("OkButton".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(button.directives(), &expected);
+        let kinds: Vec<_> = button.attributes.iter().map(|a| a.kind).collect();
+        let directive = AttributeKind::Directive;
+        assert_eq!(kinds, [directive, directive, directive, AttributeKind::Property]);
+
+        let collection = elements.next().unwrap();
+        assert_eq!(collection.directives().type_argument_list(), ["x:String", "scg:Dictionary(x:String, x:Int32)"]);
+    }
+
+    #[test]
+    fn rejects_misplaced_directives() {
+        let error = |body: &str| {
+            let error = parse(body).unwrap_err();
+            assert_eq!(error.kind, ErrorKind::Directive);
+            (error.line, error.column, error.message)
+        };
+        let (line, column, message) = error("\n  <Grid x:Class=\"App.Other\"/>");
+        assert_eq!((line, column), (2, 9));
+        assert_eq!(message, "x:Class is only allowed on the root element");
+        assert_eq!(error("<Button x:FieldModifier=\"public\"/>").2, "x:FieldModifier requires x:Name");
+        assert!(parse("<Button Name=\"ok\" x:FieldModifier=\"public\"/>").is_ok());
+        assert_eq!(error("<Style x:Shared=\"yes\"/>").2, "value 'yes' of x:Shared is not a boolean");
+        assert_eq!(
+            error("<Window.Content x:Name=\"c\"/>").2,
+            "directive x:Name is not allowed on property element 'Window.Content'"
+        );
+
+        let text = format!("<Window xmlns:x=\"{XAML_LANGUAGE_NAMESPACE}\" x:Subclass=\"App.Base\"/>");
+        let error = validated(&text).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Directive);
+        assert_eq!(error.message, "x:Subclass requires x:Class");
+
+        // Без validate_directives директивы поднимаются как есть
+        let text = format!("<Grid xmlns:x=\"{XAML_LANGUAGE_NAMESPACE}\"><Button x:Class=\"App.Other\"/></Grid>");
+        let doc = XamlDocument::parse(&text).unwrap();
+        assert_eq!(doc.root.elements().next().unwrap().directives().class.as_deref(), Some("App.Other"));
+    }
+}
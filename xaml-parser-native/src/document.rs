@@ -0,0 +1,606 @@
+use crate::compat::{self, Action, Compat, Scope};
+use crate::directive::{self, XamlDirectives, XAML_LANGUAGE_NAMESPACE};
+use crate::error::ParseError;
+use crate::lexer::{Lexer, Token, TokenKind};
+use crate::markup::{self, MarkupError, MarkupValue};
+use crate::namespace::{ClrNamespace, XamlNamespace};
+use crate::options::{self, ParseOptions};
+use crate::span::{self, LineIndex, TextSpan};
+use crate::text::{self, TextSegment};
+use roxmltree::Node;
+use std::rc::Rc;
+
+/// Вид дочернего узла элемента. Значения совпадают с полем `kind` в FFI.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    Element = 0,
+    Text = 1,
+    CData = 2,
+    Comment = 3,
+    ProcessingInstruction = 4,
+}
+
+/// Вид атрибута. Значения совпадают с полем `kind` в FFI.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AttributeKind {
+    #[default]
+    Property = 0,
+    /// Присоединённое свойство вида `Grid.Row`.
+    AttachedProperty = 1,
+    /// Директива языка XAML вида `x:Name`, см. `XamlElement::directives`.
+    Directive = 2,
+}
+
+/// Разобранный XAML документ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XamlDocument {
+    pub root: XamlElement,
+}
+
+/// Элемент XAML с атрибутами и дочерними узлами в порядке документа.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct XamlElement {
+    pub name: String,
+    pub namespace: Option<String>,
+    pub prefix: Option<String>,
+    /// Объявления `xmlns` на самом элементе в порядке документа.
+    pub namespaces: Vec<XamlNamespace>,
+    pub attributes: Vec<XamlAttribute>,
+    /// Директивы `x:` из `attributes`; `None`, если их нет. См. `directives()`.
+    pub directives: Option<Box<XamlDirectives>>,
+    /// Содержимое без элементов свойств.
+    pub children: Vec<XamlNode>,
+    /// Элементы свойств в порядке документа; место каждого среди `children`
+    /// задаёт `XamlPropertyElement::index`.
+    pub properties: Vec<XamlPropertyElement>,
+    /// Весь элемент от `<` открывающего тега до конца закрывающего.
+    pub span: TextSpan,
+    pub start_tag_span: TextSpan,
+}
+
+/// Атрибут элемента. `name` хранит локальное имя без префикса.
+/// Для имён с точкой `owner_type` содержит тип до точки.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct XamlAttribute {
+    pub name: String,
+    pub value: String,
+    pub namespace: Option<String>,
+    pub prefix: Option<String>,
+    pub kind: AttributeKind,
+    pub owner_type: Option<String>,
+    pub span: TextSpan,
+    /// Значение без кавычек.
+    pub value_span: TextSpan,
+}
+
+/// Элемент свойства вида `<Grid.RowDefinitions>`.
+/// `element` хранит сам элемент свойства с его содержимым.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XamlPropertyElement {
+    pub owner_type: String,
+    pub member: String,
+    pub element: XamlElement,
+    /// Место в документе: элемент свойства стоит перед `children[index]`
+    /// владельца, а при `index`, равном длине `children`, — после них.
+    pub index: usize,
+}
+
+/// Дочерний узел элемента.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XamlNode {
+    Element(XamlElement),
+    Text(String),
+    CData(String),
+    Comment(String),
+    ProcessingInstruction { target: String, value: Option<String> },
+}
+
+impl XamlDocument {
+    pub fn new(root: XamlElement) -> Self {
+        XamlDocument { root }
+    }
+
+    /// Разбирает XAML из строки с параметрами по умолчанию.
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        XamlDocument::parse_with_options(text, &ParseOptions::default())
+    }
+
+    /// Разбирает XAML из строки с заданными параметрами.
+    pub fn parse_with_options(text: &str, options: &ParseOptions) -> Result<Self, ParseError> {
+        let doc = options::parse_xml(text, options)?;
+        let index = LineIndex::new(text);
+        let compat = options.understood_namespaces.as_deref().map(Compat::new);
+        let validate = options.validate_directives;
+        let root = XamlElement::from_node(doc.root_element(), &index, compat.as_ref(), validate)?;
+        Ok(XamlDocument::new(root))
+    }
+}
+
+impl XamlElement {
+    pub fn new(name: impl Into<String>) -> Self {
+        let mut element = XamlElement::default();
+        element.name = name.into();
+        element
+    }
+
+    /// Имя с префиксом в том виде, в каком оно записано в документе.
+    pub fn qualified_name(&self) -> String {
+        qualify(self.prefix.as_deref(), &self.name)
+    }
+
+    /// Значение атрибута без пространства имён по локальному имени.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|a| a.namespace.is_none() && a.name == name)
+            .map(|a| a.value.as_str())
+    }
+
+    /// Значение атрибута по URI пространства имён и локальному имени.
+    pub fn attribute_ns(&self, namespace: &str, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|a| a.namespace.as_deref() == Some(namespace) && a.name == name)
+            .map(|a| a.value.as_str())
+    }
+
+    /// Директивы `x:` элемента; все поля пусты, если директив нет.
+    pub fn directives(&self) -> &XamlDirectives {
+        self.directives.as_deref().unwrap_or(&directive::NO_DIRECTIVES)
+    }
+
+    /// Дочерние элементы без текста, комментариев и инструкций.
+    pub fn elements(&self) -> impl Iterator<Item = &XamlElement> {
+        self.children.iter().filter_map(XamlNode::as_element)
+    }
+
+    /// Пространство имён CLR типа элемента для URI `clr-namespace:` и `using:`.
+    pub fn clr_namespace(&self) -> Option<ClrNamespace> {
+        ClrNamespace::parse(self.namespace.as_deref()?)
+    }
+
+    /// Элемент свойства по имени члена, например `RowDefinitions`.
+    pub fn property(&self, member: &str) -> Option<&XamlPropertyElement> {
+        self.properties.iter().find(|p| p.member == member)
+    }
+
+    /// Текст до первого дочернего узла другого вида, как `text_content` в FFI.
+    pub fn leading_text(&self) -> Option<String> {
+        let mut text: Option<String> = None;
+        for child in &self.children {
+            match child {
+                XamlNode::Text(s) | XamlNode::CData(s) => text.get_or_insert_with(String::new).push_str(s),
+                _ => break,
+            }
+        }
+        text
+    }
+
+    // Дерево строится с явным стеком открытых элементов, поэтому глубина
+    // документа не ограничена стеком вызовов. С compat элементы, убранные
+    // по правилам Markup Compatibility, дают в стеке прозрачные записи без
+    // элемента: их содержимое достаётся ближайшему настоящему предку.
+    fn from_node(root: Node, index: &LineIndex, compat: Option<&Compat>, validate: bool) -> Result<Self, ParseError> {
+        let scope = match compat {
+            Some(compat) => {
+                let scope = compat.scope(root, &Rc::default())?;
+                if !matches!(compat.action(root, &scope)?, Action::Keep) {
+                    return Err(compat::error(root, "root element cannot be ignored".to_string()));
+                }
+                scope
+            }
+            None => Rc::default(),
+        };
+        let element = XamlElement::open(root, index, compat, &scope, true, validate)?;
+        let mut stack = vec![(Some(element), root.children(), scope)];
+        loop {
+            let (_, children, scope) = stack.last_mut().expect("stack holds the root");
+            let Some(child) = children.next() else {
+                let (element, _, _) = stack.pop().expect("stack holds the root");
+                let Some(element) = element else { continue };
+                match stack.iter_mut().rev().find_map(|(parent, _, _)| parent.as_mut()) {
+                    Some(parent) => parent.push_element(element),
+                    None => return Ok(element),
+                }
+                continue;
+            };
+
+            if child.is_element() {
+                let Some(compat) = compat else {
+                    let element = XamlElement::open(child, index, None, scope, false, validate)?;
+                    stack.push((Some(element), child.children(), Rc::default()));
+                    continue;
+                };
+                let scope = compat.scope(child, scope)?;
+                match compat.action(child, &scope)? {
+                    Action::Keep => {
+                        let element = XamlElement::open(child, index, Some(compat), &scope, false, validate)?;
+                        stack.push((Some(element), child.children(), scope));
+                    }
+                    Action::Skip => {}
+                    Action::Unwrap(node) => {
+                        let scope = compat.scope(node, &scope)?;
+                        stack.push((None, node.children(), scope));
+                    }
+                }
+                continue;
+            }
+
+            let element = stack
+                .iter_mut()
+                .rev()
+                .find_map(|(element, _, _)| element.as_mut())
+                .expect("stack holds the root");
+            if child.is_text() {
+                element.children.extend(text::split_text_node(child).into_iter().map(|segment| match segment {
+                    TextSegment::Text(s) => XamlNode::Text(s),
+                    TextSegment::CData(s) => XamlNode::CData(s),
+                }));
+            } else if child.is_comment() {
+                element.children.push(XamlNode::Comment(child.text().unwrap_or_default().to_string()));
+            } else if let Some(pi) = child.pi() {
+                element.children.push(XamlNode::ProcessingInstruction {
+                    target: pi.target.to_string(),
+                    value: pi.value.map(str::to_string),
+                });
+            }
+        }
+    }
+
+    // Элемент без содержимого с директивами после правил Markup Compatibility
+    fn open(
+        node: Node,
+        index: &LineIndex,
+        compat: Option<&Compat>,
+        scope: &Scope,
+        root: bool,
+        validate: bool,
+    ) -> Result<Self, ParseError> {
+        let mut element = XamlElement::from_tag(node, index);
+        if let Some(compat) = compat {
+            compat.clean(&mut element, scope);
+        }
+        element.directives = XamlDirectives::boxed(&element.attributes);
+        if validate {
+            directive::validate(&element, root)?;
+        }
+        Ok(element)
+    }
+
+    // Имя, атрибуты и позиции элемента без содержимого
+    fn from_tag(node: Node, index: &LineIndex) -> Self {
+        let input = node.document().input_text();
+        let element_name = node.tag_name().name();
+        let attributes = node
+            .attributes()
+            .map(|attr| {
+                let owner_type = attr.name().split_once('.').map(|(owner, _)| owner);
+                // Button.Content на самом Button — обычное свойство, а не присоединённое
+                let kind = match owner_type {
+                    _ if attr.namespace() == Some(XAML_LANGUAGE_NAMESPACE) => AttributeKind::Directive,
+                    Some(owner) if owner != element_name => AttributeKind::AttachedProperty,
+                    _ => AttributeKind::Property,
+                };
+                XamlAttribute {
+                    name: attr.name().to_string(),
+                    value: attr.value().to_string(),
+                    namespace: attr.namespace().map(str::to_string),
+                    prefix: qname_prefix(&input[attr.range_qname()]).map(str::to_string),
+                    kind,
+                    owner_type: owner_type.map(str::to_string),
+                    span: index.span(attr.range()),
+                    value_span: index.span(attr.range_value()),
+                }
+            })
+            .collect();
+
+        XamlElement {
+            name: element_name.to_string(),
+            namespace: node.tag_name().namespace().map(str::to_string),
+            prefix: qname_prefix(element_qname(input, node.range().start)).map(str::to_string),
+            namespaces: declared_namespaces(node, input),
+            attributes,
+            directives: None,
+            children: Vec::new(),
+            properties: Vec::new(),
+            span: index.span(node.range()),
+            start_tag_span: index.span(node.range().start..span::start_tag_end(input, node.range().start)),
+        }
+    }
+
+    // Элемент с точкой в имени становится элементом свойства
+    fn push_element(&mut self, element: XamlElement) {
+        match element.name.split_once('.') {
+            Some((owner_type, member)) => self.properties.push(XamlPropertyElement {
+                owner_type: owner_type.to_string(),
+                member: member.to_string(),
+                element,
+                index: self.children.len(),
+            }),
+            None => self.children.push(XamlNode::Element(element)),
+        }
+    }
+
+    fn take_nested(&mut self, stack: &mut Vec<XamlElement>) {
+        for child in self.children.drain(..) {
+            if let XamlNode::Element(element) = child {
+                stack.push(element);
+            }
+        }
+        stack.extend(self.properties.drain(..).map(|property| property.element));
+    }
+}
+
+// Вложенные элементы снимаются в явный стек: иначе освобождение
+// глубокого дерева рекурсивно и переполняет стек
+impl Drop for XamlElement {
+    fn drop(&mut self) {
+        let mut stack = Vec::new();
+        self.take_nested(&mut stack);
+        while let Some(mut element) = stack.pop() {
+            element.take_nested(&mut stack);
+        }
+    }
+}
+
+impl XamlAttribute {
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        XamlAttribute {
+            name: name.into(),
+            value: value.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Имя с префиксом в том виде, в каком оно записано в документе.
+    pub fn qualified_name(&self) -> String {
+        qualify(self.prefix.as_deref(), &self.name)
+    }
+
+    /// Имя члена без типа-владельца: `Row` для `Grid.Row`.
+    pub fn member(&self) -> &str {
+        self.name.split_once('.').map_or(self.name.as_str(), |(_, member)| member)
+    }
+
+    pub fn is_attached(&self) -> bool {
+        self.kind == AttributeKind::AttachedProperty
+    }
+
+    /// Значение, разобранное как расширение разметки.
+    pub fn markup_value(&self) -> Result<MarkupValue, MarkupError> {
+        markup::parse_markup_value(&self.value)
+    }
+}
+
+impl XamlNode {
+    pub fn kind(&self) -> NodeKind {
+        match self {
+            XamlNode::Element(_) => NodeKind::Element,
+            XamlNode::Text(_) => NodeKind::Text,
+            XamlNode::CData(_) => NodeKind::CData,
+            XamlNode::Comment(_) => NodeKind::Comment,
+            XamlNode::ProcessingInstruction { .. } => NodeKind::ProcessingInstruction,
+        }
+    }
+
+    pub fn as_element(&self) -> Option<&XamlElement> {
+        match self {
+            XamlNode::Element(element) => Some(element),
+            _ => None,
+        }
+    }
+}
+
+fn qualify(prefix: Option<&str>, name: &str) -> String {
+    match prefix {
+        Some(prefix) => format!("{prefix}:{name}"),
+        None => name.to_string(),
+    }
+}
+
+// roxmltree не хранит префиксы, поэтому берём их из исходного текста
+fn element_qname(input: &str, start: usize) -> &str {
+    let tag = &input[start + 1..];
+    let end = tag
+        .find(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>')
+        .unwrap_or(tag.len());
+    &tag[..end]
+}
+
+// roxmltree не отдаёт объявления xmlns как атрибуты: префиксы берутся
+// из записи тега, URI — из разобранного документа
+fn declared_namespaces(node: Node, input: &str) -> Vec<XamlNamespace> {
+    let Some(Ok(Token {
+        kind: TokenKind::StartTag(tag),
+        ..
+    })) = Lexer::new(&input[node.range().start..]).next()
+    else {
+        return Vec::new();
+    };
+    tag.attributes
+        .iter()
+        .filter_map(|attr| {
+            let prefix = match attr.name.strip_prefix("xmlns")? {
+                "" => None,
+                rest => Some(rest.strip_prefix(':')?),
+            };
+            Some(XamlNamespace::new(prefix, node.lookup_namespace_uri(prefix).unwrap_or_default()))
+        })
+        .collect()
+}
+
+fn qname_prefix(qname: &str) -> Option<&str> {
+    qname.split_once(':').map(|(prefix, _)| prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_owned_tree() {
+        let doc = XamlDocument::parse(
+            r#"<Window xmlns="http://schemas.microsoft.com/winfx/2006/xaml/presentation"
+                xmlns:x="http://schemas.microsoft.com/winfx/2006/xaml" x:Class="App.MainWindow" Title="Main">
+                <TextBlock>Hello <Run>big</Run> world</TextBlock>
+            </Window>"#,
+        )
+        .unwrap();
+
+        let root = &doc.root;
+        assert_eq!(root.name, "Window");
+        assert_eq!(root.attribute("Title"), Some("Main"));
+        assert_eq!(root.attribute("Class"), None);
+        assert_eq!(
+            root.attribute_ns("http://schemas.microsoft.com/winfx/2006/xaml", "Class"),
+            Some("App.MainWindow")
+        );
+        assert_eq!(root.attributes[0].qualified_name(), "x:Class");
+
+        let text_block = root.elements().next().unwrap();
+        assert_eq!(text_block.leading_text().as_deref(), Some("Hello "));
+        let kinds: Vec<_> = text_block.children.iter().map(XamlNode::kind).collect();
+        assert_eq!(kinds, [NodeKind::Text, NodeKind::Element, NodeKind::Text]);
+        assert_eq!(text_block.children[2], XamlNode::Text(" world".to_string()));
+        let run = text_block.elements().next().unwrap();
+        assert_eq!(run.namespace, root.namespace);
+        assert_eq!(run.children, [XamlNode::Text("big".to_string())]);
+    }
+
+    #[test]
+    fn separates_property_elements() {
+        let doc = XamlDocument::parse(
+            "<Grid><Grid.RowDefinitions><RowDefinition/><RowDefinition/></Grid.RowDefinitions>\
+             <Button><Button.Content>Click</Button.Content></Button></Grid>",
+        )
+        .unwrap();
+
+        let grid = &doc.root;
+        assert_eq!(grid.elements().map(|e| e.name.as_str()).collect::<Vec<_>>(), ["Button"]);
+        let rows = grid.property("RowDefinitions").unwrap();
+        assert_eq!(rows.owner_type, "Grid");
+        assert_eq!(rows.element.name, "Grid.RowDefinitions");
+        assert_eq!(rows.element.elements().count(), 2);
+
+        let button = grid.elements().next().unwrap();
+        assert!(button.children.is_empty());
+        let content = button.property("Content").unwrap();
+        assert_eq!(content.owner_type, "Button");
+        assert_eq!(content.element.leading_text().as_deref(), Some("Click"));
+
+        let doc = XamlDocument::parse("<Grid>text<Grid.Tag/><!--c--><Grid.Row/></Grid>").unwrap();
+        let indices: Vec<_> = doc.root.properties.iter().map(|p| p.index).collect();
+        assert_eq!(indices, [1, 2]);
+    }
+
+    #[test]
+    fn classifies_attached_properties() {
+        let doc = XamlDocument::parse(
+            r#"<Button Grid.Row="1" ToolTipService.ShowDuration="500" Button.Content="OK" Width="10"/>"#,
+        )
+        .unwrap();
+        let attrs: Vec<_> = doc
+            .root
+            .attributes
+            .iter()
+            .map(|a| (a.kind, a.owner_type.as_deref(), a.member()))
+            .collect();
+        assert_eq!(
+            attrs,
+            [
+                (AttributeKind::AttachedProperty, Some("Grid"), "Row"),
+                (AttributeKind::AttachedProperty, Some("ToolTipService"), "ShowDuration"),
+                (AttributeKind::Property, Some("Button"), "Content"),
+                (AttributeKind::Property, None, "Width"),
+            ]
+        );
+        assert!(doc.root.attributes[0].is_attached());
+    }
+
+    #[test]
+    fn records_namespace_declarations() {
+        let doc = XamlDocument::parse(
+            r#"<Window xmlns="http://schemas.microsoft.com/winfx/2006/xaml/presentation"
+                xmlns:local="clr-namespace:App.Controls;assembly=App.UI">
+                <local:Card xmlns:x="http://schemas.microsoft.com/winfx/2006/xaml" x:Name="card"/>
+                <Plain xmlns=""/>
+            </Window>"#,
+        )
+        .unwrap();
+
+        let root = &doc.root;
+        assert_eq!(
+            root.namespaces,
+            [
+                XamlNamespace::new(None, "http://schemas.microsoft.com/winfx/2006/xaml/presentation"),
+                XamlNamespace::new(Some("local"), "clr-namespace:App.Controls;assembly=App.UI"),
+            ]
+        );
+        assert_eq!(root.clr_namespace(), None);
+        let local = root.namespaces[1].clr().unwrap();
+        assert_eq!((local.namespace.as_str(), local.assembly.as_deref()), ("App.Controls", Some("App.UI")));
+
+        let mut elements = root.elements();
+        let card = elements.next().unwrap();
+        assert_eq!(card.namespaces, [XamlNamespace::new(Some("x"), "http://schemas.microsoft.com/winfx/2006/xaml")]);
+        assert_eq!(card.clr_namespace(), Some(local));
+        let plain = elements.next().unwrap();
+        assert_eq!(plain.namespaces, [XamlNamespace::new(None, "")]);
+    }
+
+    #[test]
+    fn records_source_spans() {
+        let text = "<Grid>\n  <Button Width=\"10\"\n          x:Name='ok' xmlns:x='urn:x'>Click</Button>\n</Grid>";
+        let doc = XamlDocument::parse(text).unwrap();
+        assert_eq!(doc.root.span.range(), 0..text.len());
+        assert_eq!(&text[doc.root.start_tag_span.range()], "<Grid>");
+
+        let button = doc.root.elements().next().unwrap();
+        assert_eq!(
+            &text[button.span.range()],
+            "<Button Width=\"10\"\n          x:Name='ok' xmlns:x='urn:x'>Click</Button>"
+        );
+        assert_eq!(
+            &text[button.start_tag_span.range()],
+            "<Button Width=\"10\"\n          x:Name='ok' xmlns:x='urn:x'>"
+        );
+        assert_eq!((button.span.start_line, button.span.start_column), (2, 3));
+        assert_eq!((button.span.end_line, button.span.end_column), (3, 53));
+
+        let name = &button.attributes[1];
+        assert_eq!(&text[name.span.range()], "x:Name='ok'");
+        assert_eq!(&text[name.value_span.range()], "ok");
+        assert_eq!((name.span.start_line, name.span.start_column), (3, 11));
+        assert_eq!((name.value_span.start_line, name.value_span.start_column), (3, 19));
+    }
+
+    #[test]
+    fn limits_nesting_depth() {
+        let text = "<Border>".repeat(3000) + &"</Border>".repeat(3000);
+        let error = XamlDocument::parse_with_options(&text, &ParseOptions::with_default_depth()).unwrap_err();
+        assert_eq!(error.kind, crate::ErrorKind::LimitExceeded);
+        let options = ParseOptions {
+            max_depth: 2999,
+            ..Default::default()
+        };
+        assert!(XamlDocument::parse_with_options(&text, &options).is_err());
+
+        let doc = XamlDocument::parse(&text).unwrap();
+        let mut depth = 1;
+        let mut element = &doc.root;
+        while let Some(child) = element.elements().next() {
+            element = child;
+            depth += 1;
+        }
+        assert_eq!(depth, 3000);
+        assert_eq!(element.span.range(), 3000 * 8 - 8..3000 * 8 + 9);
+    }
+
+    #[test]
+    fn returns_parse_error() {
+        let error = XamlDocument::parse("<Grid>").unwrap_err();
+        assert_eq!(error.kind, crate::ErrorKind::UnclosedTag);
+    }
+}
@@ -0,0 +1,266 @@
+use crate::document::XamlDocument;
+use crate::error::{self, ErrorKind, ParseError};
+
+/// Кодировка входных байтов. Значения совпадают с кодом, который
+/// `parse_xaml_bytes` возвращает через FFI.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8 = 0,
+    Utf16Le = 1,
+    Utf16Be = 2,
+    /// ISO-8859-1.
+    Latin1 = 3,
+    Windows1252 = 4,
+    Windows1251 = 5,
+}
+
+impl Encoding {
+    /// Кодировка по имени из XML-объявления, без учёта регистра.
+    pub fn from_label(label: &str) -> Option<Self> {
+        let encoding = match label.trim().to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" | "us-ascii" | "ascii" => Encoding::Utf8,
+            "utf-16le" => Encoding::Utf16Le,
+            "utf-16be" => Encoding::Utf16Be,
+            "iso-8859-1" | "iso_8859-1" | "latin1" | "latin-1" | "l1" => Encoding::Latin1,
+            "windows-1252" | "cp1252" => Encoding::Windows1252,
+            "windows-1251" | "cp1251" => Encoding::Windows1251,
+            _ => return None,
+        };
+        Some(encoding)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "utf-8",
+            Encoding::Utf16Le => "utf-16le",
+            Encoding::Utf16Be => "utf-16be",
+            Encoding::Latin1 => "iso-8859-1",
+            Encoding::Windows1252 => "windows-1252",
+            Encoding::Windows1251 => "windows-1251",
+        }
+    }
+}
+
+impl XamlDocument {
+    /// Разбирает XAML из байтов файла, определяя кодировку по BOM
+    /// и XML-объявлению. Позиции в результате относятся к декодированному тексту.
+    pub fn parse_bytes(bytes: &[u8]) -> Result<(Self, Encoding), ParseError> {
+        let (text, encoding) = decode(bytes)?;
+        Ok((XamlDocument::parse(&text)?, encoding))
+    }
+}
+
+/// Декодирует байты документа в текст без BOM.
+///
+/// Порядок определения: BOM, затем первые байты `<` в UTF-16 без BOM,
+/// затем атрибут `encoding` XML-объявления. Без объявления текст считается UTF-8.
+pub fn decode(bytes: &[u8]) -> Result<(String, Encoding), ParseError> {
+    let (encoding, body) = if let Some(body) = bytes.strip_prefix(b"\xEF\xBB\xBF") {
+        (Encoding::Utf8, body)
+    } else if let Some(body) = bytes.strip_prefix(b"\xFF\xFE") {
+        (Encoding::Utf16Le, body)
+    } else if let Some(body) = bytes.strip_prefix(b"\xFE\xFF") {
+        (Encoding::Utf16Be, body)
+    } else if bytes.starts_with(b"<\0") {
+        (Encoding::Utf16Le, bytes)
+    } else if bytes.starts_with(b"\0<") {
+        (Encoding::Utf16Be, bytes)
+    } else {
+        (declared_encoding(bytes)?, bytes)
+    };
+    let bom_len = bytes.len() - body.len();
+
+    let text = match encoding {
+        Encoding::Utf8 => match std::str::from_utf8(body) {
+            Ok(text) => text.to_string(),
+            Err(e) => {
+                let valid = &body[..e.valid_up_to()];
+                let prefix = std::str::from_utf8(valid).unwrap_or_default();
+                return Err(decode_error(prefix, bom_len + e.valid_up_to(), "invalid UTF-8 sequence"));
+            }
+        },
+        Encoding::Utf16Le | Encoding::Utf16Be => decode_utf16(body, encoding, bom_len)?,
+        Encoding::Latin1 => body.iter().map(|&b| b as char).collect(),
+        Encoding::Windows1252 => decode_single_byte(body, &WINDOWS_1252),
+        Encoding::Windows1251 => decode_single_byte(body, &WINDOWS_1251),
+    };
+    Ok((text, encoding))
+}
+
+// Кодировка из `<?xml ... encoding="..."?>` в начале ASCII-совместимого текста.
+// Объявление UTF-16 в файле без BOM и без нулевых байтов — частый результат
+// записи через StringWriter в .NET; такой файл на самом деле в UTF-8.
+fn declared_encoding(bytes: &[u8]) -> Result<Encoding, ParseError> {
+    if !bytes.starts_with(b"<?xml") {
+        return Ok(Encoding::Utf8);
+    }
+    let end = match bytes.windows(2).position(|w| w == b"?>") {
+        Some(end) => end,
+        None => return Ok(Encoding::Utf8),
+    };
+    let declaration = String::from_utf8_lossy(&bytes[..end]);
+    let Some(start) = declaration.find("encoding") else {
+        return Ok(Encoding::Utf8);
+    };
+    let rest = declaration[start + "encoding".len()..].trim_start();
+    let Some(rest) = rest.strip_prefix('=') else {
+        return Ok(Encoding::Utf8);
+    };
+    let rest = rest.trim_start();
+    let label = match rest.chars().next() {
+        Some(quote @ ('"' | '\'')) => rest[1..].split(quote).next().unwrap_or_default(),
+        _ => return Ok(Encoding::Utf8),
+    };
+
+    if label.eq_ignore_ascii_case("utf-16") {
+        return Ok(Encoding::Utf8);
+    }
+    Encoding::from_label(label).ok_or_else(|| {
+        let offset = start + declaration[start..].find(label).unwrap_or(0);
+        decode_error(&declaration[..offset], offset, &format!("unsupported encoding '{label}'"))
+    })
+}
+
+fn decode_utf16(body: &[u8], encoding: Encoding, bom_len: usize) -> Result<String, ParseError> {
+    let units = body.chunks(2).map(|pair| match (encoding, pair) {
+        (Encoding::Utf16Le, [lo, hi]) => u16::from_le_bytes([*lo, *hi]),
+        (_, [hi, lo]) => u16::from_be_bytes([*hi, *lo]),
+        // Нечётная длина: оставшийся байт заведомо не образует символ
+        _ => 0xDC00,
+    });
+
+    let mut text = String::with_capacity(body.len() / 2);
+    for c in char::decode_utf16(units) {
+        match c {
+            Ok(c) => text.push(c),
+            Err(_) => {
+                let units_before: usize = text.chars().map(char::len_utf16).sum();
+                return Err(decode_error(&text, bom_len + units_before * 2, "invalid UTF-16 sequence"));
+            }
+        }
+    }
+    Ok(text)
+}
+
+fn decode_single_byte(body: &[u8], high: &[char; 128]) -> String {
+    body.iter()
+        .map(|&b| if b < 0x80 { b as char } else { high[b as usize - 0x80] })
+        .collect()
+}
+
+// Ошибка декодирования: offset — смещение во входных байтах,
+// строка и столбец — конец уже декодированного текста
+fn decode_error(decoded: &str, offset: usize, message: &str) -> ParseError {
+    let (line, column) = error::position_of(decoded, decoded.len());
+    ParseError {
+        kind: ErrorKind::Encoding,
+        line,
+        column,
+        offset,
+        message: message.to_string(),
+        file: None,
+    }
+}
+
+// Байты 0x80..=0xFF. Неопределённые позиции отображаются в управляющие символы C1, как в WHATWG.
+const WINDOWS_1252: [char; 128] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+    '\u{00A0}', '\u{00A1}', '\u{00A2}', '\u{00A3}', '\u{00A4}', '\u{00A5}', '\u{00A6}', '\u{00A7}',
+    '\u{00A8}', '\u{00A9}', '\u{00AA}', '\u{00AB}', '\u{00AC}', '\u{00AD}', '\u{00AE}', '\u{00AF}',
+    '\u{00B0}', '\u{00B1}', '\u{00B2}', '\u{00B3}', '\u{00B4}', '\u{00B5}', '\u{00B6}', '\u{00B7}',
+    '\u{00B8}', '\u{00B9}', '\u{00BA}', '\u{00BB}', '\u{00BC}', '\u{00BD}', '\u{00BE}', '\u{00BF}',
+    '\u{00C0}', '\u{00C1}', '\u{00C2}', '\u{00C3}', '\u{00C4}', '\u{00C5}', '\u{00C6}', '\u{00C7}',
+    '\u{00C8}', '\u{00C9}', '\u{00CA}', '\u{00CB}', '\u{00CC}', '\u{00CD}', '\u{00CE}', '\u{00CF}',
+    '\u{00D0}', '\u{00D1}', '\u{00D2}', '\u{00D3}', '\u{00D4}', '\u{00D5}', '\u{00D6}', '\u{00D7}',
+    '\u{00D8}', '\u{00D9}', '\u{00DA}', '\u{00DB}', '\u{00DC}', '\u{00DD}', '\u{00DE}', '\u{00DF}',
+    '\u{00E0}', '\u{00E1}', '\u{00E2}', '\u{00E3}', '\u{00E4}', '\u{00E5}', '\u{00E6}', '\u{00E7}',
+    '\u{00E8}', '\u{00E9}', '\u{00EA}', '\u{00EB}', '\u{00EC}', '\u{00ED}', '\u{00EE}', '\u{00EF}',
+    '\u{00F0}', '\u{00F1}', '\u{00F2}', '\u{00F3}', '\u{00F4}', '\u{00F5}', '\u{00F6}', '\u{00F7}',
+    '\u{00F8}', '\u{00F9}', '\u{00FA}', '\u{00FB}', '\u{00FC}', '\u{00FD}', '\u{00FE}', '\u{00FF}',
+];
+
+const WINDOWS_1251: [char; 128] = [
+    '\u{0402}', '\u{0403}', '\u{201A}', '\u{0453}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{20AC}', '\u{2030}', '\u{0409}', '\u{2039}', '\u{040A}', '\u{040C}', '\u{040B}', '\u{040F}',
+    '\u{0452}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{0098}', '\u{2122}', '\u{0459}', '\u{203A}', '\u{045A}', '\u{045C}', '\u{045B}', '\u{045F}',
+    '\u{00A0}', '\u{040E}', '\u{045E}', '\u{0408}', '\u{00A4}', '\u{0490}', '\u{00A6}', '\u{00A7}',
+    '\u{0401}', '\u{00A9}', '\u{0404}', '\u{00AB}', '\u{00AC}', '\u{00AD}', '\u{00AE}', '\u{0407}',
+    '\u{00B0}', '\u{00B1}', '\u{0406}', '\u{0456}', '\u{0491}', '\u{00B5}', '\u{00B6}', '\u{00B7}',
+    '\u{0451}', '\u{2116}', '\u{0454}', '\u{00BB}', '\u{0458}', '\u{0405}', '\u{0455}', '\u{0457}',
+    '\u{0410}', '\u{0411}', '\u{0412}', '\u{0413}', '\u{0414}', '\u{0415}', '\u{0416}', '\u{0417}',
+    '\u{0418}', '\u{0419}', '\u{041A}', '\u{041B}', '\u{041C}', '\u{041D}', '\u{041E}', '\u{041F}',
+    '\u{0420}', '\u{0421}', '\u{0422}', '\u{0423}', '\u{0424}', '\u{0425}', '\u{0426}', '\u{0427}',
+    '\u{0428}', '\u{0429}', '\u{042A}', '\u{042B}', '\u{042C}', '\u{042D}', '\u{042E}', '\u{042F}',
+    '\u{0430}', '\u{0431}', '\u{0432}', '\u{0433}', '\u{0434}', '\u{0435}', '\u{0436}', '\u{0437}',
+    '\u{0438}', '\u{0439}', '\u{043A}', '\u{043B}', '\u{043C}', '\u{043D}', '\u{043E}', '\u{043F}',
+    '\u{0440}', '\u{0441}', '\u{0442}', '\u{0443}', '\u{0444}', '\u{0445}', '\u{0446}', '\u{0447}',
+    '\u{0448}', '\u{0449}', '\u{044A}', '\u{044B}', '\u{044C}', '\u{044D}', '\u{044E}', '\u{044F}',
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str, big_endian: bool, bom: bool) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let units = bom.then_some(0xFEFF).into_iter().chain(text.encode_utf16());
+        for unit in units {
+            bytes.extend(if big_endian { unit.to_be_bytes() } else { unit.to_le_bytes() });
+        }
+        bytes
+    }
+
+    #[test]
+    fn detects_bom_and_utf16_without_bom() {
+        let text = "<Окно Title=\"Привет\"/>";
+        assert_eq!(decode(format!("\u{feff}{text}").as_bytes()).unwrap(), (text.to_string(), Encoding::Utf8));
+        assert_eq!(decode(&utf16(text, false, true)).unwrap(), (text.to_string(), Encoding::Utf16Le));
+        assert_eq!(decode(&utf16(text, true, true)).unwrap(), (text.to_string(), Encoding::Utf16Be));
+        assert_eq!(decode(&utf16(text, false, false)).unwrap(), (text.to_string(), Encoding::Utf16Le));
+        assert_eq!(decode(&utf16(text, true, false)).unwrap(), (text.to_string(), Encoding::Utf16Be));
+    }
+
+    #[test]
+    fn follows_xml_declaration() {
+        let mut bytes = b"<?xml version=\"1.0\" encoding='windows-1251'?><a t=\"".to_vec();
+        bytes.extend([0xCF, 0xF0, 0xE8, 0xE2, 0xE5, 0xF2, 0x20, 0xB9, 0x88]);
+        bytes.extend(b"\"/>");
+        let (text, encoding) = decode(&bytes).unwrap();
+        assert_eq!(encoding, Encoding::Windows1251);
+        assert!(text.ends_with("<a t=\"Привет №€\"/>"));
+
+        let bytes = b"<?xml version=\"1.0\" encoding=\"Windows-1252\"?><a>\x93caf\xE9\x94</a>";
+        let (doc, encoding) = XamlDocument::parse_bytes(bytes).unwrap();
+        assert_eq!(encoding, Encoding::Windows1252);
+        assert_eq!(doc.root.leading_text().as_deref(), Some("\u{201C}café\u{201D}"));
+
+        let (_, encoding) = decode(b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><a>\xE9</a>").unwrap();
+        assert_eq!(encoding, Encoding::Latin1);
+        let (_, encoding) = decode(b"<?xml version=\"1.0\" encoding=\"utf-16\"?><a/>").unwrap();
+        assert_eq!(encoding, Encoding::Utf8);
+        let (_, encoding) = decode(b"<a/>").unwrap();
+        assert_eq!(encoding, Encoding::Utf8);
+    }
+
+    #[test]
+    fn reports_undecodable_input() {
+        let error = decode(b"<?xml version=\"1.0\" encoding=\"shift_jis\"?><a/>").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Encoding);
+        assert_eq!(error.offset, 30);
+        assert!(error.message.contains("shift_jis"));
+
+        let error = decode(b"<a>\nok \xC3</a>").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Encoding);
+        assert_eq!((error.line, error.column, error.offset), (2, 4, 7));
+
+        let mut bytes = utf16("<a>", false, true);
+        bytes.extend([0x00, 0xD8, b'/', 0]);
+        let error = decode(&bytes).unwrap_err();
+        assert_eq!((error.line, error.column, error.offset), (1, 4, 8));
+    }
+}
@@ -0,0 +1,141 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Категория ошибки разбора, передаётся через FFI как i32.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    Syntax = 1,
+    UnclosedTag = 2,
+    NoRootElement = 3,
+    InvalidEntity = 4,
+    DuplicateAttribute = 5,
+    UnknownNamespace = 6,
+    InvalidNamespace = 7,
+    DtdNotAllowed = 8,
+    LimitExceeded = 9,
+    MarkupExtension = 10,
+    /// Байты не удалось декодировать в заявленной или определённой кодировке.
+    Encoding = 11,
+    /// Файл не удалось прочитать; позиции равны 0.
+    Io = 12,
+    /// Нарушены правила Markup Compatibility (`mc:`) при их обработке.
+    MarkupCompatibility = 13,
+    /// Директива `x:` стоит не на своём месте или имеет недопустимое значение
+    /// (только с `ParseOptions::validate_directives`).
+    Directive = 14,
+    /// Нет прав на чтение файла; позиции равны 0.
+    AccessDenied = 15,
+}
+
+/// Ошибка разбора с позицией в исходном тексте.
+/// Строка и столбец считаются с 1, столбец в символах.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ErrorKind,
+    pub line: u32,
+    pub column: u32,
+    pub offset: usize,
+    pub message: String,
+    /// Путь к файлу, если документ читался из файла.
+    pub file: Option<PathBuf>,
+}
+
+impl ParseError {
+    pub(crate) fn from_xml(error: &roxmltree::Error, text: &str) -> Self {
+        use roxmltree::Error as E;
+
+        let kind = match error {
+            E::UnexpectedCloseTag(..) | E::UnclosedRootNode | E::UnexpectedEndOfStream => {
+                ErrorKind::UnclosedTag
+            }
+            E::NoRootNode => ErrorKind::NoRootElement,
+            E::UnknownEntityReference(..)
+            | E::MalformedEntityReference(_)
+            | E::UnexpectedEntityCloseTag(_)
+            | E::EntityReferenceLoop(_) => ErrorKind::InvalidEntity,
+            E::DuplicatedAttribute(..) => ErrorKind::DuplicateAttribute,
+            E::UnknownNamespace(..) => ErrorKind::UnknownNamespace,
+            E::InvalidXmlPrefixUri(_)
+            | E::UnexpectedXmlUri(_)
+            | E::UnexpectedXmlnsUri(_)
+            | E::InvalidElementNamePrefix(_)
+            | E::DuplicatedNamespace(..) => ErrorKind::InvalidNamespace,
+            E::DtdDetected => ErrorKind::DtdNotAllowed,
+            E::NodesLimitReached | E::AttributesLimitReached | E::NamespacesLimitReached => {
+                ErrorKind::LimitExceeded
+            }
+            _ => ErrorKind::Syntax,
+        };
+
+        // Для незакрытого документа roxmltree отдаёт позицию 1:1,
+        // полезнее указать на конец входных данных
+        let (line, column, offset) = match error {
+            E::UnclosedRootNode | E::UnexpectedEndOfStream => {
+                let (line, column) = position_of(text, text.len());
+                (line, column, text.len())
+            }
+            _ => {
+                let pos = error.pos();
+                (pos.row, pos.col, offset_of(text, pos.row, pos.col))
+            }
+        };
+
+        ParseError {
+            kind,
+            line,
+            column,
+            offset,
+            message: error.to_string(),
+            file: None,
+        }
+    }
+
+    pub(crate) fn with_file(self, path: &Path) -> Self {
+        ParseError {
+            file: Some(path.to_path_buf()),
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.file {
+            Some(file) if self.line > 0 => {
+                write!(f, "{}:{}:{}: {}", file.display(), self.line, self.column, self.message)
+            }
+            Some(file) => write!(f, "{}: {}", file.display(), self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// Строка и столбец (с 1, столбец в символах) для байтового смещения
+pub(crate) fn position_of(text: &str, offset: usize) -> (u32, u32) {
+    let offset = offset.min(text.len());
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let line = before.bytes().filter(|&b| b == b'\n').count() + 1;
+    let column = before[line_start..].chars().count() + 1;
+    (line as u32, column as u32)
+}
+
+// Байтовое смещение для строки и столбца в формате roxmltree::TextPos
+pub(crate) fn offset_of(text: &str, line: u32, column: u32) -> usize {
+    let line_start = if line <= 1 {
+        0
+    } else {
+        match text.match_indices('\n').nth(line as usize - 2) {
+            Some((i, _)) => i + 1,
+            None => return text.len(),
+        }
+    };
+
+    text[line_start..]
+        .char_indices()
+        .nth(column.saturating_sub(1) as usize)
+        .map_or(text.len(), |(i, _)| line_start + i)
+}
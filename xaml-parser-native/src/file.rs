@@ -0,0 +1,74 @@
+use crate::document::XamlDocument;
+use crate::encoding::{self, Encoding};
+use crate::error::{ErrorKind, ParseError};
+use crate::options::ParseOptions;
+use std::path::Path;
+
+impl XamlDocument {
+    /// Читает и разбирает файл, определяя кодировку как `parse_bytes`.
+    /// Ошибки чтения и разбора содержат путь к файлу.
+    pub fn parse_file(path: impl AsRef<Path>) -> Result<(Self, Encoding), ParseError> {
+        Self::parse_file_with_options(path, &ParseOptions::default())
+    }
+
+    /// То же, что `parse_file`, но с явными параметрами разбора.
+    pub fn parse_file_with_options(
+        path: impl AsRef<Path>,
+        options: &ParseOptions,
+    ) -> Result<(Self, Encoding), ParseError> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|e| io_error(path, &e))?;
+        let (text, encoding) = encoding::decode(&bytes).map_err(|e| e.with_file(path))?;
+        let doc = XamlDocument::parse_with_options(&text, options).map_err(|e| e.with_file(path))?;
+        Ok((doc, encoding))
+    }
+}
+
+pub(crate) fn io_error(path: &Path, error: &std::io::Error) -> ParseError {
+    let kind = match error.kind() {
+        std::io::ErrorKind::PermissionDenied => ErrorKind::AccessDenied,
+        _ => ErrorKind::Io,
+    };
+    ParseError {
+        kind,
+        line: 0,
+        column: 0,
+        offset: 0,
+        message: error.to_string(),
+        file: Some(path.to_path_buf()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attaches_path_to_diagnostics() {
+        let dir = std::env::temp_dir().join(format!("xaml-parser-file-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let good = dir.join("Good.xaml");
+        std::fs::write(&good, b"\xEF\xBB\xBF<Window Title=\"\xD0\x9E\xD0\xBA\"/>").unwrap();
+        let (doc, encoding) = XamlDocument::parse_file(&good).unwrap();
+        assert_eq!(encoding, Encoding::Utf8);
+        assert_eq!(doc.root.attribute("Title"), Some("Ок"));
+
+        let bad = dir.join("Bad.xaml");
+        std::fs::write(&bad, "<Window>\n  <Grid>\n</Window>").unwrap();
+        let error = XamlDocument::parse_file(&bad).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::UnclosedTag);
+        assert_eq!(error.file.as_deref(), Some(bad.as_path()));
+        assert!(error.to_string().starts_with(&format!("{}:3:1: ", bad.display())));
+
+        let missing = dir.join("Missing.xaml");
+        let error = XamlDocument::parse_file(&missing).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Io);
+        assert_eq!((error.line, error.column), (0, 0));
+        assert_eq!(error.file.as_deref(), Some(missing.as_path()));
+        let denied = io_error(&good, &std::io::Error::from(std::io::ErrorKind::PermissionDenied));
+        assert_eq!(denied.kind, ErrorKind::AccessDenied);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
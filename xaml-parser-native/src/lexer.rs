@@ -0,0 +1,282 @@
+use std::ops::Range;
+
+// Лексер исходного текста без раскрытия сущностей и нормализации.
+// Используется там, где нужен текст в точности как в файле.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Token<'a> {
+    pub kind: TokenKind<'a>,
+    pub range: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum TokenKind<'a> {
+    Text,
+    CData { content: &'a str },
+    Comment { content: &'a str },
+    ProcessingInstruction { target: &'a str, content: Option<&'a str> },
+    Doctype,
+    StartTag(StartTag<'a>),
+    EndTag { name: &'a str },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct StartTag<'a> {
+    pub name: &'a str,
+    pub attributes: Vec<RawAttribute<'a>>,
+    // Пробелы между последним атрибутом и `>` или `/>`
+    pub trailing: &'a str,
+    pub self_closing: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RawAttribute<'a> {
+    pub leading: &'a str,
+    pub name: &'a str,
+    // Знак `=` вместе с окружающими пробелами
+    pub separator: &'a str,
+    pub quote: char,
+    // Значение между кавычками без раскрытия сущностей
+    pub value: &'a str,
+    pub range: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LexError {
+    pub offset: usize,
+    pub message: String,
+}
+
+pub(crate) struct Lexer<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub(crate) fn new(text: &'a str) -> Self {
+        Lexer { text, pos: 0 }
+    }
+
+    fn next_token(&mut self) -> Result<Token<'a>, LexError> {
+        let start = self.pos;
+        let rest = &self.text[start..];
+        let kind = if !rest.starts_with('<') {
+            self.pos += rest.find('<').unwrap_or(rest.len());
+            TokenKind::Text
+        } else if rest.starts_with("<!--") {
+            let content = self.until(start + 4, "-->", "unterminated comment")?;
+            TokenKind::Comment { content }
+        } else if rest.starts_with("<![CDATA[") {
+            let content = self.until(start + 9, "]]>", "unterminated CDATA section")?;
+            TokenKind::CData { content }
+        } else if rest.starts_with("<!DOCTYPE") {
+            self.doctype(start)?;
+            TokenKind::Doctype
+        } else if rest.starts_with("<?") {
+            let body = self.until(start + 2, "?>", "unterminated processing instruction")?;
+            let end = body.find(is_space).unwrap_or(body.len());
+            let content = body[end..].trim_start_matches(is_space);
+            TokenKind::ProcessingInstruction {
+                target: &body[..end],
+                content: (!content.is_empty()).then_some(content),
+            }
+        } else if rest.starts_with("</") {
+            self.pos = start + 2;
+            let name = self.name()?;
+            self.skip_space();
+            self.expect(">")?;
+            TokenKind::EndTag { name }
+        } else {
+            self.pos = start + 1;
+            TokenKind::StartTag(self.start_tag()?)
+        };
+
+        Ok(Token {
+            kind,
+            range: start..self.pos,
+        })
+    }
+
+    fn start_tag(&mut self) -> Result<StartTag<'a>, LexError> {
+        let name = self.name()?;
+        let mut attributes = Vec::new();
+        loop {
+            let leading = self.skip_space();
+            let rest = &self.text[self.pos..];
+            if rest.starts_with("/>") || rest.starts_with('>') {
+                let self_closing = rest.starts_with('/');
+                self.pos += if self_closing { 2 } else { 1 };
+                return Ok(StartTag {
+                    name,
+                    attributes,
+                    trailing: leading,
+                    self_closing,
+                });
+            }
+            if leading.is_empty() {
+                return Err(self.error("expected whitespace before attribute"));
+            }
+
+            let start = self.pos;
+            let name = self.name()?;
+            let separator_start = self.pos;
+            self.skip_space();
+            self.expect("=")?;
+            self.skip_space();
+            let separator = &self.text[separator_start..self.pos];
+
+            let quote = match self.text[self.pos..].chars().next() {
+                Some(q @ ('"' | '\'')) => q,
+                _ => return Err(self.error("expected quoted attribute value")),
+            };
+            let value_start = self.pos + 1;
+            let len = self.text[value_start..]
+                .find(quote)
+                .ok_or_else(|| self.error("unterminated attribute value"))?;
+            let value = &self.text[value_start..value_start + len];
+            if let Some(i) = value.find('<') {
+                return Err(LexError {
+                    offset: value_start + i,
+                    message: "unescaped '<' in attribute value".to_string(),
+                });
+            }
+            self.pos = value_start + len + 1;
+
+            attributes.push(RawAttribute {
+                leading,
+                name,
+                separator,
+                quote,
+                value,
+                range: start..self.pos,
+            });
+        }
+    }
+
+    fn doctype(&mut self, start: usize) -> Result<(), LexError> {
+        let mut quote = None;
+        let mut depth = 0usize;
+        for (i, c) in self.text[start..].char_indices() {
+            match (quote, c) {
+                (Some(q), c) if c == q => quote = None,
+                (Some(_), _) => {}
+                (None, '"' | '\'') => quote = Some(c),
+                (None, '[') => depth += 1,
+                (None, ']') => depth = depth.saturating_sub(1),
+                (None, '>') if depth == 0 => {
+                    self.pos = start + i + 1;
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+        Err(LexError {
+            offset: start,
+            message: "unterminated DOCTYPE".to_string(),
+        })
+    }
+
+    fn until(&mut self, from: usize, terminator: &str, message: &str) -> Result<&'a str, LexError> {
+        match self.text[from..].find(terminator) {
+            Some(len) => {
+                self.pos = from + len + terminator.len();
+                Ok(&self.text[from..from + len])
+            }
+            None => Err(LexError {
+                offset: self.pos,
+                message: message.to_string(),
+            }),
+        }
+    }
+
+    fn name(&mut self) -> Result<&'a str, LexError> {
+        let rest = &self.text[self.pos..];
+        let len = rest
+            .find(|c: char| is_space(c) || matches!(c, '/' | '>' | '=' | '<' | '"' | '\''))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error("expected name"));
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    fn skip_space(&mut self) -> &'a str {
+        let rest = &self.text[self.pos..];
+        let len = rest.find(|c: char| !is_space(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn expect(&mut self, s: &str) -> Result<(), LexError> {
+        if self.text[self.pos..].starts_with(s) {
+            self.pos += s.len();
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{s}'")))
+        }
+    }
+
+    fn error(&self, message: &str) -> LexError {
+        LexError {
+            offset: self.pos,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token<'a>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.text.len() {
+            return None;
+        }
+        let token = self.next_token();
+        if token.is_err() {
+            self.pos = self.text.len();
+        }
+        Some(token)
+    }
+}
+
+fn is_space(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\r' | '\n')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenizes_raw_markup() {
+        let text = "<?xml version='1.0'?><a  x = \"1 &amp;\" y='2' ><!--c--><![CDATA[d]]>e&lt;</a >";
+        let kinds: Vec<_> = Lexer::new(text).map(|t| t.unwrap().kind).collect();
+        assert_eq!(
+            kinds[0],
+            TokenKind::ProcessingInstruction {
+                target: "xml",
+                content: Some("version='1.0'")
+            }
+        );
+        let TokenKind::StartTag(tag) = &kinds[1] else { panic!() };
+        assert_eq!(tag.name, "a");
+        assert_eq!(tag.trailing, " ");
+        assert_eq!(tag.attributes[0].leading, "  ");
+        assert_eq!(tag.attributes[0].separator, " = ");
+        assert_eq!(tag.attributes[0].value, "1 &amp;");
+        assert_eq!(tag.attributes[1].quote, '\'');
+        assert_eq!(kinds[2], TokenKind::Comment { content: "c" });
+        assert_eq!(kinds[3], TokenKind::CData { content: "d" });
+        assert_eq!(kinds[4], TokenKind::Text);
+        assert_eq!(kinds[5], TokenKind::EndTag { name: "a" });
+    }
+
+    #[test]
+    fn reports_unterminated_markup() {
+        let error = Lexer::new("<a b='1").find_map(Result::err).unwrap();
+        assert_eq!(error.offset, 5);
+        let error = Lexer::new("<a><!-- x").find_map(Result::err).unwrap();
+        assert_eq!(error.offset, 3);
+    }
+}
@@ -1,16 +1,33 @@
+mod batch;
+mod compat;
+mod cst;
+mod directive;
+mod document;
+mod encoding;
+mod error;
+mod file;
+mod lexer;
+mod markup;
+mod namespace;
+mod options;
 mod parser;
+mod span;
+mod stream;
+mod strings;
+mod text;
+mod writer;
 
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub use batch::{find_files, parse_files};
+pub use cst::{CstAttribute, CstDocument, CstElement, CstNode, InvalidNameError};
+pub use directive::{XamlDirectives, XAML_LANGUAGE_NAMESPACE};
+pub use document::{
+    AttributeKind, NodeKind, XamlAttribute, XamlDocument, XamlElement, XamlNode, XamlPropertyElement,
+};
+pub use encoding::{decode, Encoding};
+pub use error::{ErrorKind, ParseError};
+pub use markup::{parse_markup_value, MarkupError, MarkupExtension, MarkupValue};
+pub use namespace::{ClrNamespace, XamlNamespace};
+pub use options::ParseOptions;
+pub use span::TextSpan;
+pub use stream::{XamlEvent, XamlReader};
+pub use writer::{write_xaml, WriteOptions};
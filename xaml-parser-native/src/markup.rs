@@ -0,0 +1,356 @@
+use std::fmt;
+
+/// Значение атрибута: обычный текст или расширение разметки.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarkupValue {
+    Text(String),
+    Extension(MarkupExtension),
+}
+
+/// Расширение разметки вида `{Binding Path=Name, Mode=TwoWay}`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MarkupExtension {
+    pub prefix: Option<String>,
+    pub name: String,
+    pub positional: Vec<MarkupValue>,
+    pub named: Vec<(String, MarkupValue)>,
+}
+
+/// Ошибка разбора расширения разметки. `offset` — байтовое смещение в значении атрибута.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkupError {
+    pub offset: usize,
+    pub message: String,
+}
+
+impl MarkupExtension {
+    /// Имя с префиксом, например `x:Static`.
+    pub fn qualified_name(&self) -> String {
+        match &self.prefix {
+            Some(prefix) => format!("{prefix}:{}", self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    pub fn argument(&self, name: &str) -> Option<&MarkupValue> {
+        self.named.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+}
+
+impl MarkupValue {
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            MarkupValue::Text(text) => Some(text),
+            MarkupValue::Extension(_) => None,
+        }
+    }
+
+    pub fn as_extension(&self) -> Option<&MarkupExtension> {
+        match self {
+            MarkupValue::Extension(extension) => Some(extension),
+            MarkupValue::Text(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for MarkupError {}
+
+/// Разбирает значение атрибута. Значения без `{` и экранированные
+/// префиксом `{}` возвращаются как текст.
+pub fn parse_markup_value(value: &str) -> Result<MarkupValue, MarkupError> {
+    if let Some(literal) = value.strip_prefix("{}") {
+        return Ok(MarkupValue::Text(literal.to_string()));
+    }
+    if !value.trim_start().starts_with('{') {
+        return Ok(MarkupValue::Text(value.to_string()));
+    }
+
+    let mut parser = MarkupParser { text: value, pos: 0, depth: 0 };
+    parser.skip_whitespace();
+    let extension = parser.parse_extension()?;
+    parser.skip_whitespace();
+    if parser.pos < value.len() {
+        return Err(parser.error("unexpected text after markup extension"));
+    }
+    Ok(MarkupValue::Extension(extension))
+}
+
+// Наибольшая вложенность расширений разметки: `{A {B {C}}}` имеет глубину 3.
+// Разбор рекурсивный, и без ограничения недоверенное значение атрибута
+// переполняло бы стек.
+const MAX_MARKUP_DEPTH: usize = 256;
+
+struct MarkupParser<'a> {
+    text: &'a str,
+    pos: usize,
+    depth: usize,
+}
+
+impl MarkupParser<'_> {
+    fn parse_extension(&mut self) -> Result<MarkupExtension, MarkupError> {
+        if self.depth == MAX_MARKUP_DEPTH {
+            return Err(self.error(&format!("markup extension nesting exceeds limit of {MAX_MARKUP_DEPTH}")));
+        }
+        self.depth += 1;
+        let extension = self.parse_extension_body();
+        self.depth -= 1;
+        extension
+    }
+
+    fn parse_extension_body(&mut self) -> Result<MarkupExtension, MarkupError> {
+        self.expect('{')?;
+        self.skip_whitespace();
+
+        let start = self.pos;
+        while self.peek().is_some_and(is_name_char) {
+            self.bump();
+        }
+        if start == self.pos {
+            return Err(self.error("expected markup extension name"));
+        }
+        let (prefix, name) = match self.text[start..self.pos].split_once(':') {
+            Some((prefix, name)) => (Some(prefix.to_string()), name.to_string()),
+            None => (None, self.text[start..self.pos].to_string()),
+        };
+        let mut extension = MarkupExtension {
+            prefix,
+            name,
+            ..Default::default()
+        };
+
+        match self.peek() {
+            Some('}') => {
+                self.bump();
+                return Ok(extension);
+            }
+            Some(c) if c.is_whitespace() => self.skip_whitespace(),
+            Some(_) => return Err(self.error("expected whitespace or '}' after markup extension name")),
+            None => return Err(self.error("unclosed markup extension")),
+        }
+
+        if self.peek() == Some('}') {
+            self.bump();
+            return Ok(extension);
+        }
+
+        loop {
+            let arg_start = self.pos;
+            let name = self.try_argument_name();
+            let value = self.parse_value()?;
+            match name {
+                Some(name) => extension.named.push((name, value)),
+                None if !extension.named.is_empty() => {
+                    return Err(MarkupError {
+                        offset: arg_start,
+                        message: "positional argument after named argument".to_string(),
+                    });
+                }
+                None => extension.positional.push(value),
+            }
+
+            self.skip_whitespace();
+            match self.bump() {
+                Some(',') => self.skip_whitespace(),
+                Some('}') => return Ok(extension),
+                Some(_) => {
+                    self.pos -= 1;
+                    return Err(self.error("expected ',' or '}'"));
+                }
+                None => return Err(self.error("unclosed markup extension")),
+            }
+        }
+    }
+
+    // Имя именованного аргумента вместе со знаком `=`, если он есть
+    fn try_argument_name(&mut self) -> Option<String> {
+        let start = self.pos;
+        while self.peek().is_some_and(is_name_char) {
+            self.bump();
+        }
+        let end = self.pos;
+        self.skip_whitespace();
+        if end > start && self.peek() == Some('=') {
+            self.bump();
+            self.skip_whitespace();
+            Some(self.text[start..end].to_string())
+        } else {
+            self.pos = start;
+            None
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<MarkupValue, MarkupError> {
+        match self.peek() {
+            Some('{') if !self.text[self.pos..].starts_with("{}") => {
+                Ok(MarkupValue::Extension(self.parse_extension()?))
+            }
+            Some(quote @ ('"' | '\'')) => {
+                self.bump();
+                let mut value = String::new();
+                loop {
+                    match self.bump() {
+                        Some('\\') => match self.bump() {
+                            Some(c) => value.push(c),
+                            None => return Err(self.error("unterminated escape sequence")),
+                        },
+                        Some(c) if c == quote => return Ok(MarkupValue::Text(value)),
+                        Some(c) => value.push(c),
+                        None => return Err(self.error("unterminated quoted string")),
+                    }
+                }
+            }
+            _ => {
+                let mut value = String::new();
+                let mut depth = 0usize;
+                while let Some(c) = self.peek() {
+                    match c {
+                        ',' if depth == 0 => break,
+                        '}' if depth == 0 => break,
+                        '\\' => {
+                            self.bump();
+                            match self.bump() {
+                                Some(c) => value.push(c),
+                                None => return Err(self.error("unterminated escape sequence")),
+                            }
+                            continue;
+                        }
+                        '{' => depth += 1,
+                        '}' => depth -= 1,
+                        _ => {}
+                    }
+                    value.push(c);
+                    self.bump();
+                }
+                let value = value.trim_end();
+                if value.is_empty() {
+                    return Err(self.error("expected argument value"));
+                }
+                Ok(MarkupValue::Text(value.to_string()))
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), MarkupError> {
+        if self.peek() == Some(expected) {
+            self.bump();
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{expected}'")))
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    fn error(&self, message: &str) -> MarkupError {
+        MarkupError {
+            offset: self.pos,
+            message: message.to_string(),
+        }
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | ':')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> MarkupValue {
+        MarkupValue::Text(s.to_string())
+    }
+
+    #[test]
+    fn parses_nested_binding() {
+        let value = parse_markup_value(
+            "{Binding Path=Name, Mode=TwoWay, Converter={StaticResource Conv}}",
+        )
+        .unwrap();
+        let binding = value.as_extension().unwrap();
+        assert_eq!(binding.name, "Binding");
+        assert!(binding.positional.is_empty());
+        assert_eq!(binding.argument("Path"), Some(&text("Name")));
+        assert_eq!(binding.argument("Mode"), Some(&text("TwoWay")));
+
+        let converter = binding.argument("Converter").unwrap().as_extension().unwrap();
+        assert_eq!(converter.name, "StaticResource");
+        assert_eq!(converter.positional, [text("Conv")]);
+    }
+
+    #[test]
+    fn limits_nesting_depth() {
+        let nested = |depth: usize| "{a ".repeat(depth - 1) + "{a}" + &"}".repeat(depth - 1);
+        let mut value = parse_markup_value(&nested(MAX_MARKUP_DEPTH)).unwrap();
+        for _ in 1..MAX_MARKUP_DEPTH {
+            value = value.as_extension().unwrap().positional[0].clone();
+        }
+        assert_eq!(value.as_extension().unwrap().name, "a");
+
+        let error = parse_markup_value(&nested(MAX_MARKUP_DEPTH + 1)).unwrap_err();
+        assert_eq!(error.offset, 3 * MAX_MARKUP_DEPTH);
+        assert_eq!(error.message, format!("markup extension nesting exceeds limit of {MAX_MARKUP_DEPTH}"));
+
+        let error = parse_markup_value(&"{a ".repeat(200_000)).unwrap_err();
+        assert_eq!(error.offset, 3 * MAX_MARKUP_DEPTH);
+    }
+
+    #[test]
+    fn parses_prefixed_and_positional_arguments() {
+        let value = parse_markup_value("{x:Static local:Colors.Accent}").unwrap();
+        let extension = value.as_extension().unwrap();
+        assert_eq!(extension.prefix.as_deref(), Some("x"));
+        assert_eq!(extension.qualified_name(), "x:Static");
+        assert_eq!(extension.positional, [text("local:Colors.Accent")]);
+
+        let value = parse_markup_value("{Binding Items[0].Name, StringFormat='{}{0:N2}', FallbackValue=\\,}").unwrap();
+        let binding = value.as_extension().unwrap();
+        assert_eq!(binding.positional, [text("Items[0].Name")]);
+        assert_eq!(binding.argument("StringFormat"), Some(&text("{}{0:N2}")));
+        assert_eq!(binding.argument("FallbackValue"), Some(&text(",")));
+
+        assert_eq!(parse_markup_value("{x:Null}").unwrap().as_extension().unwrap().name, "Null");
+    }
+
+    #[test]
+    fn keeps_escaped_and_plain_text() {
+        assert_eq!(parse_markup_value("{}{literal}").unwrap(), text("{literal}"));
+        assert_eq!(parse_markup_value("Hello").unwrap(), text("Hello"));
+    }
+
+    #[test]
+    fn reports_errors_with_offsets() {
+        let error = parse_markup_value("{Binding Path=Name").unwrap_err();
+        assert_eq!(error.offset, 18);
+
+        let error = parse_markup_value("{Binding Path=Name, Foo}").unwrap_err();
+        assert_eq!(error.offset, 20);
+        assert_eq!(error.message, "positional argument after named argument");
+
+        let error = parse_markup_value("{ }").unwrap_err();
+        assert_eq!(error.offset, 2);
+
+        let error = parse_markup_value("{Binding} tail").unwrap_err();
+        assert_eq!(error.offset, 10);
+    }
+}
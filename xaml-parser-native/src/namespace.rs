@@ -0,0 +1,89 @@
+/// Объявление `xmlns` или `xmlns:prefix` на элементе.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct XamlNamespace {
+    /// `None` для пространства имён по умолчанию.
+    pub prefix: Option<String>,
+    /// Пустая строка — сброс пространства имён по умолчанию (`xmlns=""`).
+    pub uri: String,
+}
+
+/// Пространство имён CLR из URI `clr-namespace:Foo.Bar;assembly=Baz` или `using:Foo.Bar`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClrNamespace {
+    pub namespace: String,
+    /// Сборка из `assembly=`; `None` — сборка самого документа.
+    /// У `using:` сборка не указывается.
+    pub assembly: Option<String>,
+}
+
+impl XamlNamespace {
+    pub fn new(prefix: Option<&str>, uri: impl Into<String>) -> Self {
+        XamlNamespace {
+            prefix: prefix.map(str::to_string),
+            uri: uri.into(),
+        }
+    }
+
+    /// Пространство имён CLR, если URI его описывает.
+    pub fn clr(&self) -> Option<ClrNamespace> {
+        ClrNamespace::parse(&self.uri)
+    }
+}
+
+impl ClrNamespace {
+    /// Разбирает URI вида `clr-namespace:` или `using:`. Остальные URI
+    /// (например, `http://schemas.microsoft.com/...`) дают `None`.
+    /// Части после `;` кроме `assembly=` пропускаются.
+    pub fn parse(uri: &str) -> Option<Self> {
+        if let Some(namespace) = uri.strip_prefix("using:") {
+            let namespace = namespace.trim();
+            return (!namespace.is_empty()).then(|| ClrNamespace {
+                namespace: namespace.to_string(),
+                assembly: None,
+            });
+        }
+
+        let mut parts = uri.strip_prefix("clr-namespace:")?.split(';');
+        let namespace = parts.next().unwrap_or_default().trim();
+        if namespace.is_empty() {
+            return None;
+        }
+        let assembly = parts
+            .filter_map(|part| part.split_once('='))
+            .find(|(key, _)| key.trim() == "assembly")
+            .map(|(_, value)| value.trim())
+            .filter(|value| !value.is_empty())
+            .map(str::to_string);
+        Some(ClrNamespace {
+            namespace: namespace.to_string(),
+            assembly,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clr(uri: &str) -> Option<(String, Option<String>)> {
+        ClrNamespace::parse(uri).map(|c| (c.namespace, c.assembly))
+    }
+
+    #[test]
+    fn parses_clr_namespace_uris() {
+        let owned = |ns: &str, assembly: Option<&str>| Some((ns.to_string(), assembly.map(str::to_string)));
+        assert_eq!(clr("clr-namespace:App.Controls;assembly=App.UI"), owned("App.Controls", Some("App.UI")));
+        assert_eq!(clr("clr-namespace:App.Controls"), owned("App.Controls", None));
+        assert_eq!(
+            clr("clr-namespace: System ; assembly = mscorlib ; targetPlatform=x"),
+            owned("System", Some("mscorlib"))
+        );
+        assert_eq!(clr("clr-namespace:App;assembly="), owned("App", None));
+        assert_eq!(clr("using:App.Controls"), owned("App.Controls", None));
+
+        assert_eq!(clr("clr-namespace:;assembly=App"), None);
+        assert_eq!(clr("using:"), None);
+        assert_eq!(clr("http://schemas.microsoft.com/winfx/2006/xaml"), None);
+        assert_eq!(clr("CLR-NAMESPACE:App"), None);
+    }
+}
@@ -0,0 +1,385 @@
+use crate::error::{self, ErrorKind, ParseError};
+use crate::lexer::{Lexer, TokenKind};
+use roxmltree::{Document, ParsingOptions};
+
+/// Параметры разбора для `XamlDocument::parse_with_options`.
+///
+/// Ограничения нужны для недоверенного ввода: превышение любого из них
+/// даёт ошибку `ErrorKind::LimitExceeded`. `None` — без ограничения.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Наибольшая глубина вложенности элементов; корень имеет глубину 1.
+    /// По умолчанию ограничена только стеком разбора: модель строится
+    /// и освобождается без рекурсии, а roxmltree получает стек под глубину
+    /// документа, но не больше 1 ГиБ (около 130 тысяч уровней).
+    pub max_depth: usize,
+    /// Наибольший размер текста документа в байтах UTF-8.
+    pub max_input_size: Option<usize>,
+    /// Наибольшее число узлов документа: элементов, текста, комментариев
+    /// и инструкций обработки (`nodes_limit` roxmltree).
+    pub max_nodes: Option<u32>,
+    /// Наибольшее число атрибутов одного элемента, включая объявления xmlns.
+    pub max_attributes: Option<usize>,
+    /// Наибольшая длина значения атрибута в байтах после раскрытия сущностей.
+    pub max_attribute_value_len: Option<usize>,
+    /// Наибольший прирост текста от раскрытия сущностей DTD в байтах: на столько
+    /// суммарная длина текста и значений атрибутов может превысить размер входа.
+    pub max_entity_expansion: Option<usize>,
+    /// Разрешить DTD с объявлениями сущностей (`allow_dtd` roxmltree).
+    /// Без него DOCTYPE даёт `ErrorKind::DtdNotAllowed`.
+    pub allow_dtd: bool,
+    /// Включает обработку Markup Compatibility с этими понятными пространствами
+    /// имён: элементы и атрибуты непонятых пространств из `mc:Ignorable`
+    /// убираются, из `mc:AlternateContent` выбирается ветка, `mc:ProcessContent`
+    /// оставляет содержимое убранных элементов. Атрибуты `mc:` и объявления
+    /// убранных пространств имён удаляются. `None` — разметка остаётся как есть.
+    pub understood_namespaces: Option<Vec<String>>,
+    /// Проверять размещение директив `x:`: `x:Class` только на корне,
+    /// `x:Subclass` с `x:Class`, `x:FieldModifier` с именем, булево `x:Shared`,
+    /// никаких директив на элементах свойств. Нарушение даёт `ErrorKind::Directive`.
+    /// Без проверки директивы поднимаются как есть.
+    pub validate_directives: bool,
+}
+
+impl ParseOptions {
+    /// Глубина для `XamlParseOptions` с нулевым `max_depth` и для разборов,
+    /// результат которых обходится рекурсивно.
+    pub const DEFAULT_MAX_DEPTH: usize = 1024;
+
+    // Параметры по умолчанию с ограничением глубины DEFAULT_MAX_DEPTH
+    pub(crate) fn with_default_depth() -> Self {
+        ParseOptions {
+            max_depth: ParseOptions::DEFAULT_MAX_DEPTH,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            max_depth: usize::MAX,
+            max_input_size: None,
+            max_nodes: None,
+            max_attributes: None,
+            max_attribute_value_len: None,
+            max_entity_expansion: None,
+            allow_dtd: false,
+            understood_namespaces: None,
+            validate_directives: false,
+        }
+    }
+}
+
+// До этой глубины roxmltree укладывается в стек любого потока
+const INLINE_DEPTH: usize = 64;
+// Запас стека на уровень вложенности с учётом отладочной сборки
+const STACK_PER_LEVEL: usize = 8 * 1024;
+// Стек потока разбора без учёта уровней и наибольший стек. Глубже, чем
+// помещается в наибольший стек, документ не разбирается при любом max_depth.
+const BASE_STACK: usize = 1 << 20;
+const MAX_STACK: usize = 1 << 30;
+const MAX_STACK_DEPTH: usize = (MAX_STACK - BASE_STACK) / STACK_PER_LEVEL;
+
+// Разбор roxmltree с проверкой ограничений. Разбор у roxmltree рекурсивный,
+// поэтому глубокие документы разбираются в отдельном потоке со стеком
+// под их глубину.
+pub(crate) fn parse_xml<'a>(text: &'a str, options: &ParseOptions) -> Result<Document<'a>, ParseError> {
+    if let Some(max) = options.max_input_size
+        && text.len() > max
+    {
+        return Err(limit_error(text, 0, format!("input size {} exceeds limit of {max} bytes", text.len())));
+    }
+    let scanned = scan(text, options)?;
+    let depth = match scanned {
+        Depth::Exact(depth) => depth,
+        Depth::AtMost(depth) if depth <= MAX_STACK_DEPTH => depth,
+        Depth::AtMost(_) => {
+            let message = format!("element nesting depth may exceed parser limit of {MAX_STACK_DEPTH}");
+            return Err(limit_error(text, 0, message));
+        }
+    };
+
+    let parsing = ParsingOptions {
+        allow_dtd: options.allow_dtd,
+        nodes_limit: options.max_nodes.unwrap_or(u32::MAX),
+    };
+    let parse = || Document::parse_with_options(text, parsing).map_err(|e| ParseError::from_xml(&e, text));
+    let doc = if depth <= INLINE_DEPTH {
+        parse()?
+    } else {
+        let stack_size = BASE_STACK + depth * STACK_PER_LEVEL;
+        std::thread::scope(|scope| {
+            let handle = std::thread::Builder::new()
+                .name("xaml-parse".to_string())
+                .stack_size(stack_size)
+                .spawn_scoped(scope, parse)
+                .map_err(|e| limit_error(text, 0, format!("cannot allocate parser stack: {e}")))?;
+            handle.join().unwrap_or_else(|e| std::panic::resume_unwind(e))
+        })?
+    };
+
+    if let Depth::AtMost(_) = scanned {
+        check_depth(&doc, options)?;
+    }
+    // Без DTD значения не бывают длиннее исходной записи, и проверки по исходному
+    // тексту в scan достаточно
+    if options.allow_dtd {
+        check_expansion(&doc, options)?;
+    }
+    Ok(doc)
+}
+
+// Глубина документа по итогам scan
+enum Depth {
+    Exact(usize),
+    // Лексер остановился на записи, которую может принять roxmltree (например,
+    // в DTD): дальше каждый `<` считается новым уровнем, а max_depth
+    // проверяется по готовому дереву
+    AtMost(usize),
+}
+
+// Проверки по лексемам исходного текста до разбора: глубина вложенности, число
+// атрибутов и длина их значений в записи документа. Возвращает наибольшую глубину.
+// Ошибки лексера здесь не сообщаются: их с точной позицией найдёт сам разбор.
+fn scan(text: &str, options: &ParseOptions) -> Result<Depth, ParseError> {
+    let max_depth = options.max_depth.min(MAX_STACK_DEPTH);
+    let mut depth = 0usize;
+    let mut deepest = 0;
+    for token in Lexer::new(text) {
+        let token = match token {
+            Ok(token) => token,
+            Err(e) => return Ok(Depth::AtMost(deepest.max(depth + text[e.offset..].matches('<').count()))),
+        };
+        match token.kind {
+            TokenKind::StartTag(tag) => {
+                if depth + 1 > max_depth {
+                    let message = format!("element nesting depth exceeds limit of {max_depth}");
+                    return Err(limit_error(text, token.range.start, message));
+                }
+                if let Some(max) = options.max_attributes
+                    && tag.attributes.len() > max
+                {
+                    let message = format!("element '{}' has more than {max} attributes", tag.name);
+                    return Err(limit_error(text, tag.attributes[max].range.start, message));
+                }
+                if let Some(max) = options.max_attribute_value_len
+                    && let Some(attr) = tag.attributes.iter().find(|a| a.value.len() > max)
+                {
+                    return Err(value_len_error(text, attr.range.start, attr.name, max));
+                }
+                if !tag.self_closing {
+                    depth += 1;
+                    deepest = deepest.max(depth);
+                }
+            }
+            TokenKind::EndTag { .. } => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    Ok(Depth::Exact(deepest))
+}
+
+// Проверка max_depth по дереву, когда scan не дошёл до конца. Открытые предки
+// узла — элементы стека, чей диапазон ещё не закончился.
+fn check_depth(doc: &Document, options: &ParseOptions) -> Result<(), ParseError> {
+    let mut open: Vec<usize> = Vec::new();
+    for node in doc.descendants().filter(|n| n.is_element()) {
+        let range = node.range();
+        while open.last().is_some_and(|&end| end <= range.start) {
+            open.pop();
+        }
+        if open.len() + 1 > options.max_depth {
+            let message = format!("element nesting depth exceeds limit of {}", options.max_depth);
+            return Err(limit_error(doc.input_text(), range.start, message));
+        }
+        open.push(range.end);
+    }
+    Ok(())
+}
+
+// Без сущностей DTD текст и значения атрибутов — части входа, и их суммарная
+// длина не больше его размера. Прирост от раскрытия — превышение этой суммы
+// над размером входа. Позиции текста, раскрытого из сущности, указывают в DTD,
+// поэтому ошибка для текста отмечается на его элементе.
+fn check_expansion(doc: &Document, options: &ParseOptions) -> Result<(), ParseError> {
+    let text = doc.input_text();
+    let budget = options.max_entity_expansion.map(|max| (max, text.len().saturating_add(max)));
+    let mut total = 0usize;
+    let mut add = |len: usize, at: usize| match budget {
+        Some((max, budget)) => {
+            total = total.saturating_add(len);
+            match total > budget {
+                true => Err(limit_error(text, at, format!("entity expansion exceeds limit of {max} bytes"))),
+                false => Ok(()),
+            }
+        }
+        None => Ok(()),
+    };
+
+    for node in doc.descendants() {
+        if node.is_text()
+            && let Some(parent) = node.parent_element()
+        {
+            add(node.text().map_or(0, str::len), parent.range().start)?;
+        }
+        for attr in node.attributes() {
+            add(attr.value().len(), attr.range().start)?;
+            if let Some(max) = options.max_attribute_value_len
+                && attr.value().len() > max
+            {
+                return Err(value_len_error(text, attr.range().start, &text[attr.range_qname()], max));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn value_len_error(text: &str, offset: usize, name: &str, max: usize) -> ParseError {
+    let message = format!("value of attribute '{name}' exceeds limit of {max} bytes");
+    limit_error(text, offset, message)
+}
+
+fn limit_error(text: &str, offset: usize, message: String) -> ParseError {
+    let (line, column) = error::position_of(text, offset);
+    ParseError {
+        kind: ErrorKind::LimitExceeded,
+        line,
+        column,
+        offset,
+        message,
+        file: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nested(depth: usize) -> String {
+        "<a>".repeat(depth) + &"</a>".repeat(depth)
+    }
+
+    #[test]
+    fn rejects_documents_deeper_than_limit() {
+        let options = ParseOptions {
+            max_depth: 3,
+            ..Default::default()
+        };
+        assert!(parse_xml("<a><b><c/></b><!-- <d><e> --></a>", &options).is_ok());
+
+        let error = parse_xml("<a>\n <b><c><d/></c></b></a>", &options).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::LimitExceeded);
+        assert_eq!((error.line, error.column, error.offset), (2, 8, 11));
+        assert_eq!(error.message, "element nesting depth exceeds limit of 3");
+
+        let error = parse_xml(&nested(100_000), &ParseOptions::with_default_depth()).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::LimitExceeded);
+        assert_eq!(error.offset, 3 * ParseOptions::DEFAULT_MAX_DEPTH);
+    }
+
+    #[test]
+    fn parses_deep_documents_on_large_stack() {
+        let options = ParseOptions {
+            max_depth: 5000,
+            ..Default::default()
+        };
+        let text = nested(5000);
+        let doc = parse_xml(&text, &options).unwrap();
+        assert_eq!(doc.descendants().filter(|n| n.is_element()).count(), 5000);
+    }
+
+    #[test]
+    fn bounds_parser_stack() {
+        let error = parse_xml(&nested(MAX_STACK_DEPTH + 1), &ParseOptions::default()).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::LimitExceeded);
+        assert_eq!(error.offset, 3 * MAX_STACK_DEPTH);
+        assert_eq!(error.message, format!("element nesting depth exceeds limit of {MAX_STACK_DEPTH}"));
+    }
+
+    #[test]
+    fn checks_depth_when_lexer_stops_early() {
+        // Апостроф в комментарии DTD сбивает лексер, но roxmltree его принимает
+        let prolog = "<!DOCTYPE a [<!-- it's -->]>";
+        assert!(Lexer::new(prolog).any(|token| token.is_err()));
+        let options = ParseOptions {
+            allow_dtd: true,
+            ..Default::default()
+        };
+        let text = format!("{prolog}{}", nested(5000));
+        let doc = parse_xml(&text, &options).unwrap();
+        assert_eq!(doc.descendants().filter(|n| n.is_element()).count(), 5000);
+
+        let options = ParseOptions {
+            max_depth: 100,
+            ..options
+        };
+        let error = parse_xml(&text, &options).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::LimitExceeded);
+        assert_eq!(error.offset, prolog.len() + 3 * 100);
+    }
+
+    fn limit(text: &str, options: ParseOptions) -> (usize, String) {
+        let error = parse_xml(text, &options).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::LimitExceeded);
+        (error.offset, error.message)
+    }
+
+    #[test]
+    fn enforces_size_node_and_attribute_limits() {
+        let text = "<Grid Width=\"100\" Height=\"20\"><Button/><!-- c --></Grid>";
+        let (max_input_size, max_nodes, max_attributes, max_attribute_value_len) =
+            (Some(text.len()), Some(4), Some(2), Some(3));
+        let options = ParseOptions {
+            max_input_size,
+            max_nodes,
+            max_attributes,
+            max_attribute_value_len,
+            ..Default::default()
+        };
+        assert!(parse_xml(text, &options).is_ok());
+
+        let too_long = format!("{text} ");
+        let options = || options.clone();
+        assert_eq!(
+            limit(&too_long, ParseOptions { max_input_size: Some(text.len()), ..options() }),
+            (0, format!("input size {} exceeds limit of {} bytes", too_long.len(), text.len()))
+        );
+        assert_eq!(limit(text, ParseOptions { max_nodes: Some(3), ..options() }).1, "nodes limit reached");
+        assert_eq!(
+            limit(text, ParseOptions { max_attributes: Some(1), ..options() }),
+            (18, "element 'Grid' has more than 1 attributes".to_string())
+        );
+        assert_eq!(
+            limit(text, ParseOptions { max_attribute_value_len: Some(2), ..options() }),
+            (6, "value of attribute 'Width' exceeds limit of 2 bytes".to_string())
+        );
+    }
+
+    #[test]
+    fn limits_entity_expansion() {
+        let text = "<!DOCTYPE Grid [<!ENTITY a \"0123456789\">\
+                    <!ENTITY b \"&a;&a;&a;&a;&a;&a;&a;&a;&a;&a;\">]>\
+                    <Grid Tag=\"&b;\">&b;&b;&amp;</Grid>";
+        let error = parse_xml(text, &ParseOptions::default()).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::DtdNotAllowed);
+
+        // 100 байт в атрибуте и 201 в тексте
+        let expansion = 301 - text.len();
+        let options = ParseOptions {
+            allow_dtd: true,
+            max_entity_expansion: Some(expansion),
+            ..Default::default()
+        };
+        let doc = parse_xml(text, &options).unwrap();
+        assert_eq!(doc.root_element().attribute("Tag").map(str::len), Some(100));
+
+        let (offset, message) = limit(text, ParseOptions { max_entity_expansion: Some(expansion - 1), ..options.clone() });
+        assert_eq!(Some(offset), text.find("<Grid"));
+        assert_eq!(message, format!("entity expansion exceeds limit of {} bytes", expansion - 1));
+
+        // Длина значения проверяется и после раскрытия
+        let options = ParseOptions { max_attribute_value_len: Some(50), ..options };
+        assert_eq!(limit(text, options).1, "value of attribute 'Tag' exceeds limit of 50 bytes");
+    }
+}
@@ -1,11 +1,100 @@
-use roxmltree::{Document, Node};
-use std::ffi::{CStr, CString};
+use crate::batch;
+use crate::cst::CstDocument;
+use crate::document::{self as model, AttributeKind, NodeKind, XamlDocument};
+use crate::encoding;
+use crate::error::{self, ErrorKind, ParseError};
+use crate::markup::{self, MarkupExtension, MarkupValue};
+use crate::options::ParseOptions;
+use crate::span::TextSpan;
+use crate::strings;
+use crate::writer::{self, WriteOptions};
+use std::ffi::CStr;
 use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+mod abi;
+mod arena;
+mod events;
+mod flat;
+mod handle;
+
+// Коды возврата экспортируемых функций
+pub const XAML_OK: i32 = 0;
+pub const XAML_ERROR_NULL_ARGUMENT: i32 = -1;
+// Некорректный UTF-8 (или UTF-16 для parse_xaml_utf16) во входных данных
+pub const XAML_ERROR_INVALID_UTF8: i32 = -2;
+pub const XAML_ERROR_PARSE: i32 = -3;
+// Строку результата нельзя отдать как C-строку: внутри есть NUL
+pub const XAML_ERROR_UNREPRESENTABLE_STRING: i32 = -4;
+// Внутренняя ошибка; паника перехвачена на границе FFI
+pub const XAML_ERROR_PANIC: i32 = -5;
+// Файл не удалось прочитать (parse_xaml_file); kind в XamlParseError — Io или AccessDenied
+pub const XAML_ERROR_IO: i32 = -6;
+// Индекс узла или дочернего узла вне диапазона (аксессоры XamlDocHandle)
+// или нет элемента с таким смещением (xaml_lossless_*_attribute)
+pub const XAML_ERROR_OUT_OF_RANGE: i32 = -7;
+// Хост собран под другую версию ABI или раскладку структур (xaml_parser_check_layout)
+pub const XAML_ERROR_ABI_MISMATCH: i32 = -8;
+// Обработчик parse_xaml_stream вернул ненулевое значение
+pub const XAML_ERROR_CANCELLED: i32 = -9;
+// Документ превысил ограничение XamlParseOptions или глубину вложенности
+// по умолчанию; подробности в XamlParseError с kind LimitExceeded
+pub const XAML_ERROR_LIMIT_EXCEEDED: i32 = -10;
+// Имя атрибута не является QName XML (xaml_lossless_set_attribute)
+pub const XAML_ERROR_INVALID_NAME: i32 = -11;
+// Недопустимое значение аргумента, например неизвестный kind в XamlNode
+// дерева хоста (write_xaml)
+pub const XAML_ERROR_INVALID_ARGUMENT: i32 = -12;
+
+// Не ошибка: xaml_lossless_remove_attribute не нашёл атрибута, документ не изменён
+pub const XAML_ATTRIBUTE_NOT_FOUND: i32 = 1;
+
+// Флаги parse_xaml_with_flags.
+// Все строки результата хранят свою длину перед первым байтом (см. xaml_string_length)
+// и завершаются NUL. С этим флагом строки могут содержать NUL и читаются по длине;
+// без него такая строка приводит к XAML_ERROR_UNREPRESENTABLE_STRING.
+pub const XAML_FLAG_LENGTH_PREFIXED_STRINGS: u32 = 1;
+// Проверять размещение директив x: (x:Class только на корне и т.п.);
+// нарушение даёт XAML_ERROR_PARSE с kind Directive.
+pub const XAML_FLAG_VALIDATE_DIRECTIVES: u32 = 2;
 
 #[repr(C)]
 pub struct XamlAttribute {
     key: *mut c_char,
     value: *mut c_char,
+    namespace: *mut c_char,
+    prefix: *mut c_char,
+    span: TextSpan,
+    value_span: TextSpan,
+    kind: i32,
+    owner_type: *mut c_char,
+    member: *mut c_char,
+}
+
+// Дочерний узел в порядке документа. Для элемента поле element указывает
+// на тот же объект, что и массив children, и отдельно не освобождается.
+#[repr(C)]
+pub struct XamlNode {
+    kind: i32,
+    element: *mut XamlElement,
+    text: *mut c_char,
+    target: *mut c_char,
+}
+
+// Директивы языка XAML элемента (x:Class, x:Name, ...); строки равны null,
+// если директивы нет. Они же остаются в attributes с kind
+// XAML_ATTRIBUTE_KIND_DIRECTIVE. shared: -1 — x:Shared нет, 0 — false, 1 — true.
+#[repr(C)]
+pub struct XamlDirectives {
+    class: *mut c_char,
+    subclass: *mut c_char,
+    name: *mut c_char,
+    key: *mut c_char,
+    uid: *mut c_char,
+    field_modifier: *mut c_char,
+    type_arguments: *mut c_char,
+    shared: i32,
 }
 
 #[repr(C)]
@@ -17,70 +106,1214 @@ pub struct XamlElement {
     children: *mut *mut XamlElement,
     children_len: usize,
     text_content: *mut c_char,
+    prefix: *mut c_char,
+    nodes: *mut XamlNode,
+    nodes_len: usize,
+    span: TextSpan,
+    start_tag_span: TextSpan,
+    properties: *mut XamlPropertyElement,
+    properties_len: usize,
+    namespaces: *mut XamlNamespace,
+    namespaces_len: usize,
+    directives: XamlDirectives,
+}
+
+// Объявление xmlns на элементе; prefix равен null для пространства имён по умолчанию.
+// clr_namespace и assembly разобраны из URI вида clr-namespace:Foo;assembly=Bar
+// или using:Foo и равны null для остальных URI; assembly равен null и тогда,
+// когда сборка не указана.
+#[repr(C)]
+pub struct XamlNamespace {
+    prefix: *mut c_char,
+    uri: *mut c_char,
+    clr_namespace: *mut c_char,
+    assembly: *mut c_char,
+}
+
+// Параметры parse_xaml_with_options. size — sizeof(XamlParseOptions) у хоста:
+// поля за его пределами берутся по умолчанию, поэтому новые поля в конце
+// не ломают хосты, собранные раньше. Нулевое поле — значение по умолчанию:
+// для max_depth это 1024, для остальных ограничений — их отсутствие.
+// Функции разбора без XamlParseOptions ограничивают глубину только стеком
+// разбора: около 130 тысяч уровней.
+// Превышение ограничения даёт XAML_ERROR_LIMIT_EXCEEDED.
+#[repr(C)]
+pub struct XamlParseOptions {
+    size: usize,
+    // Флаги XAML_FLAG_*
+    flags: u32,
+    // Наибольшая глубина вложенности элементов
+    max_depth: usize,
+    // Наибольший размер текста в байтах UTF-8
+    max_input_size: usize,
+    // Наибольшее число узлов: элементов, текста, комментариев, инструкций
+    max_nodes: u32,
+    // Наибольшее число атрибутов одного элемента, включая xmlns
+    max_attributes: usize,
+    // Наибольшая длина значения атрибута в байтах после раскрытия сущностей
+    max_attribute_value_len: usize,
+    // На сколько байт текст и значения атрибутов после раскрытия сущностей
+    // могут в сумме превысить размер входа
+    max_entity_expansion: usize,
+    // Разрешить DTD с объявлениями сущностей; иначе DOCTYPE — ошибка разбора
+    allow_dtd: bool,
+    // Обработка Markup Compatibility: mc:Ignorable, mc:AlternateContent,
+    // mc:ProcessContent. Нарушение правил даёт ошибку XAML_ERROR_PARSE
+    // с kind MarkupCompatibility
+    markup_compatibility: bool,
+    // Понятные пространства имён для markup_compatibility: массив URI
+    // в UTF-8 длиной understood_namespaces_len; может быть null при нулевой длине
+    understood_namespaces: *const *const c_char,
+    understood_namespaces_len: usize,
+}
+
+// Элемент свойства (Grid.RowDefinitions): тип-владелец, имя члена и сам элемент.
+// Место в документе: перед nodes[node_index] и перед children[child_index]
+// владельца; индекс, равный длине массива, — после всего содержимого.
+#[repr(C)]
+pub struct XamlPropertyElement {
+    owner_type: *mut c_char,
+    member: *mut c_char,
+    element: *mut XamlElement,
+    node_index: usize,
+    child_index: usize,
+}
+
+#[repr(C)]
+pub struct XamlParseError {
+    kind: i32,
+    line: u32,
+    column: u32,
+    offset: usize,
+    message: *mut c_char,
+    // Путь к файлу для ошибок parse_xaml_file, иначе null
+    file_path: *mut c_char,
+}
+
+// Значение расширения разметки: либо text, либо extension
+#[repr(C)]
+pub struct XamlMarkupValue {
+    text: *mut c_char,
+    extension: *mut XamlMarkupExtension,
+}
+
+// Аргумент расширения разметки; name равен null для позиционных аргументов
+#[repr(C)]
+pub struct XamlMarkupArgument {
+    name: *mut c_char,
+    value: XamlMarkupValue,
+}
+
+#[repr(C)]
+pub struct XamlMarkupExtension {
+    prefix: *mut c_char,
+    name: *mut c_char,
+    arguments: *mut XamlMarkupArgument,
+    arguments_len: usize,
+}
+
+// Параметры write_xaml; indent равен null — узлы пишутся как есть
+#[repr(C)]
+pub struct XamlWriteOptions {
+    indent: *const c_char,
+    self_close_empty: bool,
+    xml_declaration: bool,
+}
+
+// Результат разбора одного файла пакета: element при code == 0, иначе error.
+// encoding равен -1, если файл не удалось прочитать или декодировать.
+#[repr(C)]
+pub struct XamlBatchResult {
+    path: *mut c_char,
+    code: i32,
+    encoding: i32,
+    element: *mut XamlElement,
+    error: *mut XamlParseError,
+}
+
+// Результат создаётся в рабочем потоке и целиком передаётся вызывающему;
+// его указатели больше нигде не используются
+unsafe impl Send for XamlBatchResult {}
+
+// Непрозрачный дескриптор документа в режиме без потерь
+pub struct XamlLosslessDocument(CstDocument);
+
+// Паника не должна пересекать границу extern "C": она превращается в код ошибки
+fn guard(f: impl FnOnce() -> i32) -> i32 {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(XAML_ERROR_PANIC)
 }
 
 // NativeXamlParser::ParseXaml - парсинг XAML документа
 #[unsafe(no_mangle)]
 pub extern "C" fn parse_xaml(xml: *const c_char, result: *mut *mut XamlElement) -> i32 {
+    guard(|| parse_c_str(xml, 0, result, std::ptr::null_mut()))
+}
+
+// Парсинг с подробной информацией об ошибке; error может быть null
+#[unsafe(no_mangle)]
+pub extern "C" fn parse_xaml_ex(
+    xml: *const c_char,
+    result: *mut *mut XamlElement,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    guard(|| parse_c_str(xml, 0, result, error))
+}
+
+// Парсинг с флагами XAML_FLAG_*; error может быть null
+#[unsafe(no_mangle)]
+pub extern "C" fn parse_xaml_with_flags(
+    xml: *const c_char,
+    flags: u32,
+    result: *mut *mut XamlElement,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    guard(|| parse_c_str(xml, flags, result, error))
+}
+
+fn parse_c_str(
+    xml: *const c_char,
+    flags: u32,
+    result: *mut *mut XamlElement,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    clear_error(error);
     if xml.is_null() || result.is_null() {
-        return -1;
+        return XAML_ERROR_NULL_ARGUMENT;
+    }
+
+    match unsafe { CStr::from_ptr(xml) }.to_str() {
+        Ok(xml_str) => parse_str(xml_str, flags, result, error),
+        Err(_) => XAML_ERROR_INVALID_UTF8,
+    }
+}
+
+// Парсинг UTF-8 текста длиной len байт; NUL в конце не нужен
+#[unsafe(no_mangle)]
+pub extern "C" fn parse_xaml_utf8(
+    xml: *const u8,
+    len: usize,
+    flags: u32,
+    result: *mut *mut XamlElement,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    guard(|| {
+        clear_error(error);
+        if xml.is_null() || result.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+
+        match std::str::from_utf8(unsafe { std::slice::from_raw_parts(xml, len) }) {
+            Ok(xml_str) => parse_str(xml_str, flags, result, error),
+            Err(_) => XAML_ERROR_INVALID_UTF8,
+        }
+    })
+}
+
+// Парсинг UTF-16 текста длиной len кодовых единиц (строки .NET без перекодирования
+// на стороне хоста). Дерево то же, что и для UTF-8: смещения в span — байтовые
+// смещения в UTF-8 представлении текста.
+#[unsafe(no_mangle)]
+pub extern "C" fn parse_xaml_utf16(
+    xml: *const u16,
+    len: usize,
+    flags: u32,
+    result: *mut *mut XamlElement,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    guard(|| {
+        clear_error(error);
+        if xml.is_null() || result.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+
+        match String::from_utf16(unsafe { std::slice::from_raw_parts(xml, len) }) {
+            Ok(xml_str) => parse_str(&xml_str, flags, result, error),
+            Err(_) => XAML_ERROR_INVALID_UTF8,
+        }
+    })
+}
+
+// Парсинг UTF-8 текста длиной len байт с параметрами; options может быть null
+#[unsafe(no_mangle)]
+pub extern "C" fn parse_xaml_with_options(
+    xml: *const u8,
+    len: usize,
+    options: *const XamlParseOptions,
+    result: *mut *mut XamlElement,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    guard(|| {
+        clear_error(error);
+        if xml.is_null() || result.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+
+        let (flags, options) = match unsafe { read_parse_options(options) } {
+            Ok(options) => options,
+            Err(code) => return code,
+        };
+        match std::str::from_utf8(unsafe { std::slice::from_raw_parts(xml, len) }) {
+            Ok(xml_str) => parse_str_with_options(xml_str, flags, &options, result, error),
+            Err(_) => XAML_ERROR_INVALID_UTF8,
+        }
+    })
+}
+
+// Копирует из хоста не больше size байт, остальное остаётся нулями.
+// Ошибка — код для null или не UTF-8 строки в understood_namespaces.
+unsafe fn read_parse_options(options: *const XamlParseOptions) -> Result<(u32, ParseOptions), i32> {
+    let mut value: XamlParseOptions = unsafe { std::mem::zeroed() };
+    if !options.is_null() {
+        let size = unsafe { (*options).size }.min(size_of::<XamlParseOptions>());
+        unsafe { std::ptr::copy_nonoverlapping(options as *const u8, &mut value as *mut _ as *mut u8, size) };
     }
 
-    let xml_str = match unsafe { CStr::from_ptr(xml) }.to_str() {
-        Ok(s) => s,
-        Err(_) => return -2,
+    let limit = |n: usize| (n != 0).then_some(n);
+    let parse_options = ParseOptions {
+        max_depth: limit(value.max_depth).unwrap_or(ParseOptions::DEFAULT_MAX_DEPTH),
+        max_input_size: limit(value.max_input_size),
+        max_nodes: (value.max_nodes != 0).then_some(value.max_nodes),
+        max_attributes: limit(value.max_attributes),
+        max_attribute_value_len: limit(value.max_attribute_value_len),
+        max_entity_expansion: limit(value.max_entity_expansion),
+        allow_dtd: value.allow_dtd,
+        understood_namespaces: match value.markup_compatibility {
+            true => Some(unsafe { read_strings(value.understood_namespaces, value.understood_namespaces_len) }?),
+            false => None,
+        },
+        validate_directives: value.flags & XAML_FLAG_VALIDATE_DIRECTIVES != 0,
     };
+    Ok((value.flags, parse_options))
+}
+
+unsafe fn read_strings(strings: *const *const c_char, len: usize) -> Result<Vec<String>, i32> {
+    if len == 0 {
+        return Ok(Vec::new());
+    }
+    if strings.is_null() {
+        return Err(XAML_ERROR_NULL_ARGUMENT);
+    }
+    unsafe { std::slice::from_raw_parts(strings, len) }
+        .iter()
+        .map(|&s| match s.is_null() {
+            true => Err(XAML_ERROR_NULL_ARGUMENT),
+            false => unsafe { CStr::from_ptr(s) }.to_str().map(str::to_string).map_err(|_| XAML_ERROR_INVALID_UTF8),
+        })
+        .collect()
+}
+
+// Парсинг байтов файла с определением кодировки по BOM и XML-объявлению.
+// encoding может быть null; при успешном декодировании в него пишется
+// значение Encoding (0 — UTF-8, 1 — UTF-16LE, ...), даже если разбор затем не удался.
+#[unsafe(no_mangle)]
+pub extern "C" fn parse_xaml_bytes(
+    bytes: *const u8,
+    len: usize,
+    flags: u32,
+    result: *mut *mut XamlElement,
+    encoding: *mut i32,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    guard(|| {
+        clear_error(error);
+        if bytes.is_null() || result.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+
+        match encoding::decode(unsafe { std::slice::from_raw_parts(bytes, len) }) {
+            Ok((text, used)) => {
+                if !encoding.is_null() {
+                    unsafe { *encoding = used as i32 };
+                }
+                parse_str(&text, flags, result, error)
+            }
+            Err(e) => {
+                set_error(error, &e);
+                XAML_ERROR_PARSE
+            }
+        }
+    })
+}
+
+// Чтение и парсинг файла без передачи текста через хост. path — путь в UTF-8;
+// ошибки чтения и разбора содержат путь в file_path. encoding как в parse_xaml_bytes.
+#[unsafe(no_mangle)]
+pub extern "C" fn parse_xaml_file(
+    path: *const c_char,
+    flags: u32,
+    result: *mut *mut XamlElement,
+    encoding: *mut i32,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    guard(|| {
+        clear_error(error);
+        if path.is_null() || result.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+
+        match unsafe { CStr::from_ptr(path) }.to_str() {
+            Ok(path) => parse_file_into(Path::new(path), flags, result, encoding, error),
+            Err(_) => XAML_ERROR_INVALID_UTF8,
+        }
+    })
+}
+
+fn parse_file_into(
+    path: &Path,
+    flags: u32,
+    result: *mut *mut XamlElement,
+    encoding: *mut i32,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    match XamlDocument::parse_file_with_options(path, &flag_options(flags)) {
+        Ok((doc, used)) => {
+            if !encoding.is_null() {
+                unsafe { *encoding = used as i32 };
+            }
+            export_element(&doc.root, flags, result)
+        }
+        Err(e) => {
+            set_error(error, &e);
+            error_code(&e)
+        }
+    }
+}
+
+// Пакетный разбор списка файлов на пуле потоков. threads равен 0 — по числу ядер.
+// Результаты идут в порядке paths; ошибка одного файла не прерывает остальные.
+// Массив освобождается через free_xaml_batch.
+#[unsafe(no_mangle)]
+pub extern "C" fn parse_xaml_files(
+    paths: *const *const c_char,
+    paths_len: usize,
+    flags: u32,
+    threads: usize,
+    results: *mut *mut XamlBatchResult,
+    results_len: *mut usize,
+) -> i32 {
+    guard(|| {
+        if paths.is_null() || results.is_null() || results_len.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+
+        let mut files = Vec::with_capacity(paths_len);
+        for &path in unsafe { read_slice(paths, paths_len) } {
+            match unsafe { read_c_str(path) } {
+                Ok(Some(path)) => files.push(PathBuf::from(path)),
+                Ok(None) => return XAML_ERROR_NULL_ARGUMENT,
+                Err(code) => return code,
+            }
+        }
+        export_batch(&files, flags, threads, results, results_len)
+    })
+}
+
+// Пакетный разбор файлов каталога по шаблону имени с * и ? (null — "*.xaml").
+// XAML_ERROR_IO, если каталог не удалось прочитать.
+#[unsafe(no_mangle)]
+pub extern "C" fn parse_xaml_directory(
+    dir: *const c_char,
+    pattern: *const c_char,
+    recursive: bool,
+    flags: u32,
+    threads: usize,
+    results: *mut *mut XamlBatchResult,
+    results_len: *mut usize,
+) -> i32 {
+    guard(|| {
+        if dir.is_null() || results.is_null() || results_len.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+
+        let (dir, pattern) = match unsafe { (read_c_str(dir), read_c_str(pattern)) } {
+            (Ok(Some(dir)), Ok(pattern)) => (dir, pattern.unwrap_or_else(|| "*.xaml".to_string())),
+            _ => return XAML_ERROR_INVALID_UTF8,
+        };
+        match batch::find_files(dir, &pattern, recursive) {
+            Ok(files) => export_batch(&files, flags, threads, results, results_len),
+            Err(_) => XAML_ERROR_IO,
+        }
+    })
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn free_xaml_batch(results: *mut XamlBatchResult, results_len: usize) -> i32 {
+    guard(|| {
+        if results.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+
+        let results = unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(results, results_len)) };
+        for item in results.into_vec() {
+            unsafe { free_string(item.path) };
+            if !item.element.is_null() {
+                free_xaml_element(item.element);
+            }
+            if !item.error.is_null() {
+                free_xaml_parse_error(item.error);
+            }
+        }
+        XAML_OK
+    })
+}
+
+// Каждый файл разбирается и преобразуется в FFI-дерево в рабочем потоке
+fn export_batch(
+    files: &[PathBuf],
+    flags: u32,
+    threads: usize,
+    results: *mut *mut XamlBatchResult,
+    results_len: *mut usize,
+) -> i32 {
+    let items = batch::run(files.len(), threads, |i| {
+        let mut element = std::ptr::null_mut();
+        let mut encoding = -1;
+        let mut error = std::ptr::null_mut();
+        let code = guard(|| parse_file_into(&files[i], flags, &mut element, &mut encoding, &mut error));
+        XamlBatchResult {
+            path: strings::alloc(&files[i].to_string_lossy()),
+            code,
+            encoding,
+            element,
+            error,
+        }
+    });
+
+    unsafe {
+        *results_len = items.len();
+        *results = if items.is_empty() {
+            std::ptr::null_mut()
+        } else {
+            Box::into_raw(items.into_boxed_slice()) as *mut XamlBatchResult
+        };
+    }
+    XAML_OK
+}
 
-    let doc = match Document::parse(xml_str) {
-        Ok(d) => d,
-        Err(_) => return -3,
+fn parse_str(
+    xml_str: &str,
+    flags: u32,
+    result: *mut *mut XamlElement,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    parse_str_with_options(xml_str, flags, &flag_options(flags), result, error)
+}
+
+/// Параметры разбора для функций без `XamlParseOptions`: из флагов берётся
+/// только проверка директив, остальное — по умолчанию.
+fn flag_options(flags: u32) -> ParseOptions {
+    ParseOptions {
+        validate_directives: flags & XAML_FLAG_VALIDATE_DIRECTIVES != 0,
+        ..ParseOptions::default()
+    }
+}
+
+fn parse_str_with_options(
+    xml_str: &str,
+    flags: u32,
+    options: &ParseOptions,
+    result: *mut *mut XamlElement,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    match XamlDocument::parse_with_options(xml_str, options) {
+        Ok(doc) => export_element(&doc.root, flags, result),
+        Err(e) => {
+            set_error(error, &e);
+            error_code(&e)
+        }
+    }
+}
+
+// Преобразование модели в FFI-дерево. Если строку нельзя отдать без потерь,
+// дерево освобождается целиком и result не заполняется.
+fn export_element(root: &model::XamlElement, flags: u32, result: *mut *mut XamlElement) -> i32 {
+    let mut strings = StringAllocator::new(flags);
+    let element = convert_to_xaml_element(root, &mut strings);
+    if strings.unrepresentable {
+        free_xaml_element_internal(element);
+        return XAML_ERROR_UNREPRESENTABLE_STRING;
+    }
+    unsafe { *result = Box::into_raw(Box::new(element)) };
+    XAML_OK
+}
+
+fn clear_error(error: *mut *mut XamlParseError) {
+    if !error.is_null() {
+        unsafe { *error = std::ptr::null_mut() };
+    }
+}
+
+fn set_error(error: *mut *mut XamlParseError, e: &ParseError) {
+    if !error.is_null() {
+        unsafe { *error = Box::into_raw(Box::new(to_xaml_parse_error(e))) };
+    }
+}
+
+// Код возврата для ошибки разбора документа
+fn error_code(e: &ParseError) -> i32 {
+    match e.kind {
+        ErrorKind::Io | ErrorKind::AccessDenied => XAML_ERROR_IO,
+        ErrorKind::LimitExceeded => XAML_ERROR_LIMIT_EXCEEDED,
+        _ => XAML_ERROR_PARSE,
+    }
+}
+
+// Длина строки, полученной из библиотеки, в байтах без завершающего NUL.
+// Нужна для строк с NUL внутри (XAML_FLAG_LENGTH_PREFIXED_STRINGS).
+#[unsafe(no_mangle)]
+pub extern "C" fn xaml_string_length(s: *const c_char, result: *mut usize) -> i32 {
+    guard(|| {
+        if s.is_null() || result.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+        unsafe { *result = strings::len(s) };
+        XAML_OK
+    })
+}
+
+// Освобождение ошибки, полученной из parse_xaml_ex
+#[unsafe(no_mangle)]
+pub extern "C" fn free_xaml_parse_error(error: *mut XamlParseError) -> i32 {
+    guard(|| {
+        if error.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+
+        unsafe {
+            let error = Box::from_raw(error);
+            free_string(error.message);
+            free_string(error.file_path);
+        }
+        XAML_OK
+    })
+}
+
+// Разбор значения атрибута как расширения разметки ({Binding ...} и т.п.)
+#[unsafe(no_mangle)]
+pub extern "C" fn parse_xaml_markup_extension(
+    text: *const c_char,
+    result: *mut *mut XamlMarkupValue,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    guard(|| {
+        clear_error(error);
+        if text.is_null() || result.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+
+        let text_str = match unsafe { CStr::from_ptr(text) }.to_str() {
+            Ok(s) => s,
+            Err(_) => return XAML_ERROR_INVALID_UTF8,
+        };
+
+        let value = match markup::parse_markup_value(text_str) {
+            Ok(v) => v,
+            Err(e) => {
+                let (line, column) = error::position_of(text_str, e.offset);
+                let parse_error = ParseError {
+                    kind: ErrorKind::MarkupExtension,
+                    line,
+                    column,
+                    offset: e.offset,
+                    message: e.to_string(),
+                    file: None,
+                };
+                set_error(error, &parse_error);
+                return XAML_ERROR_PARSE;
+            }
+        };
+
+        let mut strings = StringAllocator::new(0);
+        let value = convert_markup_value(&value, &mut strings);
+        if strings.unrepresentable {
+            free_markup_value_internal(value);
+            return XAML_ERROR_UNREPRESENTABLE_STRING;
+        }
+        unsafe { *result = Box::into_raw(Box::new(value)) };
+        XAML_OK
+    })
+}
+
+// Освобождение результата parse_xaml_markup_extension
+#[unsafe(no_mangle)]
+pub extern "C" fn free_xaml_markup_value(value: *mut XamlMarkupValue) -> i32 {
+    guard(|| {
+        if value.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+
+        unsafe {
+            free_markup_value_internal(*Box::from_raw(value));
+        }
+        XAML_OK
+    })
+}
+
+fn convert_markup_value(value: &MarkupValue, strings: &mut StringAllocator) -> XamlMarkupValue {
+    match value {
+        MarkupValue::Text(text) => XamlMarkupValue {
+            text: strings.string(text),
+            extension: std::ptr::null_mut(),
+        },
+        MarkupValue::Extension(extension) => XamlMarkupValue {
+            text: std::ptr::null_mut(),
+            extension: Box::into_raw(Box::new(convert_markup_extension(extension, strings))),
+        },
+    }
+}
+
+fn convert_markup_extension(extension: &MarkupExtension, strings: &mut StringAllocator) -> XamlMarkupExtension {
+    let mut arguments = Vec::with_capacity(extension.positional.len() + extension.named.len());
+    for value in &extension.positional {
+        arguments.push(XamlMarkupArgument {
+            name: std::ptr::null_mut(),
+            value: convert_markup_value(value, strings),
+        });
+    }
+    for (name, value) in &extension.named {
+        arguments.push(XamlMarkupArgument {
+            name: strings.string(name),
+            value: convert_markup_value(value, strings),
+        });
+    }
+
+    let arguments_len = arguments.len();
+    let arguments_ptr = if arguments.is_empty() {
+        std::ptr::null_mut()
+    } else {
+        Box::into_raw(arguments.into_boxed_slice()) as *mut XamlMarkupArgument
     };
 
-    let root_element = convert_node_to_xaml_element(doc.root_element());
-    let boxed = Box::new(root_element);
-    unsafe { *result = Box::into_raw(boxed) };
-    0
+    XamlMarkupExtension {
+        prefix: strings.string_or_null(extension.prefix.as_deref()),
+        name: strings.string(&extension.name),
+        arguments: arguments_ptr,
+        arguments_len,
+    }
+}
+
+fn free_markup_value_internal(value: XamlMarkupValue) {
+    unsafe {
+        free_string(value.text);
+        if !value.extension.is_null() {
+            let extension = Box::from_raw(value.extension);
+            free_string(extension.prefix);
+            free_string(extension.name);
+            if !extension.arguments.is_null() {
+                let arguments = Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+                    extension.arguments,
+                    extension.arguments_len
+                ));
+                for argument in arguments.into_vec() {
+                    free_string(argument.name);
+                    free_markup_value_internal(argument.value);
+                }
+            }
+        }
+    }
+}
+
+// Запись дерева XamlElement (в том числе построенного на стороне C#) в XAML.
+// options может быть null; результат освобождается через free_xaml_string
+#[unsafe(no_mangle)]
+pub extern "C" fn write_xaml(
+    element: *const XamlElement,
+    options: *const XamlWriteOptions,
+    result: *mut *mut c_char,
+) -> i32 {
+    guard(|| {
+        if element.is_null() || result.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+
+        let options = if options.is_null() {
+            WriteOptions::default()
+        } else {
+            let options = unsafe { &*options };
+            let indent = match unsafe { read_c_str(options.indent) } {
+                Ok(indent) => indent,
+                Err(code) => return code,
+            };
+            WriteOptions {
+                indent,
+                self_close_empty: options.self_close_empty,
+                xml_declaration: options.xml_declaration,
+            }
+        };
+
+        let root = match unsafe { read_xaml_element(&*element) } {
+            Ok(root) => root,
+            Err(code) => return code,
+        };
+
+        export_string(&writer::write_xaml(&root, &options), result)
+    })
+}
+
+// Освобождение строки, полученной из write_xaml или xaml_lossless_to_string
+#[unsafe(no_mangle)]
+pub extern "C" fn free_xaml_string(s: *mut c_char) -> i32 {
+    guard(|| {
+        if s.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+
+        unsafe { strings::free(s) };
+        XAML_OK
+    })
+}
+
+// Разбор в режиме без потерь для точечных правок с сохранением форматирования
+#[unsafe(no_mangle)]
+pub extern "C" fn parse_xaml_lossless(
+    xml: *const c_char,
+    result: *mut *mut XamlLosslessDocument,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    guard(|| {
+        clear_error(error);
+        if xml.is_null() || result.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+
+        let xml_str = match unsafe { CStr::from_ptr(xml) }.to_str() {
+            Ok(s) => s,
+            Err(_) => return XAML_ERROR_INVALID_UTF8,
+        };
+
+        match CstDocument::parse(xml_str) {
+            Ok(doc) => {
+                unsafe { *result = Box::into_raw(Box::new(XamlLosslessDocument(doc))) };
+                XAML_OK
+            }
+            Err(e) => {
+                set_error(error, &e);
+                error_code(&e)
+            }
+        }
+    })
+}
+
+// Установка атрибута у элемента, заданного смещением его начала (span.start)
+#[unsafe(no_mangle)]
+pub extern "C" fn xaml_lossless_set_attribute(
+    doc: *mut XamlLosslessDocument,
+    element_offset: usize,
+    name: *const c_char,
+    value: *const c_char,
+) -> i32 {
+    guard(|| {
+        if doc.is_null() || name.is_null() || value.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+
+        let (name, value) = match unsafe { (read_c_str(name), read_c_str(value)) } {
+            (Ok(Some(name)), Ok(Some(value))) => (name, value),
+            _ => return XAML_ERROR_INVALID_UTF8,
+        };
+        match unsafe { &mut *doc }.0.element_at_mut(element_offset) {
+            Some(element) => match element.set_attribute(&name, &value) {
+                Ok(()) => XAML_OK,
+                Err(_) => XAML_ERROR_INVALID_NAME,
+            },
+            None => XAML_ERROR_OUT_OF_RANGE,
+        }
+    })
+}
+
+// Удаление атрибута; XAML_ATTRIBUTE_NOT_FOUND, если атрибута не было
+#[unsafe(no_mangle)]
+pub extern "C" fn xaml_lossless_remove_attribute(
+    doc: *mut XamlLosslessDocument,
+    element_offset: usize,
+    name: *const c_char,
+) -> i32 {
+    guard(|| {
+        if doc.is_null() || name.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+
+        let name = match unsafe { read_c_str(name) } {
+            Ok(Some(name)) => name,
+            _ => return XAML_ERROR_INVALID_UTF8,
+        };
+        match unsafe { &mut *doc }.0.element_at_mut(element_offset) {
+            Some(element) => match element.remove_attribute(&name) {
+                true => XAML_OK,
+                false => XAML_ATTRIBUTE_NOT_FOUND,
+            },
+            None => XAML_ERROR_OUT_OF_RANGE,
+        }
+    })
+}
+
+// Текст документа; результат освобождается через free_xaml_string
+#[unsafe(no_mangle)]
+pub extern "C" fn xaml_lossless_to_string(doc: *const XamlLosslessDocument, result: *mut *mut c_char) -> i32 {
+    guard(|| {
+        if doc.is_null() || result.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+
+        export_string(&unsafe { &*doc }.0.to_string(), result)
+    })
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn free_xaml_lossless(doc: *mut XamlLosslessDocument) -> i32 {
+    guard(|| {
+        if doc.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+
+        unsafe {
+            drop(Box::from_raw(doc));
+        }
+        XAML_OK
+    })
+}
+
+fn export_string(text: &str, result: *mut *mut c_char) -> i32 {
+    if text.contains('\0') {
+        return XAML_ERROR_UNREPRESENTABLE_STRING;
+    }
+    unsafe { *result = strings::alloc(text) };
+    XAML_OK
+}
+
+unsafe fn read_c_str(ptr: *const c_char) -> Result<Option<String>, i32> {
+    if ptr.is_null() {
+        return Ok(None);
+    }
+    match unsafe { CStr::from_ptr(ptr) }.to_str() {
+        Ok(s) => Ok(Some(s.to_string())),
+        Err(_) => Err(XAML_ERROR_INVALID_UTF8),
+    }
+}
+
+unsafe fn read_slice<'a, T>(ptr: *const T, len: usize) -> &'a [T] {
+    if ptr.is_null() || len == 0 {
+        &[]
+    } else {
+        unsafe { std::slice::from_raw_parts(ptr, len) }
+    }
+}
+
+// Обратное преобразование FFI-дерева в модель. Если nodes не заполнен,
+// содержимое берётся из text_content и children. Обход идёт со своим стеком,
+// поэтому глубина дерева хоста не ограничена стеком вызовов.
+unsafe fn read_xaml_element(root: &XamlElement) -> Result<model::XamlElement, i32> {
+    let (element, items) = unsafe { read_element_shell(root) }?;
+    let mut stack = vec![(element, items.into_iter(), Attach::Child)];
+    loop {
+        let (element, items, _) = stack.last_mut().expect("stack holds the root");
+        let (child, attach) = match items.next() {
+            Some(Item::Node(node)) => {
+                element.children.push(node);
+                continue;
+            }
+            Some(Item::Element(child, attach)) => (child, attach),
+            None => {
+                let (element, _, attach) = stack.pop().expect("stack holds the root");
+                let Some((parent, _, _)) = stack.last_mut() else {
+                    return Ok(element);
+                };
+                match attach {
+                    Attach::Child => parent.children.push(model::XamlNode::Element(element)),
+                    Attach::Property { owner_type, member, index } => {
+                        let property = model::XamlPropertyElement { owner_type, member, element, index };
+                        parent.properties.push(property)
+                    }
+                }
+                continue;
+            }
+        };
+        let (element, items) = unsafe { read_element_shell(child) }?;
+        stack.push((element, items.into_iter(), attach));
+    }
+}
+
+// Содержимое FFI-элемента в порядке модели; дочерние элементы читаются позже
+enum Item<'a> {
+    Node(model::XamlNode),
+    Element(&'a XamlElement, Attach),
+}
+
+enum Attach {
+    Child,
+    Property { owner_type: String, member: String, index: usize },
+}
+
+// Элемент без дочерних элементов и элементов свойств и список его содержимого
+unsafe fn read_element_shell(element: &XamlElement) -> Result<(model::XamlElement, Vec<Item<'_>>), i32> {
+    let name = unsafe { read_c_str(element.name) }?.ok_or(XAML_ERROR_NULL_ARGUMENT)?;
+
+    let mut attributes = Vec::new();
+    for attr in unsafe { read_slice(element.attributes, element.attributes_len) } {
+        let kind = match attr.kind {
+            k if k == AttributeKind::AttachedProperty as i32 => AttributeKind::AttachedProperty,
+            k if k == AttributeKind::Directive as i32 => AttributeKind::Directive,
+            _ => AttributeKind::Property,
+        };
+        attributes.push(model::XamlAttribute {
+            name: unsafe { read_c_str(attr.key) }?.ok_or(XAML_ERROR_NULL_ARGUMENT)?,
+            value: unsafe { read_c_str(attr.value) }?.unwrap_or_default(),
+            namespace: unsafe { read_c_str(attr.namespace) }?,
+            prefix: unsafe { read_c_str(attr.prefix) }?,
+            kind,
+            owner_type: unsafe { read_c_str(attr.owner_type) }?,
+            span: attr.span,
+            value_span: attr.value_span,
+        });
+    }
+
+    let mut namespaces = Vec::new();
+    for namespace in unsafe { read_slice(element.namespaces, element.namespaces_len) } {
+        namespaces.push(crate::namespace::XamlNamespace {
+            prefix: unsafe { read_c_str(namespace.prefix) }?,
+            uri: unsafe { read_c_str(namespace.uri) }?.unwrap_or_default(),
+        });
+    }
+
+    let mut items = Vec::new();
+    let nodes = unsafe { read_slice(element.nodes, element.nodes_len) };
+    // Без nodes содержимое модели — text_content и за ним children
+    let mut leading_text = 0;
+    if nodes.is_empty() {
+        if let Some(text) = unsafe { read_c_str(element.text_content) }? {
+            items.push(Item::Node(model::XamlNode::Text(text)));
+            leading_text = 1;
+        }
+        for &child in unsafe { read_slice(element.children, element.children_len) } {
+            if !child.is_null() {
+                items.push(Item::Element(unsafe { &*child }, Attach::Child));
+            }
+        }
+    }
+    for node in nodes {
+        let text = || unsafe { read_c_str(node.text) }.map(Option::unwrap_or_default);
+        let child = match node.kind {
+            k if k == NodeKind::Element as i32 => {
+                if node.element.is_null() {
+                    return Err(XAML_ERROR_NULL_ARGUMENT);
+                }
+                items.push(Item::Element(unsafe { &*node.element }, Attach::Child));
+                continue;
+            }
+            k if k == NodeKind::Text as i32 => model::XamlNode::Text(text()?),
+            k if k == NodeKind::CData as i32 => model::XamlNode::CData(text()?),
+            k if k == NodeKind::Comment as i32 => model::XamlNode::Comment(text()?),
+            k if k == NodeKind::ProcessingInstruction as i32 => model::XamlNode::ProcessingInstruction {
+                target: unsafe { read_c_str(node.target) }?.ok_or(XAML_ERROR_NULL_ARGUMENT)?,
+                value: unsafe { read_c_str(node.text) }?,
+            },
+            _ => return Err(XAML_ERROR_INVALID_ARGUMENT),
+        };
+        items.push(Item::Node(child));
+    }
+
+    for property in unsafe { read_slice(element.properties, element.properties_len) } {
+        if property.element.is_null() {
+            return Err(XAML_ERROR_NULL_ARGUMENT);
+        }
+        let attach = Attach::Property {
+            owner_type: unsafe { read_c_str(property.owner_type) }?.unwrap_or_default(),
+            member: unsafe { read_c_str(property.member) }?.unwrap_or_default(),
+            index: match nodes.is_empty() {
+                true => property.child_index + leading_text,
+                false => property.node_index,
+            },
+        };
+        items.push(Item::Element(unsafe { &*property.element }, attach));
+    }
+
+    // Директивы заново выводятся из атрибутов, поле directives не читается
+    let element = model::XamlElement {
+        name,
+        namespace: unsafe { read_c_str(element.namespace) }?,
+        prefix: unsafe { read_c_str(element.prefix) }?,
+        namespaces,
+        directives: crate::directive::XamlDirectives::boxed(&attributes),
+        attributes,
+        children: Vec::new(),
+        properties: Vec::new(),
+        span: element.span,
+        start_tag_span: element.start_tag_span,
+    };
+    Ok((element, items))
+}
+
+// Сообщение об ошибке отдаётся всегда; NUL в нём (например, из текста документа)
+// лишь обрезает его при чтении как C-строки
+fn to_xaml_parse_error(error: &ParseError) -> XamlParseError {
+    XamlParseError {
+        kind: error.kind as i32,
+        line: error.line,
+        column: error.column,
+        offset: error.offset,
+        message: strings::alloc(&error.message),
+        file_path: match &error.file {
+            Some(path) => strings::alloc(&path.to_string_lossy()),
+            None => std::ptr::null_mut(),
+        },
+    }
 }
 
 // NativeXamlParser::FreeXamlElement - освобождение памяти
 #[unsafe(no_mangle)]
 pub extern "C" fn free_xaml_element(element: *mut XamlElement) -> i32 {
-    if element.is_null() {
-        return -1;
+    guard(|| {
+        if element.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+
+        unsafe {
+            free_xaml_element_internal(*Box::from_raw(element));
+        }
+        XAML_OK
+    })
+}
+
+// Выделение строк результата. Строка с NUL внутри выделяется как обычно,
+// чтобы дерево освобождалось единообразно, но без флага
+// XAML_FLAG_LENGTH_PREFIXED_STRINGS отмечает результат как непредставимый.
+struct StringAllocator {
+    allow_nul: bool,
+    unrepresentable: bool,
+}
+
+impl StringAllocator {
+    fn new(flags: u32) -> Self {
+        StringAllocator {
+            allow_nul: flags & XAML_FLAG_LENGTH_PREFIXED_STRINGS != 0,
+            unrepresentable: false,
+        }
     }
 
-    unsafe {
-        free_xaml_element_internal(Box::from_raw(element));
+    fn string(&mut self, s: &str) -> *mut c_char {
+        if !self.allow_nul && s.contains('\0') {
+            self.unrepresentable = true;
+        }
+        strings::alloc(s)
+    }
+
+    fn string_or_null(&mut self, s: Option<&str>) -> *mut c_char {
+        match s {
+            Some(s) => self.string(s),
+            None => std::ptr::null_mut(),
+        }
     }
-    0
 }
 
-fn convert_node_to_xaml_element(node: Node) -> XamlElement {
-    let name = CString::new(node.tag_name().name()).unwrap().into_raw();
-    let namespace = if let Some(ns) = node.tag_name().namespace() {
-        CString::new(ns).unwrap().into_raw()
-    } else {
-        std::ptr::null_mut()
+// Число дочерних элементов в children[..i] для каждого i от 0 до длины children:
+// по нему позиция элемента свойства переводится в индекс массива children FFI
+fn elements_before(element: &model::XamlElement) -> Vec<usize> {
+    let mut before = Vec::with_capacity(element.children.len() + 1);
+    before.push(0);
+    for child in &element.children {
+        before.push(before[before.len() - 1] + matches!(child, model::XamlNode::Element(_)) as usize);
+    }
+    before
+}
+
+// Вложенные элементы выделяются пустыми и заполняются из явного стека,
+// поэтому глубина дерева не ограничена стеком вызовов
+fn convert_to_xaml_element(element: &model::XamlElement, strings: &mut StringAllocator) -> XamlElement {
+    let mut pending = Vec::new();
+    let root = convert_element(element, strings, &mut pending);
+    while let Some((element, slot)) = pending.pop() {
+        let value = convert_element(element, strings, &mut pending);
+        unsafe { slot.write(value) };
+    }
+    root
+}
+
+// Элемент без вложенных: их ещё не заполненные места добавляются в pending
+fn convert_element<'a>(
+    element: &'a model::XamlElement,
+    strings: &mut StringAllocator,
+    pending: &mut Vec<(&'a model::XamlElement, *mut XamlElement)>,
+) -> XamlElement {
+    let mut slot = |element: &'a model::XamlElement| {
+        let slot: *mut XamlElement = Box::into_raw(Box::new(unsafe { std::mem::zeroed() }));
+        pending.push((element, slot));
+        slot
     };
 
-    let attrs: Vec<XamlAttribute> = node.attributes().map(|attr| {
+    let name = strings.string(&element.name);
+    let namespace = strings.string_or_null(element.namespace.as_deref());
+    let prefix = strings.string_or_null(element.prefix.as_deref());
+
+    let attrs: Vec<XamlAttribute> = element.attributes.iter().map(|attr| {
         XamlAttribute {
-            key: CString::new(attr.name()).unwrap().into_raw(),
-            value: CString::new(attr.value()).unwrap().into_raw(),
+            key: strings.string(&attr.name),
+            value: strings.string(&attr.value),
+            namespace: strings.string_or_null(attr.namespace.as_deref()),
+            prefix: strings.string_or_null(attr.prefix.as_deref()),
+            span: attr.span,
+            value_span: attr.value_span,
+            kind: attr.kind as i32,
+            owner_type: strings.string_or_null(attr.owner_type.as_deref()),
+            member: strings.string(attr.member()),
         }
     }).collect();
 
-    let children: Vec<*mut XamlElement> = node.children()
-        .filter(|n| n.is_element())
-        .map(|child| Box::into_raw(Box::new(convert_node_to_xaml_element(child))))
-        .collect();
+    let mut children: Vec<*mut XamlElement> = Vec::new();
+    let mut nodes: Vec<XamlNode> = Vec::new();
+    for child in &element.children {
+        let node = match child {
+            model::XamlNode::Element(child) => {
+                let element = slot(child);
+                children.push(element);
+                XamlNode::new(NodeKind::Element, element, None, None, strings)
+            }
+            model::XamlNode::Text(s) => XamlNode::new(NodeKind::Text, std::ptr::null_mut(), Some(s), None, strings),
+            model::XamlNode::CData(s) => XamlNode::new(NodeKind::CData, std::ptr::null_mut(), Some(s), None, strings),
+            model::XamlNode::Comment(s) => XamlNode::new(NodeKind::Comment, std::ptr::null_mut(), Some(s), None, strings),
+            model::XamlNode::ProcessingInstruction { target, value } => XamlNode::new(
+                NodeKind::ProcessingInstruction,
+                std::ptr::null_mut(),
+                value.as_deref(),
+                Some(target),
+                strings,
+            ),
+        };
+        nodes.push(node);
+    }
+
+    let text_content = strings.string_or_null(element.leading_text().as_deref());
 
-    let text_content = if let Some(text) = node.text() {
-        CString::new(text).unwrap().into_raw()
+    let before = elements_before(element);
+    let properties: Vec<XamlPropertyElement> = element.properties.iter().map(|property| {
+        let node_index = property.index.min(element.children.len());
+        XamlPropertyElement {
+            owner_type: strings.string(&property.owner_type),
+            member: strings.string(&property.member),
+            element: slot(&property.element),
+            node_index,
+            child_index: before[node_index],
+        }
+    }).collect();
+    let properties_len = properties.len();
+    let properties_ptr = if properties.is_empty() {
+        std::ptr::null_mut()
     } else {
+        Box::into_raw(properties.into_boxed_slice()) as *mut XamlPropertyElement
+    };
+
+    let namespaces: Vec<XamlNamespace> = element
+        .namespaces
+        .iter()
+        .map(|namespace| XamlNamespace::new(namespace, strings))
+        .collect();
+    let namespaces_len = namespaces.len();
+    let namespaces_ptr = if namespaces.is_empty() {
         std::ptr::null_mut()
+    } else {
+        Box::into_raw(namespaces.into_boxed_slice()) as *mut XamlNamespace
     };
 
+    let attributes_len = attrs.len();
     let attributes_ptr = if attrs.is_empty() {
         std::ptr::null_mut()
     } else {
@@ -88,6 +1321,7 @@ fn convert_node_to_xaml_element(node: Node) -> XamlElement {
         Box::into_raw(boxed_attrs) as *mut XamlAttribute
     };
 
+    let children_len = children.len();
     let children_ptr = if children.is_empty() {
         std::ptr::null_mut()
     } else {
@@ -95,54 +1329,1007 @@ fn convert_node_to_xaml_element(node: Node) -> XamlElement {
         Box::into_raw(boxed_children) as *mut *mut XamlElement
     };
 
+    let nodes_len = nodes.len();
+    let nodes_ptr = if nodes.is_empty() {
+        std::ptr::null_mut()
+    } else {
+        Box::into_raw(nodes.into_boxed_slice()) as *mut XamlNode
+    };
+
     XamlElement {
         name,
         namespace,
         attributes: attributes_ptr,
-        attributes_len: node.attributes().count(),
+        attributes_len,
         children: children_ptr,
-        children_len: node.children().filter(|n| n.is_element()).count(),
+        children_len,
         text_content,
+        prefix,
+        nodes: nodes_ptr,
+        nodes_len,
+        span: element.span,
+        start_tag_span: element.start_tag_span,
+        properties: properties_ptr,
+        properties_len,
+        namespaces: namespaces_ptr,
+        namespaces_len,
+        directives: XamlDirectives::new(element.directives(), |s| strings.string_or_null(s)),
     }
 }
 
-fn free_xaml_element_internal(element: Box<XamlElement>) {
-    unsafe {
-        if !element.name.is_null() {
-            let _ = CString::from_raw(element.name);
+impl XamlNode {
+    fn new(
+        kind: NodeKind,
+        element: *mut XamlElement,
+        text: Option<&str>,
+        target: Option<&str>,
+        strings: &mut StringAllocator,
+    ) -> Self {
+        XamlNode {
+            kind: kind as i32,
+            element,
+            text: strings.string_or_null(text),
+            target: strings.string_or_null(target),
         }
-        if !element.namespace.is_null() {
-            let _ = CString::from_raw(element.namespace);
+    }
+}
+
+impl XamlDirectives {
+    // string выделяет строку в куче или в арене
+    fn new(
+        directives: &crate::directive::XamlDirectives,
+        mut string: impl FnMut(Option<&str>) -> *mut c_char,
+    ) -> Self {
+        XamlDirectives {
+            class: string(directives.class.as_deref()),
+            subclass: string(directives.subclass.as_deref()),
+            name: string(directives.name.as_deref()),
+            key: string(directives.key.as_deref()),
+            uid: string(directives.uid.as_deref()),
+            field_modifier: string(directives.field_modifier.as_deref()),
+            type_arguments: string(directives.type_arguments.as_deref()),
+            shared: directives.shared.map_or(-1, i32::from),
         }
-        if !element.text_content.is_null() {
-            let _ = CString::from_raw(element.text_content);
+    }
+
+    fn strings(&self) -> [*mut c_char; 7] {
+        [self.class, self.subclass, self.name, self.key, self.uid, self.field_modifier, self.type_arguments]
+    }
+}
+
+impl XamlNamespace {
+    fn new(namespace: &crate::namespace::XamlNamespace, strings: &mut StringAllocator) -> Self {
+        let clr = namespace.clr();
+        XamlNamespace {
+            prefix: strings.string_or_null(namespace.prefix.as_deref()),
+            uri: strings.string(&namespace.uri),
+            clr_namespace: strings.string_or_null(clr.as_ref().map(|c| c.namespace.as_str())),
+            assembly: strings.string_or_null(clr.as_ref().and_then(|c| c.assembly.as_deref())),
+        }
+    }
+}
+
+unsafe fn free_string(s: *mut c_char) {
+    if !s.is_null() {
+        unsafe { strings::free(s) };
+    }
+}
+
+// Освобождение без рекурсии: вложенные элементы собираются в явный стек
+fn free_xaml_element_internal(element: XamlElement) {
+    let mut pending = Vec::new();
+    free_element_fields(element, &mut pending);
+    while let Some(element) = pending.pop() {
+        free_element_fields(unsafe { *Box::from_raw(element) }, &mut pending);
+    }
+}
+
+// Строки и массивы элемента; указатели на вложенные элементы добавляются в pending
+fn free_element_fields(element: XamlElement, pending: &mut Vec<*mut XamlElement>) {
+    unsafe {
+        free_string(element.name);
+        free_string(element.namespace);
+        free_string(element.text_content);
+        free_string(element.prefix);
+        for s in element.directives.strings() {
+            free_string(s);
         }
 
         if !element.attributes.is_null() {
-            let attrs = Box::from_raw(std::slice::from_raw_parts_mut(
+            let attrs = Box::from_raw(std::ptr::slice_from_raw_parts_mut(
                 element.attributes,
                 element.attributes_len
             ));
             for attr in attrs.iter() {
-                if !attr.key.is_null() {
-                    let _ = CString::from_raw(attr.key);
-                }
-                if !attr.value.is_null() {
-                    let _ = CString::from_raw(attr.value);
+                free_string(attr.key);
+                free_string(attr.value);
+                free_string(attr.namespace);
+                free_string(attr.prefix);
+                free_string(attr.owner_type);
+                free_string(attr.member);
+            }
+        }
+
+        if !element.namespaces.is_null() {
+            let namespaces = Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+                element.namespaces,
+                element.namespaces_len
+            ));
+            for namespace in namespaces.iter() {
+                free_string(namespace.prefix);
+                free_string(namespace.uri);
+                free_string(namespace.clr_namespace);
+                free_string(namespace.assembly);
+            }
+        }
+
+        if !element.nodes.is_null() {
+            let nodes = Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+                element.nodes,
+                element.nodes_len
+            ));
+            for node in nodes.iter() {
+                free_string(node.text);
+                free_string(node.target);
+            }
+        }
+
+        if !element.properties.is_null() {
+            let properties = Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+                element.properties,
+                element.properties_len
+            ));
+            for property in properties.iter() {
+                free_string(property.owner_type);
+                free_string(property.member);
+                if !property.element.is_null() {
+                    pending.push(property.element);
                 }
             }
         }
 
         if !element.children.is_null() {
-            let children = Box::from_raw(std::slice::from_raw_parts_mut(
+            let children = Box::from_raw(std::ptr::slice_from_raw_parts_mut(
                 element.children,
                 element.children_len
             ));
-            for &child_ptr in children.iter() {
-                if !child_ptr.is_null() {
-                    free_xaml_element_internal(Box::from_raw(child_ptr));
-                }
+            pending.extend(children.iter().copied().filter(|child| !child.is_null()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::AttributeKind;
+    use std::ffi::CString;
+
+    fn parse_error(xml: &str) -> (i32, i32, u32, u32, usize, String) {
+        let xml = CString::new(xml).unwrap();
+        let mut result = std::ptr::null_mut();
+        let mut error = std::ptr::null_mut();
+        let code = parse_xaml_ex(xml.as_ptr(), &mut result, &mut error);
+        assert!(result.is_null());
+        assert!(!error.is_null());
+
+        let e = unsafe { &*error };
+        let message = unsafe { CStr::from_ptr(e.message) }.to_str().unwrap().to_string();
+        let fields = (code, e.kind, e.line, e.column, e.offset, message);
+        assert_eq!(free_xaml_parse_error(error), 0);
+        fields
+    }
+
+    #[test]
+    fn parse_xaml_ex_succeeds_without_error() {
+        let xml = CString::new("<Grid><Button/></Grid>").unwrap();
+        let mut result = std::ptr::null_mut();
+        let mut error = std::ptr::null_mut();
+        assert_eq!(parse_xaml_ex(xml.as_ptr(), &mut result, &mut error), 0);
+        assert!(!result.is_null());
+        assert!(error.is_null());
+        assert_eq!(free_xaml_element(result), 0);
+    }
+
+    fn c_str(ptr: *mut c_char) -> Option<String> {
+        if ptr.is_null() {
+            None
+        } else {
+            Some(unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string())
+        }
+    }
+
+    #[test]
+    fn keeps_namespace_prefixes() {
+        let xml = CString::new(
+            r#"<Window xmlns="http://schemas.microsoft.com/winfx/2006/xaml/presentation"
+                xmlns:x="http://schemas.microsoft.com/winfx/2006/xaml"
+                xmlns:d="http://schemas.microsoft.com/expression/blend/2008"
+                x:Name="main" Name="other" d:DesignWidth="800">
+                <x:Code/>
+            </Window>"#,
+        )
+        .unwrap();
+        let mut result = std::ptr::null_mut();
+        assert_eq!(parse_xaml(xml.as_ptr(), &mut result), 0);
+
+        let root = unsafe { &*result };
+        assert_eq!(c_str(root.prefix), None);
+        let attrs_raw = unsafe { std::slice::from_raw_parts(root.attributes, root.attributes_len) };
+        let attrs: Vec<_> = attrs_raw
+            .iter()
+            .map(|a| (c_str(a.prefix), c_str(a.key).unwrap(), c_str(a.namespace)))
+            .collect();
+        assert_eq!(
+            attrs,
+            vec![
+                (
+                    Some("x".to_string()),
+                    "Name".to_string(),
+                    Some("http://schemas.microsoft.com/winfx/2006/xaml".to_string())
+                ),
+                (None, "Name".to_string(), None),
+                (
+                    Some("d".to_string()),
+                    "DesignWidth".to_string(),
+                    Some("http://schemas.microsoft.com/expression/blend/2008".to_string())
+                ),
+            ]
+        );
+
+        let kinds: Vec<_> = attrs_raw.iter().map(|a| a.kind).collect();
+        let (directive, property) = (AttributeKind::Directive as i32, AttributeKind::Property as i32);
+        assert_eq!(kinds, [directive, property, property]);
+        let code = unsafe { &**root.children };
+        assert_eq!(c_str(code.prefix).as_deref(), Some("x"));
+        assert_eq!(c_str(code.name).as_deref(), Some("Code"));
+        assert_eq!(free_xaml_element(result), 0);
+    }
+
+    #[test]
+    fn exposes_namespace_declarations() {
+        let xml = CString::new(
+            "<Grid xmlns=\"urn:ui\" xmlns:local=\"clr-namespace:App.Controls;assembly=App\">\
+             <local:Card xmlns:w=\"using:Windows.UI\"/></Grid>",
+        )
+        .unwrap();
+        let mut result = std::ptr::null_mut();
+        assert_eq!(parse_xaml(xml.as_ptr(), &mut result), 0);
+
+        let namespaces = |element: &XamlElement| {
+            unsafe { std::slice::from_raw_parts(element.namespaces, element.namespaces_len) }
+                .iter()
+                .map(|n| (c_str(n.prefix), c_str(n.uri).unwrap(), c_str(n.clr_namespace), c_str(n.assembly)))
+                .collect::<Vec<_>>()
+        };
+        let owned = |s: &str| Some(s.to_string());
+        let root = unsafe { &*result };
+        assert_eq!(
+            namespaces(root),
+            [
+                (None, "urn:ui".to_string(), None, None),
+                (
+                    owned("local"),
+                    "clr-namespace:App.Controls;assembly=App".to_string(),
+                    owned("App.Controls"),
+                    owned("App")
+                ),
+            ]
+        );
+        let card = unsafe { &**root.children };
+        assert_eq!(
+            namespaces(card),
+            [(owned("w"), "using:Windows.UI".to_string(), owned("Windows.UI"), None)]
+        );
+        assert_eq!(free_xaml_element(result), 0);
+    }
+
+    #[test]
+    fn exposes_mixed_content_in_order() {
+        let xml = CString::new(
+            "<TextBlock>Hello <Run>big</Run> world<!-- note --><?pi data?><![CDATA[<raw>]]>&amp;tail</TextBlock>",
+        )
+        .unwrap();
+        let mut result = std::ptr::null_mut();
+        assert_eq!(parse_xaml(xml.as_ptr(), &mut result), 0);
+
+        let root = unsafe { &*result };
+        let nodes = unsafe { std::slice::from_raw_parts(root.nodes, root.nodes_len) };
+        let nodes: Vec<_> = nodes.iter().map(|n| (n.kind, c_str(n.text), c_str(n.target))).collect();
+        assert_eq!(
+            nodes,
+            vec![
+                (NodeKind::Text as i32, Some("Hello ".to_string()), None),
+                (NodeKind::Element as i32, None, None),
+                (NodeKind::Text as i32, Some(" world".to_string()), None),
+                (NodeKind::Comment as i32, Some(" note ".to_string()), None),
+                (NodeKind::ProcessingInstruction as i32, Some("data".to_string()), Some("pi".to_string())),
+                (NodeKind::CData as i32, Some("<raw>".to_string()), None),
+                (NodeKind::Text as i32, Some("&tail".to_string()), None),
+            ]
+        );
+
+        let run = unsafe { &*(*root.nodes.add(1)).element };
+        assert_eq!(unsafe { *root.children }, unsafe { (*root.nodes.add(1)).element });
+        assert_eq!(c_str(run.name).as_deref(), Some("Run"));
+        assert_eq!(run.nodes_len, 1);
+        assert_eq!(run.span.range(), 17..31);
+        assert_eq!(run.start_tag_span.range(), 17..22);
+        assert_eq!(free_xaml_element(result), 0);
+    }
+
+    #[test]
+    fn classifies_attached_property_attributes() {
+        let xml = CString::new(r#"<Button Grid.Row="1" Width="10"/>"#).unwrap();
+        let mut result = std::ptr::null_mut();
+        assert_eq!(parse_xaml(xml.as_ptr(), &mut result), 0);
+
+        let button = unsafe { &*result };
+        let attrs = unsafe { std::slice::from_raw_parts(button.attributes, button.attributes_len) };
+        assert_eq!(attrs[0].kind, AttributeKind::AttachedProperty as i32);
+        assert_eq!(c_str(attrs[0].owner_type).as_deref(), Some("Grid"));
+        assert_eq!(c_str(attrs[0].member).as_deref(), Some("Row"));
+        assert_eq!(c_str(attrs[0].key).as_deref(), Some("Grid.Row"));
+        assert_eq!(attrs[1].kind, AttributeKind::Property as i32);
+        assert_eq!(c_str(attrs[1].owner_type), None);
+        assert_eq!(c_str(attrs[1].member).as_deref(), Some("Width"));
+        assert_eq!(free_xaml_element(result), 0);
+    }
+
+    #[test]
+    fn attaches_property_elements_to_owner() {
+        let xml = CString::new(
+            "<Grid><Grid.RowDefinitions><RowDefinition/></Grid.RowDefinitions><Button/>t<Grid.Tag/></Grid>",
+        )
+        .unwrap();
+        let mut result = std::ptr::null_mut();
+        assert_eq!(parse_xaml(xml.as_ptr(), &mut result), 0);
+
+        let grid = unsafe { &*result };
+        assert_eq!(grid.children_len, 1);
+        assert_eq!(grid.nodes_len, 2);
+        assert_eq!(grid.properties_len, 2);
+        let properties = unsafe { std::slice::from_raw_parts(grid.properties, 2) };
+        let property = &properties[0];
+        assert_eq!(c_str(property.owner_type).as_deref(), Some("Grid"));
+        assert_eq!(c_str(property.member).as_deref(), Some("RowDefinitions"));
+        assert_eq!((property.node_index, property.child_index), (0, 0));
+        assert_eq!((properties[1].node_index, properties[1].child_index), (2, 1));
+        let rows = unsafe { &*property.element };
+        assert_eq!(c_str(rows.name).as_deref(), Some("Grid.RowDefinitions"));
+        assert_eq!(rows.children_len, 1);
+
+        // Обратное чтение сохраняет места элементов свойств
+        let model = unsafe { read_xaml_element(grid) }.unwrap();
+        assert_eq!(model.properties.iter().map(|p| p.index).collect::<Vec<_>>(), [0, 2]);
+        assert_eq!(free_xaml_element(result), 0);
+    }
+
+    #[test]
+    fn writes_parsed_tree_back() {
+        let xml = CString::new(
+            r#"<Grid xmlns:x="urn:x" x:Name="root"><Grid.RowDefinitions><RowDefinition/></Grid.RowDefinitions><TextBlock>a &lt; <Run>b</Run></TextBlock></Grid>"#,
+        )
+        .unwrap();
+        let mut tree = std::ptr::null_mut();
+        assert_eq!(parse_xaml(xml.as_ptr(), &mut tree), 0);
+
+        let indent = CString::new("  ").unwrap();
+        let options = XamlWriteOptions {
+            indent: indent.as_ptr(),
+            self_close_empty: true,
+            xml_declaration: false,
+        };
+        let mut text = std::ptr::null_mut();
+        assert_eq!(write_xaml(tree, &options, &mut text), 0);
+        assert_eq!(
+            c_str(text).as_deref(),
+            Some(
+                "<Grid xmlns:x=\"urn:x\" x:Name=\"root\">\n  <Grid.RowDefinitions>\n    <RowDefinition/>\n  \
+                 </Grid.RowDefinitions>\n  <TextBlock>a &lt; <Run>b</Run></TextBlock>\n</Grid>"
+            )
+        );
+        assert_eq!(free_xaml_string(text), 0);
+        assert_eq!(free_xaml_element(tree), 0);
+    }
+
+    #[test]
+    fn exposes_directives() {
+        let xml = CString::new(
+            "<Window xmlns:x=\"http://schemas.microsoft.com/winfx/2006/xaml\" x:Class=\"App.Main\">\
+             <Style x:Key=\"Accent\" x:Shared=\"false\"/><Button x:Name=\"ok\"/></Window>",
+        )
+        .unwrap();
+        let mut result = std::ptr::null_mut();
+        assert_eq!(parse_xaml(xml.as_ptr(), &mut result), 0);
+
+        let root = unsafe { &*result };
+        assert_eq!(c_str(root.directives.class).as_deref(), Some("App.Main"));
+        assert_eq!((c_str(root.directives.name), root.directives.shared), (None, -1));
+        let children = unsafe { std::slice::from_raw_parts(root.children, root.children_len) };
+        let style = unsafe { &*children[0] };
+        assert_eq!(c_str(style.directives.key).as_deref(), Some("Accent"));
+        assert_eq!(style.directives.shared, 0);
+        let button = unsafe { &*children[1] };
+        assert_eq!(c_str(button.directives.name).as_deref(), Some("ok"));
+        assert_eq!(free_xaml_element(result), 0);
+
+        let xml = "<Grid xmlns:x=\"http://schemas.microsoft.com/winfx/2006/xaml\"><Button x:Class=\"App.Main\"/></Grid>";
+        assert_eq!(parse_with_options(xml, std::ptr::null()), (XAML_OK, None));
+        let mut options = default_options();
+        options.flags = XAML_FLAG_VALIDATE_DIRECTIVES;
+        assert_eq!(parse_with_options(xml, &options), (XAML_ERROR_PARSE, Some(ErrorKind::Directive as i32)));
+    }
+
+    // Элемент без содержимого, как его заполняет хост
+    fn host_element(name: &CString) -> XamlElement {
+        XamlElement {
+            name: name.as_ptr() as *mut c_char,
+            namespace: std::ptr::null_mut(),
+            attributes: std::ptr::null_mut(),
+            attributes_len: 0,
+            children: std::ptr::null_mut(),
+            children_len: 0,
+            text_content: std::ptr::null_mut(),
+            prefix: std::ptr::null_mut(),
+            nodes: std::ptr::null_mut(),
+            nodes_len: 0,
+            span: TextSpan::default(),
+            start_tag_span: TextSpan::default(),
+            properties: std::ptr::null_mut(),
+            properties_len: 0,
+            namespaces: std::ptr::null_mut(),
+            namespaces_len: 0,
+            directives: XamlDirectives::new(&Default::default(), |_| std::ptr::null_mut()),
+        }
+    }
+
+    #[test]
+    fn writes_tree_built_by_host() {
+        let name = CString::new("Button").unwrap();
+        let key = CString::new("Content").unwrap();
+        let value = CString::new("OK").unwrap();
+        let mut attribute = XamlAttribute {
+            key: key.as_ptr() as *mut c_char,
+            value: value.as_ptr() as *mut c_char,
+            namespace: std::ptr::null_mut(),
+            prefix: std::ptr::null_mut(),
+            span: TextSpan::default(),
+            value_span: TextSpan::default(),
+            kind: 0,
+            owner_type: std::ptr::null_mut(),
+            member: std::ptr::null_mut(),
+        };
+        let element = XamlElement {
+            attributes: &mut attribute,
+            attributes_len: 1,
+            ..host_element(&name)
+        };
+
+        let mut text = std::ptr::null_mut();
+        assert_eq!(write_xaml(&element, std::ptr::null(), &mut text), 0);
+        assert_eq!(c_str(text).as_deref(), Some(r#"<Button Content="OK"/>"#));
+        assert_eq!(free_xaml_string(text), 0);
+
+        let mut node = XamlNode {
+            kind: 99,
+            element: std::ptr::null_mut(),
+            text: std::ptr::null_mut(),
+            target: std::ptr::null_mut(),
+        };
+        let element = XamlElement {
+            nodes: &mut node,
+            nodes_len: 1,
+            ..host_element(&name)
+        };
+        assert_eq!(write_xaml(&element, std::ptr::null(), &mut text), XAML_ERROR_INVALID_ARGUMENT);
+    }
+
+    #[test]
+    fn writes_deep_trees() {
+        let depth = 100_000;
+        let options = XamlWriteOptions {
+            indent: std::ptr::null(),
+            self_close_empty: true,
+            xml_declaration: false,
+        };
+
+        let xml = format!("{}<a/>{}", "<a>\n".repeat(depth - 1), "\n</a>".repeat(depth - 1));
+        let mut parse_options = default_options();
+        parse_options.max_depth = depth;
+        let mut tree = std::ptr::null_mut();
+        let mut error = std::ptr::null_mut();
+        let code = parse_xaml_with_options(xml.as_ptr(), xml.len(), &parse_options, &mut tree, &mut error);
+        assert_eq!(code, XAML_OK);
+        let mut text = std::ptr::null_mut();
+        assert_eq!(write_xaml(tree, &options, &mut text), 0);
+        assert_eq!(c_str(text).as_deref(), Some(xml.as_str()));
+        assert_eq!(free_xaml_string(text), 0);
+        assert_eq!(free_xaml_element(tree), 0);
+
+        let name = CString::new("a").unwrap();
+        let mut elements: Vec<XamlElement> = (0..depth).map(|_| host_element(&name)).collect();
+        let base = elements.as_mut_ptr();
+        let mut links: Vec<*mut XamlElement> = (1..depth).map(|i| unsafe { base.add(i) }).collect();
+        for (element, link) in elements.iter_mut().zip(links.iter_mut()) {
+            element.children = link;
+            element.children_len = 1;
+        }
+        let mut text = std::ptr::null_mut();
+        assert_eq!(write_xaml(&elements[0], &options, &mut text), 0);
+        let expected = format!("{}<a/>{}", "<a>".repeat(depth - 1), "</a>".repeat(depth - 1));
+        assert_eq!(c_str(text).as_deref(), Some(expected.as_str()));
+        assert_eq!(free_xaml_string(text), 0);
+    }
+
+    #[test]
+    fn edits_lossless_document() {
+        let source = "<Grid  Margin='4'>\r\n  <!-- keep -->\r\n  <Button Content = \"A\" />\r\n</Grid>";
+        let xml = CString::new(source).unwrap();
+        let mut doc = std::ptr::null_mut();
+        let mut error = std::ptr::null_mut();
+        assert_eq!(parse_xaml_lossless(xml.as_ptr(), &mut doc, &mut error), 0);
+
+        let mut tree = std::ptr::null_mut();
+        assert_eq!(parse_xaml(xml.as_ptr(), &mut tree), 0);
+        let button_offset = unsafe { (**(*tree).children).span.start };
+        assert_eq!(free_xaml_element(tree), 0);
+
+        let name = CString::new("Content").unwrap();
+        let value = CString::new("B & C").unwrap();
+        assert_eq!(xaml_lossless_set_attribute(doc, button_offset, name.as_ptr(), value.as_ptr()), 0);
+        let margin = CString::new("Margin").unwrap();
+        assert_eq!(xaml_lossless_remove_attribute(doc, 0, margin.as_ptr()), 0);
+        assert_eq!(xaml_lossless_remove_attribute(doc, 0, margin.as_ptr()), XAML_ATTRIBUTE_NOT_FOUND);
+        assert_eq!(xaml_lossless_set_attribute(doc, 1, name.as_ptr(), value.as_ptr()), XAML_ERROR_OUT_OF_RANGE);
+        assert_eq!(xaml_lossless_remove_attribute(doc, 1, margin.as_ptr()), XAML_ERROR_OUT_OF_RANGE);
+        let invalid = CString::new("a=\"1\" b").unwrap();
+        assert_eq!(xaml_lossless_set_attribute(doc, 0, invalid.as_ptr(), value.as_ptr()), XAML_ERROR_INVALID_NAME);
+
+        let mut text = std::ptr::null_mut();
+        assert_eq!(xaml_lossless_to_string(doc, &mut text), 0);
+        assert_eq!(
+            c_str(text).as_deref(),
+            Some("<Grid>\r\n  <!-- keep -->\r\n  <Button Content = \"B &amp; C\" />\r\n</Grid>")
+        );
+        assert_eq!(free_xaml_string(text), 0);
+        assert_eq!(free_xaml_lossless(doc), 0);
+    }
+
+    #[test]
+    fn parses_markup_extension_over_ffi() {
+        let text = CString::new("{Binding Name, Converter={StaticResource Conv}}").unwrap();
+        let mut result = std::ptr::null_mut();
+        let mut error = std::ptr::null_mut();
+        assert_eq!(parse_xaml_markup_extension(text.as_ptr(), &mut result, &mut error), 0);
+        assert!(error.is_null());
+
+        let value = unsafe { &*result };
+        assert!(value.text.is_null());
+        let binding = unsafe { &*value.extension };
+        assert_eq!(c_str(binding.name).as_deref(), Some("Binding"));
+        let args = unsafe { std::slice::from_raw_parts(binding.arguments, binding.arguments_len) };
+        assert_eq!(c_str(args[0].name), None);
+        assert_eq!(c_str(args[0].value.text).as_deref(), Some("Name"));
+        assert_eq!(c_str(args[1].name).as_deref(), Some("Converter"));
+        let resource = unsafe { &*args[1].value.extension };
+        assert_eq!(c_str(resource.name).as_deref(), Some("StaticResource"));
+        assert_eq!(free_xaml_markup_value(result), 0);
+
+        let text = CString::new("{Binding Path=A, B}").unwrap();
+        assert_eq!(parse_xaml_markup_extension(text.as_ptr(), &mut result, &mut error), -3);
+        let e = unsafe { &*error };
+        assert_eq!(e.kind, ErrorKind::MarkupExtension as i32);
+        assert_eq!((e.line, e.column, e.offset), (1, 18, 17));
+        assert_eq!(free_xaml_parse_error(error), 0);
+
+        // Глубокая вложенность даёт ошибку, а не переполнение стека
+        let text = CString::new("{a ".repeat(200_000)).unwrap();
+        assert_eq!(parse_xaml_markup_extension(text.as_ptr(), &mut result, &mut error), -3);
+        assert_eq!(unsafe { (*error).kind }, ErrorKind::MarkupExtension as i32);
+        assert_eq!(free_xaml_parse_error(error), 0);
+    }
+
+    #[test]
+    fn reports_unclosed_tag() {
+        let (code, kind, line, column, offset, _) = parse_error("<Grid>\n  <Button>\n</Grid>");
+        assert_eq!(code, -3);
+        assert_eq!(kind, ErrorKind::UnclosedTag as i32);
+        assert_eq!((line, column, offset), (3, 1, 18));
+
+        let (_, kind, line, _, offset, _) = parse_error("<Grid>\n  <Button/>");
+        assert_eq!(kind, ErrorKind::UnclosedTag as i32);
+        assert_eq!((line, offset), (2, 18));
+    }
+
+    #[test]
+    fn reports_bad_entity() {
+        let (_, kind, line, column, offset, message) =
+            parse_error("<TextBlock Text=\"a &nbsp; b\"/>");
+        assert_eq!(kind, ErrorKind::InvalidEntity as i32);
+        assert_eq!((line, column, offset), (1, 20, 19));
+        assert!(message.contains("nbsp"));
+    }
+
+    #[test]
+    fn reports_duplicate_attribute() {
+        let (_, kind, line, column, offset, message) =
+            parse_error("<Button\n    Width=\"10\" Width=\"20\"/>");
+        assert_eq!(kind, ErrorKind::DuplicateAttribute as i32);
+        assert_eq!((line, column, offset), (2, 16, 23));
+        assert!(message.contains("Width"));
+    }
+
+    #[test]
+    fn reports_unknown_namespace_prefix() {
+        let (_, kind, line, column, offset, message) = parse_error("<Grid>\n  <local:Button/>\n</Grid>");
+        assert_eq!(kind, ErrorKind::UnknownNamespace as i32);
+        assert_eq!((line, column, offset), (2, 4, 10));
+        assert!(message.contains("local"));
+    }
+
+    #[test]
+    fn rejects_interior_nul_unless_length_prefixed() {
+        let mut root = model::XamlElement::new("TextBlock");
+        root.attributes.push(model::XamlAttribute::new("Text", "a\0b"));
+
+        let mut result = std::ptr::null_mut();
+        assert_eq!(export_element(&root, 0, &mut result), XAML_ERROR_UNREPRESENTABLE_STRING);
+        assert!(result.is_null());
+
+        assert_eq!(export_element(&root, XAML_FLAG_LENGTH_PREFIXED_STRINGS, &mut result), XAML_OK);
+        let attr = unsafe { &*(*result).attributes };
+        let mut len = 0;
+        assert_eq!(xaml_string_length(attr.value, &mut len), XAML_OK);
+        assert_eq!(len, 3);
+        assert_eq!(unsafe { std::slice::from_raw_parts(attr.value as *const u8, len) }, b"a\0b");
+        assert_eq!(free_xaml_element(result), XAML_OK);
+
+        let xml = CString::new("<TextBlock Text=\"&#0;\"/>").unwrap();
+        assert_eq!(parse_xaml(xml.as_ptr(), &mut result), XAML_ERROR_PARSE);
+    }
+
+    #[test]
+    fn converts_panics_to_error_code() {
+        assert_eq!(guard(|| panic!("boom")), XAML_ERROR_PANIC);
+        assert_eq!(guard(|| XAML_OK), XAML_OK);
+    }
+
+    #[test]
+    fn parses_utf8_and_utf16_slices() {
+        let text = "<Кнопка Текст=\"Привет\"/>trailing garbage";
+        let len = text.find("trailing").unwrap();
+        let mut utf8 = std::ptr::null_mut();
+        assert_eq!(parse_xaml_utf8(text.as_ptr(), len, 0, &mut utf8, std::ptr::null_mut()), XAML_OK);
+
+        let wide: Vec<u16> = text[..len].encode_utf16().collect();
+        let mut utf16 = std::ptr::null_mut();
+        assert_eq!(parse_xaml_utf16(wide.as_ptr(), wide.len(), 0, &mut utf16, std::ptr::null_mut()), XAML_OK);
+
+        for root in [utf8, utf16] {
+            let root = unsafe { &*root };
+            assert_eq!(c_str(root.name).as_deref(), Some("Кнопка"));
+            assert_eq!(c_str(unsafe { (*root.attributes).value }).as_deref(), Some("Привет"));
+            assert_eq!(root.span.range(), 0..len);
+        }
+        assert_eq!(free_xaml_element(utf8), XAML_OK);
+        assert_eq!(free_xaml_element(utf16), XAML_OK);
+
+        let mut result = std::ptr::null_mut();
+        let bad_utf8 = b"<a>\xff</a>";
+        assert_eq!(parse_xaml_utf8(bad_utf8.as_ptr(), bad_utf8.len(), 0, &mut result, std::ptr::null_mut()), XAML_ERROR_INVALID_UTF8);
+        let lone_surrogate = [b'<' as u16, 0xD800, b'/' as u16, b'>' as u16];
+        assert_eq!(parse_xaml_utf16(lone_surrogate.as_ptr(), 4, 0, &mut result, std::ptr::null_mut()), XAML_ERROR_INVALID_UTF8);
+
+        let mut error = std::ptr::null_mut();
+        let unclosed: Vec<u16> = "<a>".encode_utf16().collect();
+        assert_eq!(parse_xaml_utf16(unclosed.as_ptr(), unclosed.len(), 0, &mut result, &mut error), XAML_ERROR_PARSE);
+        assert!(result.is_null());
+        assert_eq!(free_xaml_parse_error(error), XAML_OK);
+    }
+
+    #[test]
+    fn parses_bytes_with_detected_encoding() {
+        let mut bytes = vec![0xFF, 0xFE];
+        for unit in "<Окно/>".encode_utf16() {
+            bytes.extend(unit.to_le_bytes());
+        }
+        let mut result = std::ptr::null_mut();
+        let mut used = -1;
+        let mut error = std::ptr::null_mut();
+        assert_eq!(parse_xaml_bytes(bytes.as_ptr(), bytes.len(), 0, &mut result, &mut used, &mut error), XAML_OK);
+        assert_eq!(used, crate::encoding::Encoding::Utf16Le as i32);
+        assert_eq!(c_str(unsafe { (*result).name }).as_deref(), Some("Окно"));
+        assert_eq!(free_xaml_element(result), XAML_OK);
+
+        let bytes = b"<?xml version=\"1.0\" encoding=\"koi8-r\"?><a/>";
+        let code = parse_xaml_bytes(bytes.as_ptr(), bytes.len(), 0, &mut result, std::ptr::null_mut(), &mut error);
+        assert_eq!(code, XAML_ERROR_PARSE);
+        assert_eq!(unsafe { (*error).kind }, ErrorKind::Encoding as i32);
+        assert_eq!(free_xaml_parse_error(error), XAML_OK);
+    }
+
+    #[test]
+    fn parses_file_and_reports_its_path() {
+        let dir = std::env::temp_dir().join(format!("xaml-parser-ffi-file-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("Page.xaml");
+        std::fs::write(&path, "<Page><Grid></Page>").unwrap();
+        let c_path = CString::new(path.to_str().unwrap()).unwrap();
+
+        let mut result = std::ptr::null_mut();
+        let mut error = std::ptr::null_mut();
+        assert_eq!(parse_xaml_file(c_path.as_ptr(), 0, &mut result, std::ptr::null_mut(), &mut error), XAML_ERROR_PARSE);
+        assert_eq!(c_str(unsafe { (*error).file_path }).as_deref(), path.to_str());
+        assert_eq!(free_xaml_parse_error(error), XAML_OK);
+
+        std::fs::write(&path, "<Page><Grid/></Page>").unwrap();
+        let mut used = -1;
+        assert_eq!(parse_xaml_file(c_path.as_ptr(), 0, &mut result, &mut used, &mut error), XAML_OK);
+        assert!(error.is_null());
+        assert_eq!(used, crate::encoding::Encoding::Utf8 as i32);
+        assert_eq!(unsafe { (*result).children_len }, 1);
+        assert_eq!(free_xaml_element(result), XAML_OK);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        result = std::ptr::null_mut();
+        assert_eq!(parse_xaml_file(c_path.as_ptr(), 0, &mut result, std::ptr::null_mut(), &mut error), XAML_ERROR_IO);
+        assert!(result.is_null());
+        assert_eq!(unsafe { (*error).kind }, ErrorKind::Io as i32);
+        assert_eq!(free_xaml_parse_error(error), XAML_OK);
+    }
+
+    // x:Class не на корне: нарушение видно только с XAML_FLAG_VALIDATE_DIRECTIVES
+    pub(super) const MISPLACED_CLASS: &str =
+        "<Grid xmlns:x=\"http://schemas.microsoft.com/winfx/2006/xaml\"><Button x:Class=\"App.Main\"/></Grid>";
+
+    // Каталог с единственным файлом MISPLACED_CLASS
+    fn misplaced_class_dir(name: &str) -> (std::path::PathBuf, CString) {
+        let dir = std::env::temp_dir().join(format!("xaml-parser-ffi-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("Page.xaml");
+        std::fs::write(&path, MISPLACED_CLASS).unwrap();
+        (dir, CString::new(path.to_str().unwrap()).unwrap())
+    }
+
+    #[test]
+    fn validates_directives_in_file() {
+        let (dir, c_path) = misplaced_class_dir("file-directives");
+        let mut result = std::ptr::null_mut();
+        let mut error = std::ptr::null_mut();
+        assert_eq!(parse_xaml_file(c_path.as_ptr(), 0, &mut result, std::ptr::null_mut(), &mut error), XAML_OK);
+        assert_eq!(free_xaml_element(result), XAML_OK);
+
+        result = std::ptr::null_mut();
+        let flags = XAML_FLAG_VALIDATE_DIRECTIVES;
+        let code = parse_xaml_file(c_path.as_ptr(), flags, &mut result, std::ptr::null_mut(), &mut error);
+        assert_eq!(code, XAML_ERROR_PARSE);
+        assert!(result.is_null());
+        assert_eq!(unsafe { (*error).kind }, ErrorKind::Directive as i32);
+        assert_eq!(free_xaml_parse_error(error), XAML_OK);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn validates_directives_in_files() {
+        let (dir, c_path) = misplaced_class_dir("files-directives");
+        let paths = [c_path.as_ptr()];
+        let mut results = std::ptr::null_mut();
+        let mut len = 0;
+        assert_eq!(parse_xaml_files(paths.as_ptr(), 1, 0, 0, &mut results, &mut len), XAML_OK);
+        assert_eq!(unsafe { (*results).code }, XAML_OK);
+        assert_eq!(free_xaml_batch(results, len), XAML_OK);
+
+        let flags = XAML_FLAG_VALIDATE_DIRECTIVES;
+        assert_eq!(parse_xaml_files(paths.as_ptr(), 1, flags, 0, &mut results, &mut len), XAML_OK);
+        let item = unsafe { &*results };
+        assert_eq!(item.code, XAML_ERROR_PARSE);
+        assert_eq!(unsafe { (*item.error).kind }, ErrorKind::Directive as i32);
+        assert_eq!(free_xaml_batch(results, len), XAML_OK);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn validates_directives_in_directory() {
+        let (dir, _) = misplaced_class_dir("directory-directives");
+        let c_dir = CString::new(dir.to_str().unwrap()).unwrap();
+        let mut results = std::ptr::null_mut();
+        let mut len = 0;
+        let code = parse_xaml_directory(c_dir.as_ptr(), std::ptr::null(), false, 0, 0, &mut results, &mut len);
+        assert_eq!(code, XAML_OK);
+        assert_eq!(unsafe { (*results).code }, XAML_OK);
+        assert_eq!(free_xaml_batch(results, len), XAML_OK);
+
+        let flags = XAML_FLAG_VALIDATE_DIRECTIVES;
+        let code = parse_xaml_directory(c_dir.as_ptr(), std::ptr::null(), false, flags, 0, &mut results, &mut len);
+        assert_eq!(code, XAML_OK);
+        let item = unsafe { &*results };
+        assert_eq!(item.code, XAML_ERROR_PARSE);
+        assert_eq!(unsafe { (*item.error).kind }, ErrorKind::Directive as i32);
+        assert_eq!(free_xaml_batch(results, len), XAML_OK);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn parses_batch_of_files() {
+        let dir = std::env::temp_dir().join(format!("xaml-parser-ffi-batch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("A.xaml"), "<A/>").unwrap();
+        std::fs::write(dir.join("B.xaml"), "<B>").unwrap();
+        let c_dir = CString::new(dir.to_str().unwrap()).unwrap();
+
+        let mut results = std::ptr::null_mut();
+        let mut len = 0;
+        let code = parse_xaml_directory(c_dir.as_ptr(), std::ptr::null(), false, 0, 2, &mut results, &mut len);
+        assert_eq!(code, XAML_OK);
+        let items = unsafe { std::slice::from_raw_parts(results, len) };
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].code, XAML_OK);
+        assert_eq!(c_str(unsafe { (*items[0].element).name }).as_deref(), Some("A"));
+        assert_eq!(items[1].code, XAML_ERROR_PARSE);
+        assert!(items[1].element.is_null());
+        assert_eq!(c_str(unsafe { (*items[1].error).file_path }), c_str(items[1].path));
+        assert_eq!(free_xaml_batch(results, len), XAML_OK);
+
+        let missing = CString::new(dir.join("C.xaml").to_str().unwrap()).unwrap();
+        let a = CString::new(dir.join("A.xaml").to_str().unwrap()).unwrap();
+        let paths = [missing.as_ptr(), a.as_ptr()];
+        assert_eq!(parse_xaml_files(paths.as_ptr(), 2, 0, 0, &mut results, &mut len), XAML_OK);
+        let items = unsafe { std::slice::from_raw_parts(results, len) };
+        assert_eq!((items[0].code, items[0].encoding), (XAML_ERROR_IO, -1));
+        assert_eq!(items[1].code, XAML_OK);
+        assert_eq!(free_xaml_batch(results, len), XAML_OK);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        let code = parse_xaml_directory(c_dir.as_ptr(), std::ptr::null(), false, 0, 0, &mut results, &mut len);
+        assert_eq!(code, XAML_ERROR_IO);
+    }
+
+    // Цепочка вложенных элементов, через один — элементы свойств
+    fn deep_model(depth: usize) -> model::XamlElement {
+        let mut element = model::XamlElement::new("Leaf");
+        for i in 1..depth {
+            let mut parent = model::XamlElement::new("Border");
+            if i % 2 == 0 {
+                parent.children.push(model::XamlNode::Element(element));
+            } else {
+                parent.properties.push(model::XamlPropertyElement {
+                    owner_type: "Border".to_string(),
+                    member: "Child".to_string(),
+                    element,
+                    index: 0,
+                });
             }
+            element = parent;
+        }
+        element
+    }
+
+    #[test]
+    fn converts_and_frees_deep_trees() {
+        let root = deep_model(100_000);
+        let mut result = std::ptr::null_mut();
+        assert_eq!(export_element(&root, 0, &mut result), XAML_OK);
+
+        let mut depth = 1;
+        let mut element = unsafe { &*result };
+        while element.children_len + element.properties_len > 0 {
+            element = match element.children_len {
+                0 => unsafe { &*(*element.properties).element },
+                _ => unsafe { &**element.children },
+            };
+            depth += 1;
         }
+        assert_eq!(depth, 100_000);
+        assert_eq!(c_str(element.name).as_deref(), Some("Leaf"));
+        assert_eq!(free_xaml_element(result), 0);
+
+        let arena = arena::XamlArena::build(&root, 0).ok().unwrap();
+        drop(arena);
+        drop(root);
+    }
+
+    fn parse_with_options(xml: &str, options: *const XamlParseOptions) -> (i32, Option<i32>) {
+        let mut result = std::ptr::null_mut();
+        let mut error = std::ptr::null_mut();
+        let code = parse_xaml_with_options(xml.as_ptr(), xml.len(), options, &mut result, &mut error);
+        let kind = (!error.is_null()).then(|| unsafe { (*error).kind });
+        if !result.is_null() {
+            assert_eq!(free_xaml_element(result), 0);
+        }
+        if !error.is_null() {
+            assert_eq!(free_xaml_parse_error(error), 0);
+        }
+        (code, kind)
+    }
+
+    fn default_options() -> XamlParseOptions {
+        let mut options: XamlParseOptions = unsafe { std::mem::zeroed() };
+        options.size = size_of::<XamlParseOptions>();
+        options
     }
-}
\ No newline at end of file
+
+    const LIMIT_EXCEEDED: (i32, Option<i32>) = (XAML_ERROR_LIMIT_EXCEEDED, Some(ErrorKind::LimitExceeded as i32));
+
+    #[test]
+    fn parse_xaml_with_options_limits_depth() {
+        let nested = |depth| "<a>".repeat(depth) + &"</a>".repeat(depth);
+        let deep = nested(100_000);
+        assert_eq!(parse_with_options(&deep, std::ptr::null()), LIMIT_EXCEEDED);
+
+        // Без XamlParseOptions глубина не ограничена
+        let xml = CString::new("<a>\n".repeat(100_000) + &"</a>\n".repeat(100_000)).unwrap();
+        let mut result = std::ptr::null_mut();
+        assert_eq!(parse_xaml(xml.as_ptr(), &mut result), XAML_OK);
+        assert_eq!(free_xaml_element(result), 0);
+
+        let mut options = default_options();
+        options.max_depth = 2000;
+        assert_eq!(parse_with_options(&nested(2000), &options), (XAML_OK, None));
+        options.max_depth = 10;
+        assert_eq!(parse_with_options(&nested(11), &options), LIMIT_EXCEEDED);
+
+        // Поля за пределами size хоста не читаются
+        options.size = size_of::<usize>();
+        assert_eq!(parse_with_options(&nested(11), &options), (XAML_OK, None));
+    }
+
+    #[test]
+    fn parse_xaml_with_options_applies_resource_limits() {
+        let xml = "<Grid Width=\"10\" Height=\"20\"><Button/>text</Grid>";
+        let check = |set: fn(&mut XamlParseOptions, usize), ok: usize, exceeded: usize| {
+            let mut options = default_options();
+            set(&mut options, ok);
+            assert_eq!(parse_with_options(xml, &options), (XAML_OK, None));
+            set(&mut options, exceeded);
+            assert_eq!(parse_with_options(xml, &options), LIMIT_EXCEEDED);
+        };
+        check(|o, n| o.max_input_size = n, xml.len(), xml.len() - 1);
+        check(|o, n| o.max_nodes = n as u32, 4, 3);
+        check(|o, n| o.max_attributes = n, 2, 1);
+        check(|o, n| o.max_attribute_value_len = n, 2, 1);
+
+        let value = "0123456789".repeat(5);
+        let dtd = &format!("<!DOCTYPE Grid [<!ENTITY e \"{value}\">]><Grid>&e;&e;&e;&e;</Grid>");
+        let mut options = default_options();
+        assert_eq!(parse_with_options(dtd, &options), (XAML_ERROR_PARSE, Some(ErrorKind::DtdNotAllowed as i32)));
+        options.allow_dtd = true;
+        assert_eq!(parse_with_options(dtd, &options), (XAML_OK, None));
+        options.max_entity_expansion = 1;
+        assert_eq!(parse_with_options(dtd, &options), LIMIT_EXCEEDED);
+    }
+
+    #[test]
+    fn parse_xaml_with_options_applies_markup_compatibility() {
+        let xml = "<Grid xmlns:mc=\"http://schemas.openxmlformats.org/markup-compatibility/2006\" \
+                   xmlns:d=\"urn:designer\" mc:Ignorable=\"d\"><d:Data/></Grid>";
+        let children = |options: &XamlParseOptions| {
+            let mut result = std::ptr::null_mut();
+            let mut error = std::ptr::null_mut();
+            assert_eq!(parse_xaml_with_options(xml.as_ptr(), xml.len(), options, &mut result, &mut error), XAML_OK);
+            let len = unsafe { (*result).children_len };
+            assert_eq!(free_xaml_element(result), 0);
+            len
+        };
+        let mut options = default_options();
+        assert_eq!(children(&options), 1);
+        options.markup_compatibility = true;
+        assert_eq!(children(&options), 0);
+
+        let understood = [c"urn:designer".as_ptr()];
+        options.understood_namespaces = understood.as_ptr();
+        options.understood_namespaces_len = understood.len();
+        assert_eq!(children(&options), 1);
+
+        let null = [std::ptr::null()];
+        options.understood_namespaces = null.as_ptr();
+        assert_eq!(parse_with_options(xml, &options), (XAML_ERROR_NULL_ARGUMENT, None));
+
+        let xml = "<Grid xmlns:mc=\"http://schemas.openxmlformats.org/markup-compatibility/2006\" \
+                   mc:MustUnderstand=\"mc zz\"/>";
+        options.understood_namespaces_len = 0;
+        assert_eq!(
+            parse_with_options(xml, &options),
+            (XAML_ERROR_PARSE, Some(ErrorKind::MarkupCompatibility as i32))
+        );
+    }
+}
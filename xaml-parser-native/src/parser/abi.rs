@@ -0,0 +1,338 @@
+use super::events::{XamlEventHandler, XamlStreamEvent};
+use super::flat::{XamlFlatAttribute, XamlFlatDocument, XamlFlatNode, XamlStr};
+use super::*;
+
+// Версия ABI: меняется при любом несовместимом изменении экспортируемых
+// структур или сигнатур. Новые функции и новые индексы раскладки её не меняют.
+pub const XAML_ABI_VERSION: u32 = 4;
+
+// Индексы структур в массиве размеров xaml_parser_check_layout.
+// Список только дополняется.
+pub const XAML_LAYOUT_TEXT_SPAN: usize = 0;
+pub const XAML_LAYOUT_ATTRIBUTE: usize = 1;
+pub const XAML_LAYOUT_NODE: usize = 2;
+pub const XAML_LAYOUT_ELEMENT: usize = 3;
+pub const XAML_LAYOUT_PROPERTY_ELEMENT: usize = 4;
+pub const XAML_LAYOUT_PARSE_ERROR: usize = 5;
+pub const XAML_LAYOUT_MARKUP_VALUE: usize = 6;
+pub const XAML_LAYOUT_MARKUP_ARGUMENT: usize = 7;
+pub const XAML_LAYOUT_MARKUP_EXTENSION: usize = 8;
+pub const XAML_LAYOUT_WRITE_OPTIONS: usize = 9;
+pub const XAML_LAYOUT_BATCH_RESULT: usize = 10;
+pub const XAML_LAYOUT_STR: usize = 11;
+pub const XAML_LAYOUT_FLAT_NODE: usize = 12;
+pub const XAML_LAYOUT_FLAT_ATTRIBUTE: usize = 13;
+pub const XAML_LAYOUT_FLAT_DOCUMENT: usize = 14;
+pub const XAML_LAYOUT_STREAM_EVENT: usize = 15;
+pub const XAML_LAYOUT_EVENT_HANDLER: usize = 16;
+pub const XAML_LAYOUT_PARSE_OPTIONS: usize = 17;
+pub const XAML_LAYOUT_NAMESPACE: usize = 18;
+pub const XAML_LAYOUT_DIRECTIVES: usize = 19;
+pub const XAML_LAYOUT_COUNT: usize = 20;
+
+fn layout() -> [usize; XAML_LAYOUT_COUNT] {
+    use std::mem::size_of;
+
+    let mut sizes = [0; XAML_LAYOUT_COUNT];
+    sizes[XAML_LAYOUT_TEXT_SPAN] = size_of::<TextSpan>();
+    sizes[XAML_LAYOUT_ATTRIBUTE] = size_of::<XamlAttribute>();
+    sizes[XAML_LAYOUT_NODE] = size_of::<XamlNode>();
+    sizes[XAML_LAYOUT_ELEMENT] = size_of::<XamlElement>();
+    sizes[XAML_LAYOUT_PROPERTY_ELEMENT] = size_of::<XamlPropertyElement>();
+    sizes[XAML_LAYOUT_PARSE_ERROR] = size_of::<XamlParseError>();
+    sizes[XAML_LAYOUT_MARKUP_VALUE] = size_of::<XamlMarkupValue>();
+    sizes[XAML_LAYOUT_MARKUP_ARGUMENT] = size_of::<XamlMarkupArgument>();
+    sizes[XAML_LAYOUT_MARKUP_EXTENSION] = size_of::<XamlMarkupExtension>();
+    sizes[XAML_LAYOUT_WRITE_OPTIONS] = size_of::<XamlWriteOptions>();
+    sizes[XAML_LAYOUT_BATCH_RESULT] = size_of::<XamlBatchResult>();
+    sizes[XAML_LAYOUT_STR] = size_of::<XamlStr>();
+    sizes[XAML_LAYOUT_FLAT_NODE] = size_of::<XamlFlatNode>();
+    sizes[XAML_LAYOUT_FLAT_ATTRIBUTE] = size_of::<XamlFlatAttribute>();
+    sizes[XAML_LAYOUT_FLAT_DOCUMENT] = size_of::<XamlFlatDocument>();
+    sizes[XAML_LAYOUT_STREAM_EVENT] = size_of::<XamlStreamEvent>();
+    sizes[XAML_LAYOUT_EVENT_HANDLER] = size_of::<XamlEventHandler>();
+    sizes[XAML_LAYOUT_PARSE_OPTIONS] = size_of::<XamlParseOptions>();
+    sizes[XAML_LAYOUT_NAMESPACE] = size_of::<XamlNamespace>();
+    sizes[XAML_LAYOUT_DIRECTIVES] = size_of::<XamlDirectives>();
+    sizes
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn xaml_parser_abi_version() -> u32 {
+    XAML_ABI_VERSION
+}
+
+// Проверка при запуске хоста: версия ABI, под которую собран хост, и размеры
+// его копий структур по индексам XAML_LAYOUT_*. Ноль — структура не используется.
+// len может быть меньше XAML_LAYOUT_COUNT, если хост собран со старым заголовком.
+#[unsafe(no_mangle)]
+pub extern "C" fn xaml_parser_check_layout(abi_version: u32, sizes: *const usize, len: usize) -> i32 {
+    guard(|| {
+        if sizes.is_null() && len > 0 {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+        if abi_version != XAML_ABI_VERSION || len > XAML_LAYOUT_COUNT {
+            return XAML_ERROR_ABI_MISMATCH;
+        }
+
+        let host = unsafe { read_slice(sizes, len) };
+        let native = layout();
+        match host.iter().zip(native).all(|(&host, native)| host == 0 || host == native) {
+            true => XAML_OK,
+            false => XAML_ERROR_ABI_MISMATCH,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_host_layout() {
+        let mut sizes = layout();
+        assert_eq!(xaml_parser_check_layout(XAML_ABI_VERSION, sizes.as_ptr(), sizes.len()), XAML_OK);
+        assert_eq!(xaml_parser_check_layout(XAML_ABI_VERSION, sizes.as_ptr(), 3), XAML_OK);
+        assert_eq!(xaml_parser_check_layout(XAML_ABI_VERSION, std::ptr::null(), 0), XAML_OK);
+        assert_eq!(xaml_parser_check_layout(XAML_ABI_VERSION + 1, sizes.as_ptr(), sizes.len()), XAML_ERROR_ABI_MISMATCH);
+
+        sizes[XAML_LAYOUT_ELEMENT] = 0;
+        assert_eq!(xaml_parser_check_layout(XAML_ABI_VERSION, sizes.as_ptr(), sizes.len()), XAML_OK);
+        sizes[XAML_LAYOUT_ATTRIBUTE] -= size_of::<usize>();
+        assert_eq!(xaml_parser_check_layout(XAML_ABI_VERSION, sizes.as_ptr(), sizes.len()), XAML_ERROR_ABI_MISMATCH);
+    }
+
+    // Заголовок include/xaml_parser.h строится из исходников этого крейта:
+    // repr(C)-структуры, repr(i32)-перечисления, константы XAML_* и extern "C" функции.
+    // После изменения ABI обновить его:
+    //
+    //     XAML_PARSER_UPDATE_HEADER=1 cargo test header_is_up_to_date
+    #[test]
+    fn header_is_up_to_date() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let header = generate_header(root);
+        let path = root.join("include").join("xaml_parser.h");
+        if std::env::var_os("XAML_PARSER_UPDATE_HEADER").is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, &header).unwrap();
+        }
+        let current = std::fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            current == header,
+            "include/xaml_parser.h устарел; обновите его: XAML_PARSER_UPDATE_HEADER=1 cargo test header_is_up_to_date"
+        );
+    }
+
+    // Файлы модели дают только repr-типы; в файлах FFI остальные pub-структуры
+    // становятся непрозрачными дескрипторами
+    const SOURCES: [(&str, bool); 10] = [
+        ("src/span.rs", false),
+        ("src/document.rs", false),
+        ("src/error.rs", false),
+        ("src/encoding.rs", false),
+        ("src/parser.rs", true),
+        ("src/parser/abi.rs", true),
+        ("src/parser/arena.rs", true),
+        ("src/parser/events.rs", true),
+        ("src/parser/flat.rs", true),
+        ("src/parser/handle.rs", true),
+    ];
+
+    #[derive(Default)]
+    struct Header {
+        opaque: Vec<String>,
+        structs: Vec<String>,
+        defines: Vec<String>,
+        functions: Vec<String>,
+    }
+
+    fn generate_header(root: &Path) -> String {
+        let mut header = Header::default();
+        for (file, ffi) in SOURCES {
+            let source = std::fs::read_to_string(root.join(file)).unwrap();
+            scan(&source, ffi, &mut header);
+        }
+
+        let mut out = String::from(
+            "// Сгенерировано тестом header_is_up_to_date (src/parser/abi.rs), не редактировать.\n\
+             #ifndef XAML_PARSER_H\n#define XAML_PARSER_H\n\n\
+             #include <stdbool.h>\n#include <stddef.h>\n#include <stdint.h>\n\n\
+             #ifdef __cplusplus\nextern \"C\" {\n#endif\n\n",
+        );
+        for part in [&header.defines, &header.opaque, &header.structs, &header.functions] {
+            for item in part.iter() {
+                out.push_str(item);
+            }
+            out.push('\n');
+        }
+        out.push_str("#ifdef __cplusplus\n}\n#endif\n\n#endif\n");
+        out
+    }
+
+    // Разбор по строкам: исходники отформатированы rustfmt, этого достаточно.
+    // Комментарии перед элементом переносятся в заголовок.
+    fn scan(source: &str, ffi: bool, header: &mut Header) {
+        let mut comments: Vec<String> = Vec::new();
+        let mut repr = None;
+        let mut lines = source.lines();
+        while let Some(line) = lines.next() {
+            let line = line.trim_end();
+            if line == "#[cfg(test)]" {
+                break;
+            }
+            if let Some(text) = line.strip_prefix("///").or_else(|| line.strip_prefix("//")) {
+                comments.push(format!("//{text}\n"));
+                continue;
+            }
+            if let Some(r) = line.strip_prefix("#[repr(") {
+                repr = Some(r.trim_end_matches(")]").to_string());
+                continue;
+            }
+            if line.starts_with("#[") {
+                continue;
+            }
+
+            let doc = comments.concat();
+            if let Some(rest) = line.strip_prefix("pub const ") {
+                let (name, rest) = rest.split_once(':').unwrap();
+                let (ty, value) = rest.split_once('=').unwrap();
+                let value = value.trim().trim_end_matches(';');
+                let value = match (ty.trim(), value) {
+                    (_, "u32::MAX") => "UINT32_MAX".to_string(),
+                    ("u32", v) => format!("{v}u"),
+                    (_, v) if v.starts_with('-') => format!("({v})"),
+                    (_, v) => v.to_string(),
+                };
+                push(&mut header.defines, &doc, format!("#define {name} {value}\n"));
+            } else if let Some(rest) = line.strip_prefix("pub struct ").filter(|_| ffi || repr.is_some()) {
+                let name = c_name(rest.split(['(', ' ', ';']).next().unwrap());
+                header.opaque.push(format!("typedef struct {name} {name};\n"));
+                if repr.as_deref() == Some("C") {
+                    let mut body = String::new();
+                    for field in lines.by_ref().map(str::trim).take_while(|l| *l != "}") {
+                        match field.strip_prefix("//") {
+                            Some(text) => body.push_str(&format!("    //{text}\n")),
+                            None => {
+                                let field = field.trim_start_matches("pub(super) ").trim_start_matches("pub ");
+                                let (field, ty) = field.trim_end_matches(',').split_once(": ").unwrap();
+                                body.push_str(&format!("    {};\n", declaration(ty, field)));
+                            }
+                        }
+                    }
+                    header.structs.push(format!("{doc}struct {name} {{\n{body}}};\n\n"));
+                }
+            } else if let Some(rest) = line.strip_prefix("pub enum ").filter(|_| repr.as_deref() == Some("i32")) {
+                let name = c_name(rest.trim_end_matches(" {"));
+                let mut body = format!("typedef int32_t {name};\n");
+                for variant in lines.by_ref().map(str::trim).take_while(|l| *l != "}") {
+                    if let Some((variant, value)) = variant.trim_end_matches(',').split_once(" = ") {
+                        body.push_str(&format!("#define {}_{} {value}\n", screaming(&name), screaming(variant)));
+                    }
+                }
+                push(&mut header.defines, &doc, body);
+            } else if let Some(rest) = line.strip_prefix("pub extern \"C\" fn ") {
+                let mut signature = rest.to_string();
+                while !signature.ends_with('{') {
+                    signature.push_str(lines.next().unwrap().trim());
+                }
+                push(&mut header.functions, &doc, format!("{}\n", c_function(&signature)));
+            }
+            comments.clear();
+            repr = None;
+        }
+    }
+
+    // Элементы с комментарием отделяются пустой строкой
+    fn push(part: &mut Vec<String>, doc: &str, item: String) {
+        let gap = if doc.is_empty() || part.is_empty() { "" } else { "\n" };
+        part.push(format!("{gap}{doc}{item}"));
+    }
+
+    fn c_function(signature: &str) -> String {
+        let (name, rest) = signature.split_once('(').unwrap();
+        let (params, ret) = rest.rsplit_once(')').unwrap();
+        let ret = match ret.trim().trim_end_matches('{').trim().strip_prefix("-> ") {
+            Some(ty) => c_type(ty),
+            None => "void".to_string(),
+        };
+        let params: Vec<String> = params
+            .split(',')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(|p| {
+                let (name, ty) = p.split_once(": ").unwrap();
+                declaration(ty, name)
+            })
+            .collect();
+        let params = if params.is_empty() { "void".to_string() } else { params.join(", ") };
+        format!("{ret} {name}({params});")
+    }
+
+    // Объявление поля или параметра; имена, совпадающие с ключевыми словами C++,
+    // получают подчёркивание (на раскладку это не влияет)
+    fn declaration(ty: &str, name: &str) -> String {
+        let name = match name {
+            "namespace" | "class" | "new" | "delete" | "template" => format!("{name}_"),
+            _ => name.to_string(),
+        };
+        // Указатель на функцию: Option<extern "C" fn(A, B) -> R>
+        if let Some(signature) = ty.strip_prefix("Option<extern \"C\" fn(") {
+            let (params, ret) = signature.trim_end_matches('>').rsplit_once(')').unwrap();
+            let ret = ret.trim().strip_prefix("-> ").map_or("void".to_string(), c_type);
+            let params: Vec<String> = params.split(", ").map(c_type).collect();
+            return format!("{ret} (*{name})({})", params.join(", "));
+        }
+        let ty = c_type(ty);
+        match ty.ends_with('*') {
+            true => format!("{ty}{name}"),
+            false => format!("{ty} {name}"),
+        }
+    }
+
+    fn c_type(ty: &str) -> String {
+        if let Some(inner) = ty.strip_prefix("*mut ") {
+            let inner = c_type(inner);
+            return match inner.ends_with('*') {
+                true => format!("{inner}*"),
+                false => format!("{inner} *"),
+            };
+        }
+        if let Some(inner) = ty.strip_prefix("*const ") {
+            let inner = c_type(inner);
+            return match inner.ends_with('*') {
+                true => format!("{inner}const *"),
+                false => format!("const {inner} *"),
+            };
+        }
+        match ty {
+            "i32" => "int32_t".to_string(),
+            "u32" => "uint32_t".to_string(),
+            "u16" => "uint16_t".to_string(),
+            "u8" => "uint8_t".to_string(),
+            "usize" => "size_t".to_string(),
+            "bool" => "bool".to_string(),
+            "c_char" => "char".to_string(),
+            "c_void" => "void".to_string(),
+            other => c_name(other),
+        }
+    }
+
+    // Имена типов в C получают префикс Xaml
+    fn c_name(name: &str) -> String {
+        match name.starts_with("Xaml") {
+            true => name.to_string(),
+            false => format!("Xaml{name}"),
+        }
+    }
+
+    fn screaming(name: &str) -> String {
+        let mut out = String::new();
+        for (i, c) in name.char_indices() {
+            if c.is_ascii_uppercase() && i > 0 && !name[..i].ends_with(|p: char| p.is_ascii_uppercase() || p.is_ascii_digit()) {
+                out.push('_');
+            }
+            out.push(c.to_ascii_uppercase());
+        }
+        out
+    }
+}
@@ -0,0 +1,466 @@
+use super::*;
+
+// Дерево результата в нескольких непрерывных блоках: по одному на элементы,
+// атрибуты, узлы, массивы дочерних указателей, элементы свойств, объявления
+// пространств имён и таблицу строк.
+// Структуры те же, что и в обычном дереве, поэтому код чтения на стороне хоста
+// не меняется. Освобождение — несколько вызовов free независимо от размера дерева.
+pub struct XamlArena {
+    elements: Block<XamlElement>,
+    attributes: Block<XamlAttribute>,
+    nodes: Block<XamlNode>,
+    children: Block<*mut XamlElement>,
+    properties: Block<XamlPropertyElement>,
+    namespaces: Block<XamlNamespace>,
+    // Строки в формате strings.rs: длина, байты и NUL, выровненные по usize
+    strings: Block<usize>,
+}
+
+// Блок фиксированного размера. Все записи идут через base, полученный один раз,
+// поэтому выданные указатели остаются действительными, пока жив блок.
+struct Block<T> {
+    _data: Vec<T>,
+    base: *mut T,
+    next: usize,
+    len: usize,
+}
+
+impl<T> Block<T> {
+    fn new(mut data: Vec<T>) -> Self {
+        Block {
+            base: data.as_mut_ptr(),
+            len: data.len(),
+            next: 0,
+            _data: data,
+        }
+    }
+
+    fn alloc(&mut self, n: usize) -> *mut T {
+        if n == 0 {
+            return std::ptr::null_mut();
+        }
+        assert!(self.next + n <= self.len, "arena block overflow");
+        let ptr = unsafe { self.base.add(self.next) };
+        self.next += n;
+        ptr
+    }
+}
+
+// Нулевые значения FFI-структур: все поля — указатели и числа
+fn zeroed_block<T>(len: usize) -> Block<T> {
+    Block::new((0..len).map(|_| unsafe { std::mem::zeroed() }).collect())
+}
+
+#[derive(Default)]
+struct Counts {
+    elements: usize,
+    attributes: usize,
+    nodes: usize,
+    children: usize,
+    properties: usize,
+    namespaces: usize,
+    string_words: usize,
+}
+
+impl Counts {
+    fn string(&mut self, s: Option<&str>) {
+        if let Some(s) = s {
+            self.string_words += string_words(s);
+        }
+    }
+
+    // Обход с явным стеком, как и в convert_to_xaml_element
+    fn tree(&mut self, root: &model::XamlElement) {
+        let mut pending = vec![root];
+        while let Some(element) = pending.pop() {
+            self.element(element, &mut pending);
+        }
+    }
+
+    fn element<'a>(&mut self, element: &'a model::XamlElement, pending: &mut Vec<&'a model::XamlElement>) {
+        self.elements += 1;
+        self.string(Some(&element.name));
+        self.string(element.namespace.as_deref());
+        self.string(element.prefix.as_deref());
+        self.string(element.leading_text().as_deref());
+        let d = element.directives();
+        for s in [&d.class, &d.subclass, &d.name, &d.key, &d.uid, &d.field_modifier, &d.type_arguments] {
+            self.string(s.as_deref());
+        }
+
+        self.namespaces += element.namespaces.len();
+        for namespace in &element.namespaces {
+            let clr = namespace.clr();
+            self.string(namespace.prefix.as_deref());
+            self.string(Some(&namespace.uri));
+            self.string(clr.as_ref().map(|c| c.namespace.as_str()));
+            self.string(clr.as_ref().and_then(|c| c.assembly.as_deref()));
+        }
+
+        self.attributes += element.attributes.len();
+        for attr in &element.attributes {
+            self.string(Some(&attr.name));
+            self.string(Some(&attr.value));
+            self.string(attr.namespace.as_deref());
+            self.string(attr.prefix.as_deref());
+            self.string(attr.owner_type.as_deref());
+            self.string(Some(attr.member()));
+        }
+
+        self.nodes += element.children.len();
+        for child in &element.children {
+            match child {
+                model::XamlNode::Element(child) => {
+                    self.children += 1;
+                    pending.push(child);
+                }
+                model::XamlNode::Text(s) | model::XamlNode::CData(s) | model::XamlNode::Comment(s) => {
+                    self.string(Some(s))
+                }
+                model::XamlNode::ProcessingInstruction { target, value } => {
+                    self.string(value.as_deref());
+                    self.string(Some(target));
+                }
+            }
+        }
+
+        self.properties += element.properties.len();
+        for property in &element.properties {
+            self.string(Some(&property.owner_type));
+            self.string(Some(&property.member));
+            pending.push(&property.element);
+        }
+    }
+}
+
+// Заголовок с длиной и байты с завершающим NUL, округлённые до usize
+fn string_words(s: &str) -> usize {
+    1 + (s.len() + 1).div_ceil(size_of::<usize>())
+}
+
+impl XamlArena {
+    // Строит дерево в арене; при XAML_ERROR_UNREPRESENTABLE_STRING арена не создаётся
+    pub(super) fn build(root: &model::XamlElement, flags: u32) -> Result<Box<XamlArena>, i32> {
+        let mut counts = Counts::default();
+        counts.tree(root);
+
+        let mut arena = Box::new(XamlArena {
+            elements: zeroed_block(counts.elements),
+            attributes: zeroed_block(counts.attributes),
+            nodes: zeroed_block(counts.nodes),
+            children: zeroed_block(counts.children),
+            properties: zeroed_block(counts.properties),
+            namespaces: zeroed_block(counts.namespaces),
+            strings: Block::new(vec![0; counts.string_words]),
+        });
+        let slot = arena.elements.alloc(1);
+        let mut builder = ArenaBuilder {
+            arena: &mut arena,
+            allow_nul: flags & XAML_FLAG_LENGTH_PREFIXED_STRINGS != 0,
+            unrepresentable: false,
+        };
+        builder.tree(slot, root);
+
+        if builder.unrepresentable {
+            return Err(XAML_ERROR_UNREPRESENTABLE_STRING);
+        }
+        Ok(arena)
+    }
+
+    pub(super) fn root(&self) -> *const XamlElement {
+        self.elements.base
+    }
+}
+
+struct ArenaBuilder<'a> {
+    arena: &'a mut XamlArena,
+    allow_nul: bool,
+    unrepresentable: bool,
+}
+
+impl ArenaBuilder<'_> {
+    fn string(&mut self, s: &str) -> *mut c_char {
+        if !self.allow_nul && s.contains('\0') {
+            self.unrepresentable = true;
+        }
+        let header = self.arena.strings.alloc(string_words(s));
+        unsafe {
+            header.write(s.len());
+            let data = header.add(1) as *mut u8;
+            // Остаток последнего слова уже заполнен нулями, в том числе завершающий NUL
+            std::ptr::copy_nonoverlapping(s.as_ptr(), data, s.len());
+            data as *mut c_char
+        }
+    }
+
+    fn string_or_null(&mut self, s: Option<&str>) -> *mut c_char {
+        match s {
+            Some(s) => self.string(s),
+            None => std::ptr::null_mut(),
+        }
+    }
+
+    fn tree(&mut self, slot: *mut XamlElement, root: &model::XamlElement) {
+        let mut pending = vec![(slot, root)];
+        while let Some((slot, element)) = pending.pop() {
+            self.fill(slot, element, &mut pending);
+        }
+    }
+
+    // Вложенным элементам выделяются места, заполняются они позже из pending
+    fn fill<'a>(
+        &mut self,
+        slot: *mut XamlElement,
+        element: &'a model::XamlElement,
+        pending: &mut Vec<(*mut XamlElement, &'a model::XamlElement)>,
+    ) {
+        let attributes = self.arena.attributes.alloc(element.attributes.len());
+        for (i, attr) in element.attributes.iter().enumerate() {
+            let value = XamlAttribute {
+                key: self.string(&attr.name),
+                value: self.string(&attr.value),
+                namespace: self.string_or_null(attr.namespace.as_deref()),
+                prefix: self.string_or_null(attr.prefix.as_deref()),
+                span: attr.span,
+                value_span: attr.value_span,
+                kind: attr.kind as i32,
+                owner_type: self.string_or_null(attr.owner_type.as_deref()),
+                member: self.string(attr.member()),
+            };
+            unsafe { attributes.add(i).write(value) };
+        }
+
+        let namespaces = self.arena.namespaces.alloc(element.namespaces.len());
+        for (i, namespace) in element.namespaces.iter().enumerate() {
+            let clr = namespace.clr();
+            let value = XamlNamespace {
+                prefix: self.string_or_null(namespace.prefix.as_deref()),
+                uri: self.string(&namespace.uri),
+                clr_namespace: self.string_or_null(clr.as_ref().map(|c| c.namespace.as_str())),
+                assembly: self.string_or_null(clr.as_ref().and_then(|c| c.assembly.as_deref())),
+            };
+            unsafe { namespaces.add(i).write(value) };
+        }
+
+        let children_len = element.elements().count();
+        let children = self.arena.children.alloc(children_len);
+        let nodes = self.arena.nodes.alloc(element.children.len());
+        let mut child_index = 0;
+        for (i, child) in element.children.iter().enumerate() {
+            let node = match child {
+                model::XamlNode::Element(child) => {
+                    let child_slot = self.arena.elements.alloc(1);
+                    unsafe { children.add(child_index).write(child_slot) };
+                    child_index += 1;
+                    pending.push((child_slot, child));
+                    self.node(NodeKind::Element, child_slot, None, None)
+                }
+                model::XamlNode::Text(s) => self.node(NodeKind::Text, std::ptr::null_mut(), Some(s), None),
+                model::XamlNode::CData(s) => self.node(NodeKind::CData, std::ptr::null_mut(), Some(s), None),
+                model::XamlNode::Comment(s) => self.node(NodeKind::Comment, std::ptr::null_mut(), Some(s), None),
+                model::XamlNode::ProcessingInstruction { target, value } => self.node(
+                    NodeKind::ProcessingInstruction,
+                    std::ptr::null_mut(),
+                    value.as_deref(),
+                    Some(target),
+                ),
+            };
+            unsafe { nodes.add(i).write(node) };
+        }
+
+        let before = elements_before(element);
+        let properties = self.arena.properties.alloc(element.properties.len());
+        for (i, property) in element.properties.iter().enumerate() {
+            let property_slot = self.arena.elements.alloc(1);
+            pending.push((property_slot, &property.element));
+            let node_index = property.index.min(element.children.len());
+            let value = XamlPropertyElement {
+                owner_type: self.string(&property.owner_type),
+                member: self.string(&property.member),
+                element: property_slot,
+                node_index,
+                child_index: before[node_index],
+            };
+            unsafe { properties.add(i).write(value) };
+        }
+
+        let value = XamlElement {
+            name: self.string(&element.name),
+            namespace: self.string_or_null(element.namespace.as_deref()),
+            attributes,
+            attributes_len: element.attributes.len(),
+            children,
+            children_len,
+            text_content: self.string_or_null(element.leading_text().as_deref()),
+            prefix: self.string_or_null(element.prefix.as_deref()),
+            nodes,
+            nodes_len: element.children.len(),
+            span: element.span,
+            start_tag_span: element.start_tag_span,
+            properties,
+            properties_len: element.properties.len(),
+            namespaces,
+            namespaces_len: element.namespaces.len(),
+            directives: XamlDirectives::new(element.directives(), |s| self.string_or_null(s)),
+        };
+        unsafe { slot.write(value) };
+    }
+
+    fn node(&mut self, kind: NodeKind, element: *mut XamlElement, text: Option<&str>, target: Option<&str>) -> XamlNode {
+        XamlNode {
+            kind: kind as i32,
+            element,
+            text: self.string_or_null(text),
+            target: self.string_or_null(target),
+        }
+    }
+}
+
+// Парсинг UTF-8 текста в дерево-арену. В root пишется корневой элемент,
+// он действителен до free_xaml_arena; free_xaml_element для него не вызывается.
+#[unsafe(no_mangle)]
+pub extern "C" fn parse_xaml_arena(
+    xml: *const u8,
+    len: usize,
+    flags: u32,
+    result: *mut *mut XamlArena,
+    root: *mut *const XamlElement,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    guard(|| {
+        clear_error(error);
+        if xml.is_null() || result.is_null() || root.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+
+        match std::str::from_utf8(unsafe { std::slice::from_raw_parts(xml, len) }) {
+            Ok(xml_str) => parse_arena_str(xml_str, flags, result, root, error),
+            Err(_) => XAML_ERROR_INVALID_UTF8,
+        }
+    })
+}
+
+// То же для UTF-16 текста длиной len кодовых единиц
+#[unsafe(no_mangle)]
+pub extern "C" fn parse_xaml_arena_utf16(
+    xml: *const u16,
+    len: usize,
+    flags: u32,
+    result: *mut *mut XamlArena,
+    root: *mut *const XamlElement,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    guard(|| {
+        clear_error(error);
+        if xml.is_null() || result.is_null() || root.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+
+        match String::from_utf16(unsafe { std::slice::from_raw_parts(xml, len) }) {
+            Ok(xml_str) => parse_arena_str(&xml_str, flags, result, root, error),
+            Err(_) => XAML_ERROR_INVALID_UTF8,
+        }
+    })
+}
+
+fn parse_arena_str(
+    xml_str: &str,
+    flags: u32,
+    result: *mut *mut XamlArena,
+    root: *mut *const XamlElement,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    let doc = match XamlDocument::parse_with_options(xml_str, &flag_options(flags)) {
+        Ok(doc) => doc,
+        Err(e) => {
+            set_error(error, &e);
+            return error_code(&e);
+        }
+    };
+    match XamlArena::build(&doc.root, flags) {
+        Ok(arena) => {
+            unsafe {
+                *root = arena.root();
+                *result = Box::into_raw(arena);
+            }
+            XAML_OK
+        }
+        Err(code) => code,
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn free_xaml_arena(arena: *mut XamlArena) -> i32 {
+    guard(|| {
+        if arena.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+
+        unsafe {
+            drop(Box::from_raw(arena));
+        }
+        XAML_OK
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"<Window xmlns="urn:ui" xmlns:x="urn:x" x:Class="App.Main" Title="Главное">
+        <Window.Resources><Style x:Key="s"/></Window.Resources>
+        <Grid Grid.Row="1"><!-- c --><?pi data?><TextBlock>a &lt; <Run>b</Run><![CDATA[c]]></TextBlock></Grid>
+    </Window>"#;
+
+    #[test]
+    fn builds_same_tree_in_contiguous_blocks() {
+        let mut arena = std::ptr::null_mut();
+        let mut root = std::ptr::null();
+        let code = parse_xaml_arena(SOURCE.as_ptr(), SOURCE.len(), 0, &mut arena, &mut root, std::ptr::null_mut());
+        assert_eq!(code, XAML_OK);
+
+        let expected = XamlDocument::parse(SOURCE).unwrap().root;
+        assert_eq!(unsafe { read_xaml_element(&*root) }, Ok(expected));
+
+        let blocks = unsafe { &*arena };
+        assert_eq!(blocks.elements.next, blocks.elements.len);
+        assert_eq!(blocks.attributes.next, blocks.attributes.len);
+        assert_eq!(blocks.nodes.next, blocks.nodes.len);
+        assert_eq!(blocks.children.next, blocks.children.len);
+        assert_eq!(blocks.properties.next, blocks.properties.len);
+        assert_eq!(blocks.strings.next, blocks.strings.len);
+        assert_eq!(blocks.elements.len, 6);
+
+        let title = unsafe { &*(*root).attributes.add(1) };
+        let mut len = 0;
+        assert_eq!(xaml_string_length(title.value, &mut len), XAML_OK);
+        assert_eq!(len, "Главное".len());
+        assert_eq!(free_xaml_arena(arena), XAML_OK);
+    }
+
+    #[test]
+    fn validates_directives_when_flagged() {
+        let xml = super::super::tests::MISPLACED_CLASS;
+        let mut arena = std::ptr::null_mut();
+        let mut root = std::ptr::null();
+        let mut error = std::ptr::null_mut();
+        assert_eq!(parse_xaml_arena(xml.as_ptr(), xml.len(), 0, &mut arena, &mut root, &mut error), XAML_OK);
+        assert_eq!(free_xaml_arena(arena), XAML_OK);
+
+        arena = std::ptr::null_mut();
+        let flags = XAML_FLAG_VALIDATE_DIRECTIVES;
+        let code = parse_xaml_arena(xml.as_ptr(), xml.len(), flags, &mut arena, &mut root, &mut error);
+        assert_eq!(code, XAML_ERROR_PARSE);
+        assert!(arena.is_null());
+        assert_eq!(unsafe { (*error).kind }, ErrorKind::Directive as i32);
+        assert_eq!(free_xaml_parse_error(error), XAML_OK);
+    }
+
+    #[test]
+    fn rejects_interior_nul_unless_length_prefixed() {
+        let mut root = model::XamlElement::new("TextBlock");
+        root.attributes.push(model::XamlAttribute::new("Text", "a\0b"));
+        assert_eq!(XamlArena::build(&root, 0).err(), Some(XAML_ERROR_UNREPRESENTABLE_STRING));
+        assert!(XamlArena::build(&root, XAML_FLAG_LENGTH_PREFIXED_STRINGS).is_ok());
+    }
+}
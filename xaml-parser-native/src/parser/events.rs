@@ -0,0 +1,222 @@
+use super::*;
+use crate::stream::{XamlEvent, XamlReader};
+use std::ffi::c_void;
+
+// Данные события потокового разбора. Строки — (указатель, длина) UTF-8 без NUL,
+// действительны только во время вызова; null — значения нет.
+// value — текст, значение атрибута или инструкции обработки; name — цель инструкции.
+// start..end — байтовый диапазон разметки во входном буфере, depth — число
+// открытых элементов (для start_element и attribute — включая сам элемент,
+// для end_element — уже без него).
+#[repr(C)]
+pub struct XamlStreamEvent {
+    prefix: *const u8,
+    prefix_len: usize,
+    name: *const u8,
+    name_len: usize,
+    namespace: *const u8,
+    namespace_len: usize,
+    value: *const u8,
+    value_len: usize,
+    start: usize,
+    end: usize,
+    depth: u32,
+}
+
+// Таблица обработчиков parse_xaml_stream. Любой указатель может быть null —
+// такие события пропускаются. Ненулевой результат обработчика прерывает разбор.
+#[repr(C)]
+pub struct XamlEventHandler {
+    user_data: *mut c_void,
+    start_element: Option<extern "C" fn(*mut c_void, *const XamlStreamEvent) -> i32>,
+    attribute: Option<extern "C" fn(*mut c_void, *const XamlStreamEvent) -> i32>,
+    end_element: Option<extern "C" fn(*mut c_void, *const XamlStreamEvent) -> i32>,
+    text: Option<extern "C" fn(*mut c_void, *const XamlStreamEvent) -> i32>,
+    cdata: Option<extern "C" fn(*mut c_void, *const XamlStreamEvent) -> i32>,
+    comment: Option<extern "C" fn(*mut c_void, *const XamlStreamEvent) -> i32>,
+    processing_instruction: Option<extern "C" fn(*mut c_void, *const XamlStreamEvent) -> i32>,
+}
+
+fn parts(s: Option<&str>) -> (*const u8, usize) {
+    s.map_or((std::ptr::null(), 0), |s| (s.as_ptr(), s.len()))
+}
+
+impl XamlStreamEvent {
+    fn new(
+        prefix: Option<&str>,
+        name: Option<&str>,
+        namespace: Option<&str>,
+        value: Option<&str>,
+        range: &std::ops::Range<usize>,
+        depth: usize,
+    ) -> Self {
+        let (prefix, prefix_len) = parts(prefix);
+        let (name, name_len) = parts(name);
+        let (namespace, namespace_len) = parts(namespace);
+        let (value, value_len) = parts(value);
+        XamlStreamEvent {
+            prefix,
+            prefix_len,
+            name,
+            name_len,
+            namespace,
+            namespace_len,
+            value,
+            value_len,
+            start: range.start,
+            end: range.end,
+            depth: depth as u32,
+        }
+    }
+}
+
+// Потоковый разбор UTF-8 текста с вызовом обработчиков по мере чтения;
+// дерево не строится. XAML_ERROR_CANCELLED, если обработчик вернул не 0.
+#[unsafe(no_mangle)]
+pub extern "C" fn parse_xaml_stream(
+    xml: *const u8,
+    len: usize,
+    handler: *const XamlEventHandler,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    guard(|| {
+        clear_error(error);
+        if xml.is_null() || handler.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+
+        let xml_str = match std::str::from_utf8(unsafe { std::slice::from_raw_parts(xml, len) }) {
+            Ok(s) => s,
+            Err(_) => return XAML_ERROR_INVALID_UTF8,
+        };
+        let handler = unsafe { &*handler };
+        let mut depth = 0;
+        for event in XamlReader::new(xml_str) {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    set_error(error, &e);
+                    return XAML_ERROR_PARSE;
+                }
+            };
+            match event {
+                XamlEvent::StartElement { .. } => depth += 1,
+                XamlEvent::EndElement { .. } => depth -= 1,
+                _ => {}
+            }
+            let (callback, data) = match &event {
+                XamlEvent::StartElement {
+                    prefix,
+                    name,
+                    namespace,
+                    range,
+                } => (
+                    handler.start_element,
+                    XamlStreamEvent::new(*prefix, Some(name), namespace.as_deref(), None, range, depth),
+                ),
+                XamlEvent::Attribute {
+                    prefix,
+                    name,
+                    namespace,
+                    value,
+                    range,
+                } => (
+                    handler.attribute,
+                    XamlStreamEvent::new(*prefix, Some(name), namespace.as_deref(), Some(value), range, depth),
+                ),
+                XamlEvent::EndElement {
+                    prefix,
+                    name,
+                    namespace,
+                    range,
+                } => (
+                    handler.end_element,
+                    XamlStreamEvent::new(*prefix, Some(name), namespace.as_deref(), None, range, depth),
+                ),
+                XamlEvent::Text { text, range } => {
+                    (handler.text, XamlStreamEvent::new(None, None, None, Some(text), range, depth))
+                }
+                XamlEvent::CData { text, range } => {
+                    (handler.cdata, XamlStreamEvent::new(None, None, None, Some(text), range, depth))
+                }
+                XamlEvent::Comment { text, range } => {
+                    (handler.comment, XamlStreamEvent::new(None, None, None, Some(text), range, depth))
+                }
+                XamlEvent::ProcessingInstruction { target, value, range } => (
+                    handler.processing_instruction,
+                    XamlStreamEvent::new(None, Some(target), None, *value, range, depth),
+                ),
+            };
+            if let Some(callback) = callback
+                && callback(handler.user_data, &data) != 0
+            {
+                return XAML_ERROR_CANCELLED;
+            }
+        }
+        XAML_OK
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn event_str<'a>(ptr: *const u8, len: usize) -> &'a str {
+        unsafe { std::str::from_utf8(std::slice::from_raw_parts(ptr, len)).unwrap() }
+    }
+
+    extern "C" fn record(user_data: *mut c_void, event: *const XamlStreamEvent) -> i32 {
+        let log = unsafe { &mut *(user_data as *mut Vec<String>) };
+        let event = unsafe { &*event };
+        let name = unsafe { event_str(event.name, event.name_len) };
+        match event.value.is_null() {
+            true => log.push(format!("{}{name}", event.depth)),
+            false => log.push(format!("{name}={}", unsafe { event_str(event.value, event.value_len) })),
+        }
+        0
+    }
+
+    extern "C" fn stop(_: *mut c_void, _: *const XamlStreamEvent) -> i32 {
+        1
+    }
+
+    fn handler(log: &mut Vec<String>) -> XamlEventHandler {
+        XamlEventHandler {
+            user_data: log as *mut Vec<String> as *mut c_void,
+            start_element: Some(record),
+            attribute: Some(record),
+            end_element: Some(record),
+            text: None,
+            cdata: None,
+            comment: None,
+            processing_instruction: None,
+        }
+    }
+
+    #[test]
+    fn calls_handlers_in_order() {
+        let xml = "<Grid Width=\"10\"><Button Content=\"&quot;OK&quot;\"/>text</Grid>";
+        let mut log = Vec::new();
+        let handler = handler(&mut log);
+        assert_eq!(parse_xaml_stream(xml.as_ptr(), xml.len(), &handler, std::ptr::null_mut()), XAML_OK);
+        assert_eq!(log, ["1Grid", "Width=10", "2Button", "Content=\"OK\"", "1Button", "0Grid"]);
+    }
+
+    #[test]
+    fn stops_on_handler_result_and_parse_error() {
+        let xml = "<Grid><Button/></Grid>";
+        let mut log = Vec::new();
+        let mut handler = handler(&mut log);
+        handler.attribute = None;
+        handler.end_element = Some(stop);
+        assert_eq!(parse_xaml_stream(xml.as_ptr(), xml.len(), &handler, std::ptr::null_mut()), XAML_ERROR_CANCELLED);
+        assert_eq!(log, ["1Grid", "2Button"]);
+
+        let xml = "<Grid><Button></Grid>";
+        let mut error = std::ptr::null_mut();
+        assert_eq!(parse_xaml_stream(xml.as_ptr(), xml.len(), &handler, &mut error), XAML_ERROR_PARSE);
+        assert!(!error.is_null());
+        assert_eq!(unsafe { (*error).kind }, ErrorKind::UnclosedTag as i32);
+        free_xaml_parse_error(error);
+    }
+}
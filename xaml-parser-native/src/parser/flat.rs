@@ -0,0 +1,578 @@
+use super::*;
+use crate::span::LineIndex;
+use std::collections::HashMap;
+
+// Плоское представление дерева для чтения без маршалинга каждого узла:
+// массивы узлов и атрибутов со связями по индексам и строки в виде (offset, len).
+// Строки, совпадающие с исходным текстом, указывают в буфер, переданный
+// в parse_xaml_flat; остальные (раскрытые сущности, URI пространств имён)
+// лежат в буфере extra со смещением offset - input_len.
+
+// Индекс отсутствующего узла (parent корня, first_child листа и т.п.)
+pub const XAML_FLAT_NONE: u32 = u32::MAX;
+
+// Срез строки. offset == usize::MAX — значения нет (аналог null).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct XamlStr {
+    offset: usize,
+    len: usize,
+}
+
+impl XamlStr {
+    const NONE: XamlStr = XamlStr {
+        offset: usize::MAX,
+        len: 0,
+    };
+}
+
+// Узел в порядке документа. Для элементов name/namespace/prefix и атрибуты
+// first_attribute..first_attribute + attributes_len; для элементов свойств
+// заполнены owner_type и member. text — содержимое текста, CDATA и комментария,
+// значение инструкции обработки (name — её цель) или текст элемента до первого
+// дочернего узла другого вида.
+#[repr(C)]
+pub struct XamlFlatNode {
+    pub(super) kind: i32,
+    pub(super) parent: u32,
+    pub(super) first_child: u32,
+    pub(super) next_sibling: u32,
+    pub(super) first_attribute: u32,
+    pub(super) attributes_len: u32,
+    pub(super) name: XamlStr,
+    namespace: XamlStr,
+    prefix: XamlStr,
+    pub(super) text: XamlStr,
+    owner_type: XamlStr,
+    member: XamlStr,
+    pub(super) span: TextSpan,
+}
+
+#[repr(C)]
+pub struct XamlFlatAttribute {
+    element: u32,
+    kind: i32,
+    pub(super) name: XamlStr,
+    pub(super) value: XamlStr,
+    namespace: XamlStr,
+    pub(super) prefix: XamlStr,
+    owner_type: XamlStr,
+    member: XamlStr,
+    span: TextSpan,
+    value_span: TextSpan,
+}
+
+// Заголовок плоского документа; корень — nodes[0]
+#[repr(C)]
+pub struct XamlFlatDocument {
+    nodes: *const XamlFlatNode,
+    nodes_len: usize,
+    attributes: *const XamlFlatAttribute,
+    attributes_len: usize,
+    input_len: usize,
+    extra: *const u8,
+    extra_len: usize,
+}
+
+// Владелец массивов; заголовок идёт первым, поэтому указатель на FlatDocument
+// отдаётся хосту как указатель на XamlFlatDocument
+#[repr(C)]
+pub(super) struct FlatDocument {
+    header: XamlFlatDocument,
+    pub(super) nodes: Vec<XamlFlatNode>,
+    pub(super) attributes: Vec<XamlFlatAttribute>,
+    pub(super) extra: Vec<u8>,
+}
+
+impl FlatDocument {
+    pub(super) fn build(source: &str, root: &model::XamlElement) -> Box<FlatDocument> {
+        let mut builder = FlatBuilder {
+            source,
+            index: LineIndex::new(source),
+            nodes: Vec::new(),
+            attributes: Vec::new(),
+            extra: Vec::new(),
+            interned: HashMap::new(),
+        };
+        builder.element(root);
+
+        let mut doc = Box::new(FlatDocument {
+            header: XamlFlatDocument {
+                nodes: std::ptr::null(),
+                nodes_len: builder.nodes.len(),
+                attributes: std::ptr::null(),
+                attributes_len: builder.attributes.len(),
+                input_len: source.len(),
+                extra: std::ptr::null(),
+                extra_len: builder.extra.len(),
+            },
+            nodes: builder.nodes,
+            attributes: builder.attributes,
+            extra: builder.extra,
+        });
+        doc.header.nodes = doc.nodes.as_ptr();
+        doc.header.attributes = doc.attributes.as_ptr();
+        doc.header.extra = doc.extra.as_ptr();
+        doc
+    }
+
+    // Строка среза; input — исходный текст, по которому строился документ
+    #[cfg(test)]
+    pub(super) fn str<'a>(&'a self, input: &'a str, s: XamlStr) -> Option<&'a str> {
+        if s.offset == usize::MAX {
+            return None;
+        }
+        let text = if s.offset < input.len() || s.len == 0 && s.offset == input.len() {
+            input.get(s.offset..s.offset + s.len)
+        } else {
+            let start = s.offset - input.len();
+            self.extra.get(start..start + s.len).and_then(|bytes| std::str::from_utf8(bytes).ok())
+        };
+        Some(text.unwrap_or_default())
+    }
+}
+
+// Элемент в обходе FlatBuilder::element: следующий дочерний узел и элемент
+// свойства, конец последнего записанного узла и последний связанный дочерний
+struct Frame<'a> {
+    element: &'a model::XamlElement,
+    index: u32,
+    next: usize,
+    property: usize,
+    cursor: usize,
+    last: u32,
+}
+
+impl<'a> Frame<'a> {
+    fn new(element: &'a model::XamlElement, index: u32) -> Self {
+        Frame {
+            element,
+            index,
+            next: 0,
+            property: 0,
+            cursor: element.start_tag_span.end,
+            last: XAML_FLAT_NONE,
+        }
+    }
+}
+
+struct FlatBuilder<'a> {
+    source: &'a str,
+    index: LineIndex<'a>,
+    nodes: Vec<XamlFlatNode>,
+    attributes: Vec<XamlFlatAttribute>,
+    extra: Vec<u8>,
+    // Одинаковые строки из extra (чаще всего URI пространств имён) хранятся один раз
+    interned: HashMap<String, usize>,
+}
+
+impl FlatBuilder<'_> {
+    fn slice_or_extra(&mut self, at: usize, value: &str) -> XamlStr {
+        if self.source.get(at..at.saturating_add(value.len())) == Some(value) {
+            return XamlStr {
+                offset: at,
+                len: value.len(),
+            };
+        }
+        let start = match self.interned.get(value) {
+            Some(&start) => start,
+            None => {
+                let start = self.extra.len();
+                self.extra.extend_from_slice(value.as_bytes());
+                self.interned.insert(value.to_string(), start);
+                start
+            }
+        };
+        XamlStr {
+            offset: self.source.len() + start,
+            len: value.len(),
+        }
+    }
+
+    fn extra(&mut self, value: Option<&str>) -> XamlStr {
+        match value {
+            // usize::MAX заведомо вне исходного текста
+            Some(value) => self.slice_or_extra(usize::MAX, value),
+            None => XamlStr::NONE,
+        }
+    }
+
+    fn push(&mut self, kind: NodeKind, parent: u32, span: TextSpan) -> u32 {
+        let index = self.nodes.len() as u32;
+        self.nodes.push(XamlFlatNode {
+            kind: kind as i32,
+            parent,
+            first_child: XAML_FLAT_NONE,
+            next_sibling: XAML_FLAT_NONE,
+            first_attribute: self.attributes.len() as u32,
+            attributes_len: 0,
+            name: XamlStr::NONE,
+            namespace: XamlStr::NONE,
+            prefix: XamlStr::NONE,
+            text: XamlStr::NONE,
+            owner_type: XamlStr::NONE,
+            member: XamlStr::NONE,
+            span,
+        });
+        index
+    }
+
+    // Узел элемента с атрибутами, без содержимого
+    fn open(
+        &mut self,
+        element: &model::XamlElement,
+        parent: u32,
+        property: Option<&model::XamlPropertyElement>,
+    ) -> u32 {
+        let index = self.push(NodeKind::Element, parent, element.span);
+        let qname_at = element.span.start + 1;
+        let name_at = qname_at + element.prefix.as_ref().map_or(0, |p| p.len() + 1);
+        let name = self.slice_or_extra(name_at, &element.name);
+        let namespace = self.extra(element.namespace.as_deref());
+        let prefix = match &element.prefix {
+            Some(prefix) => self.slice_or_extra(qname_at, prefix),
+            None => XamlStr::NONE,
+        };
+        let (owner_type, member) = match property {
+            Some(property) => (
+                self.slice_or_extra(name_at, &property.owner_type),
+                self.slice_or_extra(name_at + property.owner_type.len() + 1, &property.member),
+            ),
+            None => (XamlStr::NONE, XamlStr::NONE),
+        };
+        let node = &mut self.nodes[index as usize];
+        node.name = name;
+        node.namespace = namespace;
+        node.prefix = prefix;
+        node.owner_type = owner_type;
+        node.member = member;
+        node.attributes_len = element.attributes.len() as u32;
+
+        for attr in &element.attributes {
+            let name_at = attr.span.start + attr.prefix.as_ref().map_or(0, |p| p.len() + 1);
+            let member = attr.member();
+            let attribute = XamlFlatAttribute {
+                element: index,
+                kind: attr.kind as i32,
+                name: self.slice_or_extra(name_at, &attr.name),
+                value: self.slice_or_extra(attr.value_span.start, &attr.value),
+                namespace: self.extra(attr.namespace.as_deref()),
+                prefix: match &attr.prefix {
+                    Some(prefix) => self.slice_or_extra(attr.span.start, prefix),
+                    None => XamlStr::NONE,
+                },
+                owner_type: match &attr.owner_type {
+                    Some(owner) => self.slice_or_extra(name_at, owner),
+                    None => XamlStr::NONE,
+                },
+                member: self.slice_or_extra(name_at + attr.name.len() - member.len(), member),
+                span: attr.span,
+                value_span: attr.value_span,
+            };
+            self.attributes.push(attribute);
+        }
+
+        index
+    }
+
+    // Обход идёт со своим стеком, поэтому глубина дерева не ограничена
+    // стеком вызовов. Элементы свойств вынесены из children модели; они
+    // возвращаются на своё место по XamlPropertyElement::index.
+    fn element(&mut self, root: &model::XamlElement) {
+        let index = self.open(root, XAML_FLAT_NONE, None);
+        let mut stack = vec![Frame::new(root, index)];
+        while let Some(frame) = stack.last_mut() {
+            let element = frame.element;
+            let children = &element.children;
+            if let Some(property) = element.properties.get(frame.property)
+                && property.index.min(children.len()) <= frame.next
+            {
+                frame.property += 1;
+                frame.cursor = property.element.span.end;
+                let parent = frame.index;
+                let child = self.open(&property.element, parent, Some(property));
+                self.link(parent, &mut frame.last, child);
+                stack.push(Frame::new(&property.element, child));
+                continue;
+            }
+            let Some(child) = children.get(frame.next) else {
+                self.leading_text(element, frame.index);
+                stack.pop();
+                continue;
+            };
+            frame.next += 1;
+
+            let parent = frame.index;
+            let cursor = &mut frame.cursor;
+            let node = match child {
+                model::XamlNode::Element(child) => {
+                    *cursor = child.span.end;
+                    let i = self.open(child, parent, None);
+                    self.link(parent, &mut frame.last, i);
+                    stack.push(Frame::new(child, i));
+                    continue;
+                }
+                model::XamlNode::Text(text) => {
+                    let at = *cursor;
+                    let end = self.source[at..].find('<').map_or(self.source.len(), |n| at + n);
+                    let i = self.push(NodeKind::Text, parent, self.index.span(at..end));
+                    // Текст с сущностями или переводами строк \r\n не совпадает с исходным
+                    let raw_matches = &self.source[at..end] == text;
+                    self.nodes[i as usize].text = if raw_matches {
+                        self.slice_or_extra(at, text)
+                    } else {
+                        self.extra(Some(text))
+                    };
+                    *cursor = end;
+                    i
+                }
+                model::XamlNode::CData(text) => self.markup(NodeKind::CData, parent, cursor, "<![CDATA[", "]]>", text),
+                model::XamlNode::Comment(text) => self.markup(NodeKind::Comment, parent, cursor, "<!--", "-->", text),
+                model::XamlNode::ProcessingInstruction { target, value } => {
+                    let i = self.markup(NodeKind::ProcessingInstruction, parent, cursor, "<?", "?>", "");
+                    let target_at = self.nodes[i as usize].span.start + 2;
+                    let name = self.slice_or_extra(target_at, target);
+                    let text = match value {
+                        Some(value) => {
+                            let body = &self.source[target_at..cursor.saturating_sub(2).max(target_at)];
+                            let value_at = target_at + body.find(value.as_str()).unwrap_or(0);
+                            self.slice_or_extra(value_at, value)
+                        }
+                        None => XamlStr::NONE,
+                    };
+                    self.nodes[i as usize].name = name;
+                    self.nodes[i as usize].text = text;
+                    i
+                }
+            };
+            self.link(parent, &mut frame.last, node);
+        }
+    }
+
+    // Текст элемента — как text_content в FFI; одиночный текстовый узел
+    // переиспользует срез, склеенный текст уходит в extra
+    fn leading_text(&mut self, element: &model::XamlElement, index: u32) {
+        let leading = element
+            .children
+            .iter()
+            .take_while(|c| matches!(c, model::XamlNode::Text(_) | model::XamlNode::CData(_)))
+            .count();
+        let first = self.nodes.get(self.nodes[index as usize].first_child as usize);
+        let text = match (leading, first) {
+            (0, _) => XamlStr::NONE,
+            (1, Some(first)) if first.kind == NodeKind::Text as i32 || first.kind == NodeKind::CData as i32 => first.text,
+            _ => self.extra(element.leading_text().as_deref()),
+        };
+        self.nodes[index as usize].text = text;
+    }
+
+    // Узел вида open..close; позиция сдвигается за него. Если разметка не найдена
+    // на текущей позиции, текст берётся из extra, а span остаётся пустым.
+    fn markup(&mut self, kind: NodeKind, parent: u32, cursor: &mut usize, open: &str, close: &str, text: &str) -> u32 {
+        let at = *cursor;
+        let content_at = at + open.len();
+        let end = match self.source[at..].starts_with(open) {
+            true => self.source[content_at..].find(close).map(|n| content_at + n),
+            false => None,
+        };
+        match end {
+            Some(end) => {
+                *cursor = end + close.len();
+                let i = self.push(kind, parent, self.index.span(at..*cursor));
+                self.nodes[i as usize].text = if &self.source[content_at..end] == text {
+                    self.slice_or_extra(content_at, text)
+                } else {
+                    self.extra(Some(text))
+                };
+                i
+            }
+            None => {
+                let i = self.push(kind, parent, TextSpan::default());
+                self.nodes[i as usize].text = self.extra(Some(text));
+                i
+            }
+        }
+    }
+
+    fn link(&mut self, parent: u32, last: &mut u32, child: u32) {
+        if *last == XAML_FLAT_NONE {
+            self.nodes[parent as usize].first_child = child;
+        } else {
+            self.nodes[*last as usize].next_sibling = child;
+        }
+        *last = child;
+    }
+}
+
+// Парсинг UTF-8 текста в плоское представление. Срезы строк указывают в xml,
+// поэтому буфер должен жить, пока используется результат.
+// Освобождается через free_xaml_flat.
+#[unsafe(no_mangle)]
+pub extern "C" fn parse_xaml_flat(
+    xml: *const u8,
+    len: usize,
+    result: *mut *mut XamlFlatDocument,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    guard(|| parse_flat(xml, len, &ParseOptions::default(), result, error))
+}
+
+// То же с параметрами разбора; options может быть null
+#[unsafe(no_mangle)]
+pub extern "C" fn parse_xaml_flat_with_options(
+    xml: *const u8,
+    len: usize,
+    options: *const XamlParseOptions,
+    result: *mut *mut XamlFlatDocument,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    guard(|| {
+        clear_error(error);
+        match unsafe { read_parse_options(options) } {
+            Ok((_, options)) => parse_flat(xml, len, &options, result, error),
+            Err(code) => code,
+        }
+    })
+}
+
+fn parse_flat(
+    xml: *const u8,
+    len: usize,
+    options: &ParseOptions,
+    result: *mut *mut XamlFlatDocument,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    clear_error(error);
+    if xml.is_null() || result.is_null() {
+        return XAML_ERROR_NULL_ARGUMENT;
+    }
+
+    let xml_str = match std::str::from_utf8(unsafe { std::slice::from_raw_parts(xml, len) }) {
+        Ok(s) => s,
+        Err(_) => return XAML_ERROR_INVALID_UTF8,
+    };
+    match XamlDocument::parse_with_options(xml_str, options) {
+        Ok(doc) => {
+            let flat = FlatDocument::build(xml_str, &doc.root);
+            unsafe { *result = Box::into_raw(flat) as *mut XamlFlatDocument };
+            XAML_OK
+        }
+        Err(e) => {
+            set_error(error, &e);
+            error_code(&e)
+        }
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn free_xaml_flat(doc: *mut XamlFlatDocument) -> i32 {
+    guard(|| {
+        if doc.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+
+        unsafe {
+            drop(Box::from_raw(doc as *mut FlatDocument));
+        }
+        XAML_OK
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "<Grid xmlns=\"urn:ui\" xmlns:x=\"urn:x\" x:Name=\"root\" Grid.Row=\"a &amp; b\">\r\n\
+        \x20 <Grid.RowDefinitions><RowDefinition/></Grid.RowDefinitions>\n\
+        \x20 <!-- note --><?pi some data?><![CDATA[<raw>]]>tail &lt;\n\
+        \x20 <x:Button Content=\"OK\"/>\n\
+        </Grid>";
+
+    fn build() -> Box<FlatDocument> {
+        let mut result = std::ptr::null_mut();
+        assert_eq!(parse_xaml_flat(SOURCE.as_ptr(), SOURCE.len(), &mut result, std::ptr::null_mut()), XAML_OK);
+        unsafe { Box::from_raw(result as *mut FlatDocument) }
+    }
+
+    fn children(doc: &FlatDocument, parent: u32) -> Vec<u32> {
+        let mut children = Vec::new();
+        let mut child = doc.nodes[parent as usize].first_child;
+        while child != XAML_FLAT_NONE {
+            assert_eq!(doc.nodes[child as usize].parent, parent);
+            children.push(child);
+            child = doc.nodes[child as usize].next_sibling;
+        }
+        children
+    }
+
+    #[test]
+    fn links_nodes_in_document_order() {
+        let doc = build();
+        let s = |x| doc.str(SOURCE, x);
+        let root = &doc.nodes[0];
+        assert_eq!(root.parent, XAML_FLAT_NONE);
+        assert_eq!(s(root.name), Some("Grid"));
+        assert_eq!(s(root.namespace), Some("urn:ui"));
+
+        let kinds: Vec<_> = children(&doc, 0).iter().map(|&i| doc.nodes[i as usize].kind).collect();
+        use NodeKind::*;
+        let expected = [Text, Element, Text, Comment, ProcessingInstruction, CData, Text, Element, Text];
+        assert_eq!(kinds, expected.map(|k| k as i32));
+
+        let nodes = children(&doc, 0);
+        let rows = &doc.nodes[nodes[1] as usize];
+        assert_eq!(s(rows.name), Some("Grid.RowDefinitions"));
+        assert_eq!((s(rows.owner_type), s(rows.member)), (Some("Grid"), Some("RowDefinitions")));
+        assert_eq!(children(&doc, nodes[1]).len(), 1);
+
+        let pi = &doc.nodes[nodes[4] as usize];
+        assert_eq!((s(pi.name), s(pi.text)), (Some("pi"), Some("some data")));
+        assert_eq!(&SOURCE[pi.span.range()], "<?pi some data?>");
+        assert_eq!(s(doc.nodes[nodes[5] as usize].text), Some("<raw>"));
+        assert_eq!(s(doc.nodes[nodes[6] as usize].text), Some("tail <\n  "));
+
+        let button = &doc.nodes[nodes[7] as usize];
+        assert_eq!((s(button.prefix), s(button.name)), (Some("x"), Some("Button")));
+        assert_eq!(s(button.namespace), Some("urn:x"));
+        assert_eq!(s(root.text), Some("\n  \n  "));
+        assert_eq!(s(button.text), None);
+    }
+
+    #[test]
+    fn slices_source_and_spills_decoded_strings() {
+        let doc = build();
+        let attrs = &doc.attributes[..2];
+        assert_eq!(doc.str(SOURCE, attrs[0].prefix), Some("x"));
+        assert_eq!(doc.str(SOURCE, attrs[0].name), Some("Name"));
+        assert!(attrs[0].value.offset < SOURCE.len());
+        assert_eq!(doc.str(SOURCE, attrs[1].owner_type), Some("Grid"));
+        assert_eq!(doc.str(SOURCE, attrs[1].member), Some("Row"));
+        assert_eq!(doc.str(SOURCE, attrs[1].value), Some("a & b"));
+        assert!(attrs[1].value.offset >= SOURCE.len());
+
+        // В extra только URI, раскрытые сущности и текст с \r\n; повторы не дублируются
+        let extra = std::str::from_utf8(&doc.extra).unwrap();
+        assert_eq!(extra.matches("urn:ui").count(), 1);
+        assert!(!extra.contains("Grid"));
+    }
+
+    #[test]
+    fn builds_deep_documents_within_options() {
+        let depth = 100_000;
+        let xml = format!("{}<a/>{}", "<a>\n".repeat(depth - 1), "\n</a>".repeat(depth - 1));
+        let mut result = std::ptr::null_mut();
+        assert_eq!(parse_xaml_flat(xml.as_ptr(), xml.len(), &mut result, std::ptr::null_mut()), XAML_OK);
+        let doc = unsafe { Box::from_raw(result as *mut FlatDocument) };
+        let elements = doc.nodes.iter().filter(|n| n.kind == NodeKind::Element as i32).count();
+        assert_eq!(elements, depth);
+        let leaf = doc.nodes.iter().rposition(|n| n.kind == NodeKind::Element as i32).unwrap();
+        assert_eq!(doc.nodes[leaf].span.start_line as usize, depth);
+        drop(doc);
+
+        let mut options: XamlParseOptions = unsafe { std::mem::zeroed() };
+        options.size = size_of::<XamlParseOptions>();
+        options.max_depth = 10;
+        let code = parse_xaml_flat_with_options(xml.as_ptr(), xml.len(), &options, &mut result, std::ptr::null_mut());
+        assert_eq!(code, XAML_ERROR_LIMIT_EXCEEDED);
+    }
+}
@@ -0,0 +1,638 @@
+use super::flat::XAML_FLAT_NONE;
+use super::*;
+use crate::lexer::{Lexer, TokenKind};
+use crate::options;
+use crate::span::LineStarts;
+use crate::text;
+use std::borrow::Cow;
+use std::ops::Range;
+use roxmltree::NodeType;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+// Непрозрачный документ с навигацией по узлам без выгрузки всего дерева
+// в FFI-структуры. Узлы адресуются индексами в порядке документа (корень — 0),
+// строки отдаются как (указатель, длина) UTF-8 без NUL в конце и живут,
+// пока не вызван free_xaml_doc_handle.
+//
+// При разборе строится только индекс узлов: вид, родитель, диапазон в тексте
+// и пространство имён элемента. Имя, префикс, атрибуты и текст узла
+// разбираются из исходника при первом обращении. Документ остаётся таким,
+// как записан: Markup Compatibility и проверка директив не применяются.
+pub struct XamlDocHandle {
+    source: String,
+    lines: LineStarts,
+    nodes: Vec<IndexNode>,
+    // URI пространств имён элементов без повторов; IndexNode::namespace — индекс в нём
+    namespaces: Vec<String>,
+    details: Vec<OnceLock<Box<Details>>>,
+    // Дочерние узлы подряд: узла i — children[child_start[i]..child_start[i + 1]]
+    child_start: Vec<u32>,
+    children: Vec<u32>,
+}
+
+struct IndexNode {
+    kind: NodeKind,
+    parent: u32,
+    range: Range<usize>,
+    namespace: u32,
+}
+
+// Строка узла: срез исходника или раскрытое значение
+#[derive(Clone)]
+enum Str {
+    Source(Range<usize>),
+    Owned(String),
+}
+
+#[derive(Default)]
+struct Details {
+    name: Option<Str>,
+    prefix: Option<Str>,
+    // Имя атрибута в записи документа и его значение; объявления xmlns не входят
+    attributes: Vec<(Range<usize>, Str)>,
+    text: Option<Str>,
+}
+
+impl XamlDocHandle {
+    // Один разбор roxmltree проверяет документ и даёт узлы с диапазонами
+    // и пространствами имён; само дерево roxmltree не сохраняется
+    fn parse(source: String, options: &ParseOptions) -> Result<Box<XamlDocHandle>, ParseError> {
+        let doc = options::parse_xml(&source, options)?;
+        let mut nodes: Vec<IndexNode> = Vec::new();
+        let mut namespaces: Vec<String> = Vec::new();
+        let mut interned: HashMap<&str, u32> = HashMap::new();
+        // Индекс узла по NodeId roxmltree; родитель попадает в индекс раньше детей
+        let mut ids: Vec<u32> = Vec::new();
+        let root = doc.root_element();
+        for node in root.descendants() {
+            let parent = match node == root {
+                true => XAML_FLAT_NONE,
+                false => node.parent().map_or(XAML_FLAT_NONE, |p| ids[p.id().get_usize()]),
+            };
+            let push = |nodes: &mut Vec<IndexNode>, kind, range, namespace| {
+                nodes.push(IndexNode {
+                    kind,
+                    parent,
+                    range,
+                    namespace,
+                })
+            };
+            let kind = match node.node_type() {
+                NodeType::Element => NodeKind::Element,
+                NodeType::Comment => NodeKind::Comment,
+                NodeType::PI => NodeKind::ProcessingInstruction,
+                // roxmltree склеивает текст с соседними CDATA; они разделяются по исходнику
+                NodeType::Text => {
+                    let range = text::text_node_range(node);
+                    let raw = &source[range.clone()];
+                    if !raw.contains("<![CDATA[") {
+                        push(&mut nodes, NodeKind::Text, range, XAML_FLAT_NONE);
+                        continue;
+                    }
+                    for token in Lexer::new(raw).flatten() {
+                        let kind = match token.kind {
+                            TokenKind::CData { .. } => NodeKind::CData,
+                            _ => NodeKind::Text,
+                        };
+                        let token_range = range.start + token.range.start..range.start + token.range.end;
+                        push(&mut nodes, kind, token_range, XAML_FLAT_NONE);
+                    }
+                    continue;
+                }
+                NodeType::Root => continue,
+            };
+            let namespace = match node.tag_name().namespace() {
+                Some(uri) => *interned.entry(uri).or_insert_with(|| {
+                    namespaces.push(uri.to_string());
+                    namespaces.len() as u32 - 1
+                }),
+                None => XAML_FLAT_NONE,
+            };
+            let id = node.id().get_usize();
+            if ids.len() <= id {
+                ids.resize(id + 1, XAML_FLAT_NONE);
+            }
+            ids[id] = nodes.len() as u32;
+            push(&mut nodes, kind, node.range(), namespace);
+        }
+        drop(interned);
+        drop(doc);
+
+        // Узлы идут в порядке документа, поэтому раскладка по родителям
+        // сохраняет порядок дочерних
+        let mut child_start = vec![0u32; nodes.len() + 1];
+        for node in nodes.iter().skip(1) {
+            child_start[node.parent as usize + 1] += 1;
+        }
+        for i in 1..child_start.len() {
+            child_start[i] += child_start[i - 1];
+        }
+        let mut next = child_start.clone();
+        let mut children = vec![0u32; nodes.len().saturating_sub(1)];
+        for (i, node) in nodes.iter().enumerate().skip(1) {
+            let slot = &mut next[node.parent as usize];
+            children[*slot as usize] = i as u32;
+            *slot += 1;
+        }
+
+        Ok(Box::new(XamlDocHandle {
+            lines: LineStarts::new(&source),
+            details: (0..nodes.len()).map(|_| OnceLock::new()).collect(),
+            source,
+            nodes,
+            namespaces,
+            child_start,
+            children,
+        }))
+    }
+
+    fn node(&self, node: u32) -> Option<&IndexNode> {
+        self.nodes.get(node as usize)
+    }
+
+    fn children(&self, node: u32) -> &[u32] {
+        let i = node as usize;
+        &self.children[self.child_start[i] as usize..self.child_start[i + 1] as usize]
+    }
+
+    fn str<'a>(&'a self, s: Option<&'a Str>) -> Option<&'a str> {
+        match s? {
+            Str::Source(range) => Some(&self.source[range.clone()]),
+            Str::Owned(s) => Some(s),
+        }
+    }
+
+    fn span(&self, node: &IndexNode) -> TextSpan {
+        self.lines.span(&self.source, node.range.clone())
+    }
+
+    fn details(&self, node: u32) -> &Details {
+        self.details[node as usize].get_or_init(|| Box::new(self.materialize(node)))
+    }
+
+    // Разбор первого токена узла. Текст уже проверен при разборе документа,
+    // поэтому ошибки раскрытия сущностей здесь не возникают.
+    fn materialize(&self, node: u32) -> Details {
+        let range = self.nodes[node as usize].range.clone();
+        let Some(Ok(token)) = Lexer::new(&self.source[range.clone()]).next() else {
+            return Details::default();
+        };
+        let raw = &self.source[range];
+        match token.kind {
+            TokenKind::Text => Details {
+                text: Some(self.keep(text::text_value(raw).unwrap_or(Cow::Borrowed(raw)))),
+                ..Details::default()
+            },
+            TokenKind::CData { content } => Details {
+                text: Some(self.keep(text::cdata_value(content))),
+                ..Details::default()
+            },
+            TokenKind::Comment { content } => Details {
+                text: Some(self.keep(Cow::Borrowed(content))),
+                ..Details::default()
+            },
+            TokenKind::ProcessingInstruction { target, content } => Details {
+                name: Some(self.keep(Cow::Borrowed(target))),
+                text: content.map(|content| self.keep(Cow::Borrowed(content))),
+                ..Details::default()
+            },
+            TokenKind::StartTag(tag) => {
+                let (prefix, local) = match tag.name.split_once(':') {
+                    Some((prefix, local)) => (Some(prefix), local),
+                    None => (None, tag.name),
+                };
+                let attributes = tag
+                    .attributes
+                    .iter()
+                    .filter(|a| a.name != "xmlns" && !a.name.starts_with("xmlns:"))
+                    .map(|a| {
+                        let value = text::attribute_value(a.value).unwrap_or(Cow::Borrowed(a.value));
+                        (self.offset(a.name)..self.offset(a.name) + a.name.len(), self.keep(value))
+                    })
+                    .collect();
+                Details {
+                    name: Some(self.keep(Cow::Borrowed(local))),
+                    prefix: prefix.map(|prefix| self.keep(Cow::Borrowed(prefix))),
+                    attributes,
+                    text: self.leading_text(node),
+                }
+            }
+            TokenKind::Doctype | TokenKind::EndTag { .. } => Details::default(),
+        }
+    }
+
+    // Текст элемента до первого дочернего узла другого вида; одиночный
+    // текстовый узел переиспользует свою строку
+    fn leading_text(&self, node: u32) -> Option<Str> {
+        let leading: Vec<&Str> = self
+            .children(node)
+            .iter()
+            .take_while(|&&child| matches!(self.nodes[child as usize].kind, NodeKind::Text | NodeKind::CData))
+            .filter_map(|&child| self.details(child).text.as_ref())
+            .collect();
+        match leading.as_slice() {
+            [] => None,
+            [text] => Some((*text).clone()),
+            _ => Some(Str::Owned(leading.iter().filter_map(|&s| self.str(Some(s))).collect())),
+        }
+    }
+
+    fn offset(&self, s: &str) -> usize {
+        s.as_ptr() as usize - self.source.as_ptr() as usize
+    }
+
+    // Строка, совпадающая с частью исходника, хранится как диапазон
+    fn keep(&self, s: Cow<str>) -> Str {
+        match s {
+            Cow::Borrowed(s) => {
+                let start = self.offset(s);
+                Str::Source(start..start + s.len())
+            }
+            Cow::Owned(s) => Str::Owned(s),
+        }
+    }
+}
+
+// Разбор UTF-8 текста в XamlDocHandle. Текст копируется, буфер можно освободить сразу.
+#[unsafe(no_mangle)]
+pub extern "C" fn parse_xaml_handle(
+    xml: *const u8,
+    len: usize,
+    result: *mut *mut XamlDocHandle,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    guard(|| parse_handle_utf8(xml, len, &ParseOptions::default(), result, error))
+}
+
+// То же с ограничениями из options; options может быть null.
+// markup_compatibility и флаги к дескриптору не применяются.
+#[unsafe(no_mangle)]
+pub extern "C" fn parse_xaml_handle_with_options(
+    xml: *const u8,
+    len: usize,
+    options: *const XamlParseOptions,
+    result: *mut *mut XamlDocHandle,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    guard(|| {
+        clear_error(error);
+        match unsafe { read_parse_options(options) } {
+            Ok((_, options)) => parse_handle_utf8(xml, len, &options, result, error),
+            Err(code) => code,
+        }
+    })
+}
+
+// То же для UTF-16 текста длиной len кодовых единиц
+#[unsafe(no_mangle)]
+pub extern "C" fn parse_xaml_handle_utf16(
+    xml: *const u16,
+    len: usize,
+    result: *mut *mut XamlDocHandle,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    guard(|| parse_handle_utf16(xml, len, &ParseOptions::default(), result, error))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn parse_xaml_handle_utf16_with_options(
+    xml: *const u16,
+    len: usize,
+    options: *const XamlParseOptions,
+    result: *mut *mut XamlDocHandle,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    guard(|| {
+        clear_error(error);
+        match unsafe { read_parse_options(options) } {
+            Ok((_, options)) => parse_handle_utf16(xml, len, &options, result, error),
+            Err(code) => code,
+        }
+    })
+}
+
+fn parse_handle_utf8(
+    xml: *const u8,
+    len: usize,
+    options: &ParseOptions,
+    result: *mut *mut XamlDocHandle,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    clear_error(error);
+    if xml.is_null() || result.is_null() {
+        return XAML_ERROR_NULL_ARGUMENT;
+    }
+
+    match std::str::from_utf8(unsafe { std::slice::from_raw_parts(xml, len) }) {
+        Ok(xml_str) => parse_handle_string(xml_str.to_string(), options, result, error),
+        Err(_) => XAML_ERROR_INVALID_UTF8,
+    }
+}
+
+fn parse_handle_utf16(
+    xml: *const u16,
+    len: usize,
+    options: &ParseOptions,
+    result: *mut *mut XamlDocHandle,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    clear_error(error);
+    if xml.is_null() || result.is_null() {
+        return XAML_ERROR_NULL_ARGUMENT;
+    }
+
+    match String::from_utf16(unsafe { std::slice::from_raw_parts(xml, len) }) {
+        Ok(xml_str) => parse_handle_string(xml_str, options, result, error),
+        Err(_) => XAML_ERROR_INVALID_UTF8,
+    }
+}
+
+fn parse_handle_string(
+    xml_str: String,
+    options: &ParseOptions,
+    result: *mut *mut XamlDocHandle,
+    error: *mut *mut XamlParseError,
+) -> i32 {
+    match XamlDocHandle::parse(xml_str, options) {
+        Ok(handle) => {
+            unsafe { *result = Box::into_raw(handle) };
+            XAML_OK
+        }
+        Err(e) => {
+            set_error(error, &e);
+            error_code(&e)
+        }
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn free_xaml_doc_handle(doc: *mut XamlDocHandle) -> i32 {
+    guard(|| {
+        if doc.is_null() {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+
+        unsafe {
+            drop(Box::from_raw(doc));
+        }
+        XAML_OK
+    })
+}
+
+// Общая часть аксессоров: проверка аргументов и индекса узла
+fn with_node(
+    doc: *const XamlDocHandle,
+    node: u32,
+    out: bool,
+    f: impl FnOnce(&XamlDocHandle, &IndexNode) -> i32,
+) -> i32 {
+    guard(|| {
+        if doc.is_null() || !out {
+            return XAML_ERROR_NULL_ARGUMENT;
+        }
+        let doc = unsafe { &*doc };
+        match doc.node(node) {
+            Some(n) => f(doc, n),
+            None => XAML_ERROR_OUT_OF_RANGE,
+        }
+    })
+}
+
+// Строка без значения отдаётся как null с длиной 0
+fn write_str(s: Option<&str>, ptr: *mut *const u8, len: *mut usize) -> i32 {
+    unsafe {
+        *ptr = s.map_or(std::ptr::null(), str::as_ptr);
+        *len = s.map_or(0, str::len);
+    }
+    XAML_OK
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn xaml_doc_root(doc: *const XamlDocHandle, root: *mut u32) -> i32 {
+    with_node(doc, 0, !root.is_null(), |_, _| {
+        unsafe { *root = 0 };
+        XAML_OK
+    })
+}
+
+// Вид узла — значение NodeKind
+#[unsafe(no_mangle)]
+pub extern "C" fn xaml_node_kind(doc: *const XamlDocHandle, node: u32, kind: *mut i32) -> i32 {
+    with_node(doc, node, !kind.is_null(), |_, n| {
+        unsafe { *kind = n.kind as i32 };
+        XAML_OK
+    })
+}
+
+// Родитель узла; у корня — XAML_FLAT_NONE
+#[unsafe(no_mangle)]
+pub extern "C" fn xaml_node_parent(doc: *const XamlDocHandle, node: u32, parent: *mut u32) -> i32 {
+    with_node(doc, node, !parent.is_null(), |_, n| {
+        unsafe { *parent = n.parent };
+        XAML_OK
+    })
+}
+
+// Локальное имя элемента или цель инструкции обработки
+#[unsafe(no_mangle)]
+pub extern "C" fn xaml_node_name(doc: *const XamlDocHandle, node: u32, name: *mut *const u8, len: *mut usize) -> i32 {
+    with_node(doc, node, !name.is_null() && !len.is_null(), |doc, _| {
+        write_str(doc.str(doc.details(node).name.as_ref()), name, len)
+    })
+}
+
+// Префикс имени элемента в записи документа; null без префикса и у других узлов
+#[unsafe(no_mangle)]
+pub extern "C" fn xaml_node_prefix(
+    doc: *const XamlDocHandle,
+    node: u32,
+    prefix: *mut *const u8,
+    len: *mut usize,
+) -> i32 {
+    with_node(doc, node, !prefix.is_null() && !len.is_null(), |doc, _| {
+        write_str(doc.str(doc.details(node).prefix.as_ref()), prefix, len)
+    })
+}
+
+// URI пространства имён элемента; null без пространства имён и у других узлов
+#[unsafe(no_mangle)]
+pub extern "C" fn xaml_node_namespace(
+    doc: *const XamlDocHandle,
+    node: u32,
+    uri: *mut *const u8,
+    len: *mut usize,
+) -> i32 {
+    with_node(doc, node, !uri.is_null() && !len.is_null(), |doc, n| {
+        write_str(doc.namespaces.get(n.namespace as usize).map(String::as_str), uri, len)
+    })
+}
+
+// Число дочерних узлов всех видов, включая элементы свойств
+#[unsafe(no_mangle)]
+pub extern "C" fn xaml_node_child_count(doc: *const XamlDocHandle, node: u32, count: *mut usize) -> i32 {
+    with_node(doc, node, !count.is_null(), |doc, _| {
+        unsafe { *count = doc.children(node).len() };
+        XAML_OK
+    })
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn xaml_node_child(doc: *const XamlDocHandle, node: u32, index: usize, child: *mut u32) -> i32 {
+    with_node(doc, node, !child.is_null(), |doc, _| match doc.children(node).get(index) {
+        Some(&i) => {
+            unsafe { *child = i };
+            XAML_OK
+        }
+        None => XAML_ERROR_OUT_OF_RANGE,
+    })
+}
+
+// Значение атрибута по имени в записи документа ("Width", "x:Name", "Grid.Row").
+// Если атрибута нет, value — null.
+#[unsafe(no_mangle)]
+pub extern "C" fn xaml_node_attribute(
+    doc: *const XamlDocHandle,
+    node: u32,
+    name: *const c_char,
+    value: *mut *const u8,
+    len: *mut usize,
+) -> i32 {
+    with_node(doc, node, !name.is_null() && !value.is_null() && !len.is_null(), |doc, _| {
+        let name = match unsafe { CStr::from_ptr(name) }.to_str() {
+            Ok(s) => s,
+            Err(_) => return XAML_ERROR_INVALID_UTF8,
+        };
+        let found = doc
+            .details(node)
+            .attributes
+            .iter()
+            .find(|(qname, _)| doc.source[qname.clone()] == *name);
+        write_str(doc.str(found.map(|(_, value)| value)), value, len)
+    })
+}
+
+// Текст текстового узла, CDATA, комментария, значение инструкции обработки
+// или текст элемента до первого дочернего узла другого вида
+#[unsafe(no_mangle)]
+pub extern "C" fn xaml_node_text(doc: *const XamlDocHandle, node: u32, text: *mut *const u8, len: *mut usize) -> i32 {
+    with_node(doc, node, !text.is_null() && !len.is_null(), |doc, _| {
+        write_str(doc.str(doc.details(node).text.as_ref()), text, len)
+    })
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn xaml_node_span(doc: *const XamlDocHandle, node: u32, span: *mut TextSpan) -> i32 {
+    with_node(doc, node, !span.is_null(), |doc, n| {
+        unsafe { *span = doc.span(n) };
+        XAML_OK
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(xml: &str) -> *mut XamlDocHandle {
+        let mut doc = std::ptr::null_mut();
+        assert_eq!(parse_xaml_handle(xml.as_ptr(), xml.len(), &mut doc, std::ptr::null_mut()), XAML_OK);
+        doc
+    }
+
+    fn text(f: impl FnOnce(*mut *const u8, *mut usize) -> i32) -> Option<String> {
+        let (mut ptr, mut len) = (std::ptr::null(), 0);
+        assert_eq!(f(&mut ptr, &mut len), XAML_OK);
+        (!ptr.is_null()).then(|| {
+            std::str::from_utf8(unsafe { std::slice::from_raw_parts(ptr, len) }).unwrap().to_string()
+        })
+    }
+
+    #[test]
+    fn navigates_children_and_attributes() {
+        let doc = parse(
+            "<Grid xmlns:x=\"urn:x\" x:Name=\"root\">\
+             <Grid.RowDefinitions><RowDefinition Height=\"*\"/></Grid.RowDefinitions>\
+             <TextBlock Grid.Row=\"1\">Привет &amp; пока</TextBlock><!-- c --></Grid>",
+        );
+        let mut root = XAML_FLAT_NONE;
+        assert_eq!(xaml_doc_root(doc, &mut root), XAML_OK);
+        assert_eq!(text(|p, l| xaml_node_attribute(doc, root, c"x:Name".as_ptr(), p, l)).as_deref(), Some("root"));
+        assert_eq!(text(|p, l| xaml_node_attribute(doc, root, c"Name".as_ptr(), p, l)), None);
+
+        let mut count = 0;
+        assert_eq!(xaml_node_child_count(doc, root, &mut count), XAML_OK);
+        assert_eq!(count, 3);
+        let child = |index| {
+            let mut child = XAML_FLAT_NONE;
+            assert_eq!(xaml_node_child(doc, root, index, &mut child), XAML_OK);
+            child
+        };
+        let (rows, block, comment) = (child(0), child(1), child(2));
+        assert_eq!(text(|p, l| xaml_node_name(doc, rows, p, l)).as_deref(), Some("Grid.RowDefinitions"));
+        assert_eq!(text(|p, l| xaml_node_attribute(doc, block, c"Grid.Row".as_ptr(), p, l)).as_deref(), Some("1"));
+        assert_eq!(text(|p, l| xaml_node_text(doc, block, p, l)).as_deref(), Some("Привет & пока"));
+
+        let (mut kind, mut parent) = (0, 0);
+        assert_eq!(xaml_node_kind(doc, comment, &mut kind), XAML_OK);
+        assert_eq!(kind, NodeKind::Comment as i32);
+        assert_eq!(xaml_node_parent(doc, comment, &mut parent), XAML_OK);
+        assert_eq!(parent, root);
+
+        let mut span = TextSpan::default();
+        assert_eq!(xaml_node_span(doc, block, &mut span), XAML_OK);
+        assert_eq!((span.start_line, span.start_column), (1, 107));
+
+        let mut out = 0;
+        assert_eq!(xaml_node_child(doc, root, 3, &mut out), XAML_ERROR_OUT_OF_RANGE);
+        assert_eq!(xaml_node_kind(doc, 1000, &mut kind), XAML_ERROR_OUT_OF_RANGE);
+        assert_eq!(free_xaml_doc_handle(doc), XAML_OK);
+    }
+
+    #[test]
+    fn materializes_nodes_on_first_access() {
+        let doc = parse(
+            "<?xml version=\"1.0\"?><!-- пролог -->\n<a:Root xmlns:a=\"urn:a\" Tag=\"x &lt;\ty\">\
+             t1<![CDATA[<c>]]><?pi data?></a:Root>",
+        );
+        let handle = unsafe { &*doc };
+        assert_eq!(handle.nodes.len(), 4);
+        assert!(handle.details.iter().all(|d| d.get().is_none()));
+
+        assert_eq!(text(|p, l| xaml_node_name(doc, 0, p, l)).as_deref(), Some("Root"));
+        assert_eq!(text(|p, l| xaml_node_prefix(doc, 0, p, l)).as_deref(), Some("a"));
+        assert_eq!(text(|p, l| xaml_node_namespace(doc, 0, p, l)).as_deref(), Some("urn:a"));
+        assert_eq!(text(|p, l| xaml_node_namespace(doc, 1, p, l)), None);
+        assert_eq!(text(|p, l| xaml_node_attribute(doc, 0, c"Tag".as_ptr(), p, l)).as_deref(), Some("x < y"));
+        assert_eq!(text(|p, l| xaml_node_attribute(doc, 0, c"xmlns:a".as_ptr(), p, l)), None);
+        assert_eq!(text(|p, l| xaml_node_text(doc, 0, p, l)).as_deref(), Some("t1<c>"));
+        assert!(handle.details[3].get().is_none());
+
+        assert_eq!(text(|p, l| xaml_node_name(doc, 3, p, l)).as_deref(), Some("pi"));
+        assert_eq!(text(|p, l| xaml_node_text(doc, 3, p, l)).as_deref(), Some("data"));
+        let mut span = TextSpan::default();
+        assert_eq!(xaml_node_span(doc, 0, &mut span), XAML_OK);
+        assert_eq!((span.start_line, span.start_column), (2, 1));
+        assert_eq!(free_xaml_doc_handle(doc), XAML_OK);
+    }
+
+    #[test]
+    fn applies_parse_options() {
+        let depth = 5000;
+        let xml = format!("{}<a/>{}", "<a>\n".repeat(depth - 1), "\n</a>".repeat(depth - 1));
+        let doc = parse(&xml);
+        assert_eq!(unsafe { &*doc }.nodes.iter().filter(|n| n.kind == NodeKind::Element).count(), depth);
+        assert_eq!(free_xaml_doc_handle(doc), XAML_OK);
+
+        let mut options: XamlParseOptions = unsafe { std::mem::zeroed() };
+        options.size = size_of::<XamlParseOptions>();
+        options.max_depth = 10;
+        let mut doc = std::ptr::null_mut();
+        let code = parse_xaml_handle_with_options(xml.as_ptr(), xml.len(), &options, &mut doc, std::ptr::null_mut());
+        assert_eq!(code, XAML_ERROR_LIMIT_EXCEEDED);
+
+        let utf16: Vec<u16> = "<a><b/></a>".encode_utf16().collect();
+        let (xml, len) = (utf16.as_ptr(), utf16.len());
+        assert_eq!(parse_xaml_handle_utf16_with_options(xml, len, &options, &mut doc, std::ptr::null_mut()), XAML_OK);
+        assert_eq!(free_xaml_doc_handle(doc), XAML_OK);
+    }
+}
@@ -0,0 +1,108 @@
+use std::ops::Range;
+
+/// Диапазон в исходном тексте: байтовые смещения `start..end`
+/// и строка/столбец обеих границ (с 1, столбец в символах).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TextSpan {
+    pub start: usize,
+    pub end: usize,
+    pub start_line: u32,
+    pub start_column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+}
+
+impl TextSpan {
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+// Индекс начал строк для перевода смещений в строку и столбец
+pub(crate) struct LineIndex<'a> {
+    text: &'a str,
+    line_starts: LineStarts,
+}
+
+impl<'a> LineIndex<'a> {
+    pub(crate) fn new(text: &'a str) -> Self {
+        LineIndex {
+            text,
+            line_starts: LineStarts::new(text),
+        }
+    }
+
+    pub(crate) fn span(&self, range: Range<usize>) -> TextSpan {
+        self.line_starts.span(self.text, range)
+    }
+}
+
+// Начала строк без ссылки на текст: текст передаётся при каждом обращении,
+// поэтому индекс можно хранить рядом с владельцем текста
+pub(crate) struct LineStarts(Vec<usize>);
+
+impl LineStarts {
+    pub(crate) fn new(text: &str) -> Self {
+        LineStarts(std::iter::once(0).chain(text.match_indices('\n').map(|(i, _)| i + 1)).collect())
+    }
+
+    pub(crate) fn position(&self, text: &str, offset: usize) -> (u32, u32) {
+        let offset = offset.min(text.len());
+        let line = self.0.partition_point(|&start| start <= offset) - 1;
+        let column = text[self.0[line]..offset].chars().count() + 1;
+        (line as u32 + 1, column as u32)
+    }
+
+    pub(crate) fn span(&self, text: &str, range: Range<usize>) -> TextSpan {
+        let (start_line, start_column) = self.position(text, range.start);
+        let (end_line, end_column) = self.position(text, range.end);
+        TextSpan {
+            start: range.start,
+            end: range.end,
+            start_line,
+            start_column,
+            end_line,
+            end_column,
+        }
+    }
+}
+
+// Конец открывающего тега, начинающегося с `<` на позиции start
+pub(crate) fn start_tag_end(input: &str, start: usize) -> usize {
+    let mut quote = None;
+    for (i, b) in input.as_bytes()[start..].iter().enumerate() {
+        match (quote, b) {
+            (None, b'"' | b'\'') => quote = Some(*b),
+            (Some(q), _) if q == *b => quote = None,
+            (None, b'>') => return start + i + 1,
+            _ => {}
+        }
+    }
+    input.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_offsets_to_lines_and_columns() {
+        let text = "ab\nвгд\n\nx";
+        let lines = LineStarts::new(text);
+        let position = |offset| lines.position(text, offset);
+        assert_eq!(position(0), (1, 1));
+        assert_eq!(position(2), (1, 3));
+        assert_eq!(position(3), (2, 1));
+        assert_eq!(position(7), (2, 3));
+        assert_eq!(position(10), (3, 1));
+        assert_eq!(position(11), (4, 1));
+        assert_eq!(position(100), (4, 2));
+    }
+
+    #[test]
+    fn finds_start_tag_end_outside_quotes() {
+        let input = r#"<a b="x>y" c='>'>text</a>"#;
+        assert_eq!(&input[..start_tag_end(input, 0)], r#"<a b="x>y" c='>'>"#);
+    }
+}
@@ -0,0 +1,354 @@
+use crate::error::{self, ErrorKind, ParseError};
+use crate::lexer::{Lexer, StartTag, TokenKind};
+use crate::text;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::ops::Range;
+
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Событие потокового разбора. Строки по возможности ссылаются на исходный
+/// текст; `Cow::Owned` — только после раскрытия сущностей или нормализации.
+/// `range` — байтовый диапазон разметки в исходном тексте.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XamlEvent<'a> {
+    /// Открывающий тег; за ним идут события `Attribute` этого элемента.
+    StartElement {
+        prefix: Option<&'a str>,
+        name: &'a str,
+        namespace: Option<Cow<'a, str>>,
+        range: Range<usize>,
+    },
+    /// Атрибут последнего открытого элемента. Объявления `xmlns` не передаются.
+    Attribute {
+        prefix: Option<&'a str>,
+        name: &'a str,
+        namespace: Option<Cow<'a, str>>,
+        value: Cow<'a, str>,
+        range: Range<usize>,
+    },
+    /// Закрывающий тег; для `<a/>` следует сразу за атрибутами, `range` пустой.
+    EndElement {
+        prefix: Option<&'a str>,
+        name: &'a str,
+        namespace: Option<Cow<'a, str>>,
+        range: Range<usize>,
+    },
+    Text { text: Cow<'a, str>, range: Range<usize> },
+    CData { text: Cow<'a, str>, range: Range<usize> },
+    Comment { text: &'a str, range: Range<usize> },
+    ProcessingInstruction {
+        target: &'a str,
+        value: Option<&'a str>,
+        range: Range<usize>,
+    },
+}
+
+/// Потоковый (pull) разбор без построения дерева: память растёт с глубиной
+/// вложенности, а не с размером документа. Проверки корректности те же по смыслу,
+/// что у `XamlDocument::parse`: парность тегов, префиксы, сущности, один корень.
+/// После первой ошибки итератор заканчивается.
+pub struct XamlReader<'a> {
+    text: &'a str,
+    lexer: Lexer<'a>,
+    pending: VecDeque<XamlEvent<'a>>,
+    // Открытые элементы: имя как в документе и число объявлений namespaces до него
+    open: Vec<(&'a str, usize)>,
+    // Объявления xmlns в области видимости; префикс "" — пространство по умолчанию
+    namespaces: Vec<(&'a str, Cow<'a, str>)>,
+    root_seen: bool,
+    done: bool,
+}
+
+impl<'a> XamlReader<'a> {
+    pub fn new(text: &'a str) -> Self {
+        XamlReader {
+            text,
+            lexer: Lexer::new(text),
+            pending: VecDeque::new(),
+            open: Vec::new(),
+            namespaces: Vec::new(),
+            root_seen: false,
+            done: false,
+        }
+    }
+
+    fn error(&self, kind: ErrorKind, offset: usize, message: String) -> ParseError {
+        let (line, column) = error::position_of(self.text, offset);
+        ParseError {
+            kind,
+            line,
+            column,
+            offset,
+            message,
+            file: None,
+        }
+    }
+
+    fn resolve(&self, prefix: Option<&str>, offset: usize) -> Result<Option<Cow<'a, str>>, ParseError> {
+        let key = prefix.unwrap_or("");
+        if key == "xml" {
+            return Ok(Some(Cow::Borrowed(XML_NAMESPACE)));
+        }
+        match self.namespaces.iter().rev().find(|(p, _)| *p == key) {
+            Some((_, uri)) if uri.is_empty() => Ok(None),
+            Some((_, uri)) => Ok(Some(uri.clone())),
+            None if prefix.is_none() => Ok(None),
+            None => Err(self.error(ErrorKind::UnknownNamespace, offset, format!("unknown namespace prefix '{key}'"))),
+        }
+    }
+
+    fn start_tag(&mut self, tag: StartTag<'a>, range: Range<usize>) -> Result<(), ParseError> {
+        if self.open.is_empty() && self.root_seen {
+            return Err(self.error(ErrorKind::Syntax, range.start, "unexpected second root element".to_string()));
+        }
+        self.root_seen = true;
+
+        let scope = self.namespaces.len();
+        for attr in &tag.attributes {
+            let prefix = match attr.name.split_once(':') {
+                Some(("xmlns", prefix)) => prefix,
+                None if attr.name == "xmlns" => "",
+                _ => continue,
+            };
+            let value = self.attribute_value(attr.value, attr.range.start)?;
+            self.namespaces.push((prefix, value));
+        }
+
+        let (prefix, name) = split_name(tag.name);
+        let namespace = self.resolve(prefix, range.start)?;
+        self.pending.push_back(XamlEvent::StartElement {
+            prefix,
+            name,
+            namespace: namespace.clone(),
+            range: range.clone(),
+        });
+
+        let first = self.pending.len();
+        for attr in &tag.attributes {
+            if attr.name == "xmlns" || attr.name.starts_with("xmlns:") {
+                continue;
+            }
+            let (prefix, name) = split_name(attr.name);
+            // Атрибут без префикса не попадает в пространство по умолчанию
+            let namespace = match prefix {
+                Some(_) => self.resolve(prefix, attr.range.start)?,
+                None => None,
+            };
+            let duplicate = self.pending.range(first..).any(|event| {
+                matches!(event, XamlEvent::Attribute { name: n, namespace: ns, .. } if *n == name && *ns == namespace)
+            });
+            if duplicate {
+                let message = format!("duplicate attribute '{}'", attr.name);
+                return Err(self.error(ErrorKind::DuplicateAttribute, attr.range.start, message));
+            }
+            let value = self.attribute_value(attr.value, attr.range.start)?;
+            self.pending.push_back(XamlEvent::Attribute {
+                prefix,
+                name,
+                namespace,
+                value,
+                range: attr.range.clone(),
+            });
+        }
+
+        if tag.self_closing {
+            self.namespaces.truncate(scope);
+            self.pending.push_back(XamlEvent::EndElement {
+                prefix,
+                name,
+                namespace,
+                range: range.end..range.end,
+            });
+        } else {
+            self.open.push((tag.name, scope));
+        }
+        Ok(())
+    }
+
+    fn end_tag(&mut self, qname: &'a str, range: Range<usize>) -> Result<(), ParseError> {
+        let scope = match self.open.last() {
+            Some(&(open, scope)) if open == qname => scope,
+            Some(&(open, _)) => {
+                let message = format!("expected '</{open}>', found '</{qname}>'");
+                return Err(self.error(ErrorKind::UnclosedTag, range.start, message));
+            }
+            None => return Err(self.error(ErrorKind::Syntax, range.start, format!("unexpected close tag '{qname}'"))),
+        };
+        let (prefix, name) = split_name(qname);
+        let namespace = self.resolve(prefix, range.start)?;
+        self.open.pop();
+        self.namespaces.truncate(scope);
+        self.pending.push_back(XamlEvent::EndElement {
+            prefix,
+            name,
+            namespace,
+            range,
+        });
+        Ok(())
+    }
+
+    fn attribute_value(&self, raw: &'a str, offset: usize) -> Result<Cow<'a, str>, ParseError> {
+        text::attribute_value(raw)
+            .ok_or_else(|| self.error(ErrorKind::InvalidEntity, offset, "unknown entity reference".to_string()))
+    }
+
+    fn next_event(&mut self) -> Result<Option<XamlEvent<'a>>, ParseError> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(event));
+            }
+            let token = match self.lexer.next() {
+                Some(token) => token.map_err(|e| self.error(ErrorKind::Syntax, e.offset, e.message))?,
+                None => return self.finish().map(|_| None),
+            };
+            let range = token.range;
+            let raw = &self.text[range.clone()];
+            match token.kind {
+                TokenKind::StartTag(tag) => self.start_tag(tag, range)?,
+                TokenKind::EndTag { name } => self.end_tag(name, range)?,
+                TokenKind::Text if self.open.is_empty() => {
+                    if !raw.trim_matches([' ', '\t', '\r', '\n']).is_empty() {
+                        return Err(self.error(ErrorKind::Syntax, range.start, "text outside root element".to_string()));
+                    }
+                }
+                TokenKind::Text => {
+                    let Some(text) = text::text_value(raw) else {
+                        let message = "unknown entity reference".to_string();
+                        return Err(self.error(ErrorKind::InvalidEntity, range.start, message));
+                    };
+                    return Ok(Some(XamlEvent::Text { text, range }));
+                }
+                TokenKind::CData { .. } if self.open.is_empty() => {
+                    return Err(self.error(ErrorKind::Syntax, range.start, "CDATA outside root element".to_string()));
+                }
+                TokenKind::CData { content } => {
+                    let text = text::cdata_value(content);
+                    return Ok(Some(XamlEvent::CData { text, range }));
+                }
+                TokenKind::Comment { content } => return Ok(Some(XamlEvent::Comment { text: content, range })),
+                // XML-объявление не событие
+                TokenKind::ProcessingInstruction { target, .. } if target.eq_ignore_ascii_case("xml") => {}
+                TokenKind::ProcessingInstruction { target, content } => {
+                    return Ok(Some(XamlEvent::ProcessingInstruction {
+                        target,
+                        value: content,
+                        range,
+                    }));
+                }
+                TokenKind::Doctype => {
+                    return Err(self.error(ErrorKind::DtdNotAllowed, range.start, "DTD is not allowed".to_string()));
+                }
+            }
+        }
+    }
+
+    fn finish(&self) -> Result<(), ParseError> {
+        match self.open.last() {
+            Some((name, _)) => {
+                let message = format!("unclosed tag '{name}'");
+                Err(self.error(ErrorKind::UnclosedTag, self.text.len(), message))
+            }
+            None if !self.root_seen => {
+                Err(self.error(ErrorKind::NoRootElement, self.text.len(), "no root element".to_string()))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a> Iterator for XamlReader<'a> {
+    type Item = Result<XamlEvent<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let event = self.next_event();
+        if !matches!(event, Ok(Some(_))) {
+            self.done = true;
+        }
+        event.transpose()
+    }
+}
+
+fn split_name(qname: &str) -> (Option<&str>, &str) {
+    match qname.split_once(':') {
+        Some((prefix, name)) => (Some(prefix), name),
+        None => (None, qname),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::XamlDocument;
+
+    fn events(text: &str) -> Result<Vec<XamlEvent<'_>>, ParseError> {
+        XamlReader::new(text).collect()
+    }
+
+    #[test]
+    fn emits_events_in_document_order() {
+        let text = "<?xml version=\"1.0\"?>\n<Grid xmlns=\"urn:ui\" xmlns:x=\"urn:x\" x:Name=\"a\tb\">\
+                    <x:Item Title=\"&lt;1&gt;\"/>t&amp;x<![CDATA[c]]><!--n--><?pi v?></Grid>\n";
+        let events = events(text).unwrap();
+        let ui = Some(Cow::Borrowed("urn:ui"));
+        let x = Some(Cow::Borrowed("urn:x"));
+        assert!(matches!(&events[0], XamlEvent::StartElement { name: "Grid", namespace, .. } if *namespace == ui));
+        assert!(matches!(
+            &events[1],
+            XamlEvent::Attribute { prefix: Some("x"), name: "Name", namespace, value, .. }
+                if *namespace == x && value == "a b"
+        ));
+        assert!(matches!(&events[2], XamlEvent::StartElement { name: "Item", namespace, .. } if *namespace == x));
+        assert!(matches!(&events[3], XamlEvent::Attribute { name: "Title", namespace: None, value, .. } if value == "<1>"));
+        assert!(matches!(&events[4], XamlEvent::EndElement { name: "Item", .. }));
+        assert!(matches!(&events[5], XamlEvent::Text { text, .. } if text == "t&x"));
+        assert!(matches!(&events[6], XamlEvent::CData { text, .. } if text == "c"));
+        assert!(matches!(events[7], XamlEvent::Comment { text: "n", .. }));
+        assert!(matches!(events[8], XamlEvent::ProcessingInstruction { target: "pi", value: Some("v"), .. }));
+        assert!(matches!(&events[9], XamlEvent::EndElement { name: "Grid", range, .. } if &text[range.clone()] == "</Grid>"));
+        assert_eq!(events.len(), 10);
+    }
+
+    #[test]
+    fn matches_document_model() {
+        let mut text = String::from("<Root xmlns=\"urn:ui\">");
+        for i in 0..200 {
+            text.push_str(&format!("<Row Index=\"{i}\"><Cell>{i}</Cell><Cell/></Row>"));
+        }
+        text.push_str("</Root>");
+
+        let doc = XamlDocument::parse(&text).unwrap();
+        let mut stack = Vec::new();
+        let mut elements = 0;
+        for event in XamlReader::new(&text) {
+            match event.unwrap() {
+                XamlEvent::StartElement { name, .. } => {
+                    stack.push(name);
+                    elements += 1;
+                }
+                XamlEvent::EndElement { name, .. } => assert_eq!(stack.pop(), Some(name)),
+                _ => {}
+            }
+        }
+        assert_eq!(elements, 1 + doc.root.elements().map(|row| 1 + row.elements().count()).sum::<usize>());
+    }
+
+    #[test]
+    fn reports_malformed_input() {
+        let kind = |text| events(text).unwrap_err().kind;
+        assert_eq!(kind("<a><b></a>"), ErrorKind::UnclosedTag);
+        assert_eq!(kind("<a><b>"), ErrorKind::UnclosedTag);
+        assert_eq!(kind("<p:a/>"), ErrorKind::UnknownNamespace);
+        assert_eq!(kind("<a>&bogus;</a>"), ErrorKind::InvalidEntity);
+        assert_eq!(kind("<a x='1' x='2'/>"), ErrorKind::DuplicateAttribute);
+        assert_eq!(kind("<!DOCTYPE a><a/>"), ErrorKind::DtdNotAllowed);
+        assert_eq!(kind("<!-- only -->"), ErrorKind::NoRootElement);
+        assert_eq!(kind("<a/><b/>"), ErrorKind::Syntax);
+
+        let error = events("<a>\n  <b></c></a>").unwrap_err();
+        assert_eq!((error.line, error.column, error.offset), (2, 6, 9));
+    }
+}
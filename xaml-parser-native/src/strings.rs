@@ -0,0 +1,63 @@
+use std::alloc::{self, Layout};
+use std::os::raw::c_char;
+
+// Строки, которые библиотека отдаёт через FFI. Перед байтами строки хранится
+// её длина (usize), после них — завершающий NUL. Указатель ссылается на первый
+// байт, поэтому строку можно читать и как C-строку, и по длине.
+
+const HEADER: usize = size_of::<usize>();
+
+fn layout(len: usize) -> Layout {
+    Layout::from_size_align(HEADER + len + 1, align_of::<usize>()).expect("string too long")
+}
+
+pub(crate) fn alloc(s: &str) -> *mut c_char {
+    let layout = layout(s.len());
+    unsafe {
+        let base = alloc::alloc(layout);
+        if base.is_null() {
+            alloc::handle_alloc_error(layout);
+        }
+        (base as *mut usize).write(s.len());
+        let data = base.add(HEADER);
+        std::ptr::copy_nonoverlapping(s.as_ptr(), data, s.len());
+        data.add(s.len()).write(0);
+        data as *mut c_char
+    }
+}
+
+// Длина строки в байтах без завершающего NUL; ptr получен из alloc
+pub(crate) unsafe fn len(ptr: *const c_char) -> usize {
+    unsafe { (ptr as *const u8).sub(HEADER).cast::<usize>().read() }
+}
+
+pub(crate) unsafe fn free(ptr: *mut c_char) {
+    unsafe {
+        let len = len(ptr);
+        alloc::dealloc((ptr as *mut u8).sub(HEADER), layout(len));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    #[test]
+    fn stores_length_before_bytes() {
+        let ptr = alloc("a\0bc");
+        unsafe {
+            assert_eq!(len(ptr), 4);
+            assert_eq!(std::slice::from_raw_parts(ptr as *const u8, 5), b"a\0bc\0");
+            assert_eq!(CStr::from_ptr(ptr).to_bytes(), b"a");
+            free(ptr);
+        }
+
+        let empty = alloc("");
+        unsafe {
+            assert_eq!(len(empty), 0);
+            assert_eq!(*empty, 0);
+            free(empty);
+        }
+    }
+}
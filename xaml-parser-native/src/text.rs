@@ -0,0 +1,130 @@
+use roxmltree::Node;
+use std::borrow::Cow;
+use std::ops::Range;
+
+// Фрагмент текстового содержимого: обычный текст или секция CDATA
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum TextSegment {
+    Text(String),
+    CData(String),
+}
+
+// roxmltree склеивает соседние текст и CDATA в один узел и хранит диапазон
+// только первого фрагмента, поэтому границы восстанавливаем по исходному тексту
+pub(crate) fn split_text_node(node: Node) -> Vec<TextSegment> {
+    let text = node.text().unwrap_or_default();
+    let raw = &node.document().input_text()[text_node_range(node)];
+    if !raw.contains("<![CDATA[") {
+        return vec![TextSegment::Text(text.to_string())];
+    }
+
+    split_raw(raw).unwrap_or_else(|| vec![TextSegment::Text(text.to_string())])
+}
+
+// Диапазон текстового узла в исходнике вместе со склеенными с ним секциями CDATA
+pub(crate) fn text_node_range(node: Node) -> Range<usize> {
+    let input = node.document().input_text();
+    let start = node.range().start;
+    let end = match node.next_sibling() {
+        Some(next) => next.range().start,
+        None => match node.parent() {
+            Some(parent) => {
+                let range = parent.range();
+                input[range.clone()].rfind("</").map_or(range.end, |i| range.start + i)
+            }
+            None => input.len(),
+        },
+    };
+    start..end.max(start)
+}
+
+fn split_raw(mut raw: &str) -> Option<Vec<TextSegment>> {
+    let mut segments = Vec::new();
+    while !raw.is_empty() {
+        match raw.find("<![CDATA[") {
+            Some(0) => {
+                let body = &raw[9..];
+                let end = body.find("]]>")?;
+                segments.push(TextSegment::CData(normalize_newlines(&body[..end])));
+                raw = &body[end + 3..];
+            }
+            Some(i) => {
+                segments.push(TextSegment::Text(unescape(&raw[..i])?));
+                raw = &raw[i..];
+            }
+            None => {
+                segments.push(TextSegment::Text(unescape(raw)?));
+                raw = "";
+            }
+        }
+    }
+    Some(segments)
+}
+
+// Значение атрибута по правилам XML: пробельные символы заменяются пробелом,
+// затем раскрываются сущности. None — ссылка на неизвестную сущность.
+pub(crate) fn attribute_value(raw: &str) -> Option<Cow<'_, str>> {
+    if !raw.contains(['&', '\t', '\n', '\r']) {
+        return Some(Cow::Borrowed(raw));
+    }
+    let spaced = raw.replace("\r\n", " ").replace(['\t', '\n', '\r'], " ");
+    unescape(&spaced).map(Cow::Owned)
+}
+
+// Текст между разметкой с раскрытыми сущностями и переводами строк \n
+pub(crate) fn text_value(raw: &str) -> Option<Cow<'_, str>> {
+    match raw.contains(['&', '\r']) {
+        false => Some(Cow::Borrowed(raw)),
+        true => unescape(raw).map(Cow::Owned),
+    }
+}
+
+// Содержимое CDATA с переводами строк \n
+pub(crate) fn cdata_value(content: &str) -> Cow<'_, str> {
+    match content.contains('\r') {
+        false => Cow::Borrowed(content),
+        true => Cow::Owned(normalize_newlines(content)),
+    }
+}
+
+// Раскрывает предопределённые сущности и ссылки на символы.
+// Для сущностей из DTD возвращает None.
+pub(crate) fn unescape(raw: &str) -> Option<String> {
+    let mut out = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(i) = rest.find('&') {
+        out.push_str(&normalize_newlines(&rest[..i]));
+        rest = &rest[i + 1..];
+        let end = rest.find(';')?;
+        let name = &rest[..end];
+        let c = match name {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "apos" => '\'',
+            "quot" => '"',
+            _ => {
+                let code = if let Some(hex) = name.strip_prefix("#x") {
+                    u32::from_str_radix(hex, 16).ok()?
+                } else if let Some(dec) = name.strip_prefix('#') {
+                    dec.parse().ok()?
+                } else {
+                    return None;
+                };
+                char::from_u32(code)?
+            }
+        };
+        out.push(c);
+        rest = &rest[end + 1..];
+    }
+    out.push_str(&normalize_newlines(rest));
+    Some(out)
+}
+
+fn normalize_newlines(s: &str) -> String {
+    if s.contains('\r') {
+        s.replace("\r\n", "\n").replace('\r', "\n")
+    } else {
+        s.to_string()
+    }
+}
@@ -0,0 +1,471 @@
+use crate::document::{XamlDocument, XamlElement, XamlNode};
+
+/// Параметры записи XAML.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// Строка отступа на уровень вложенности. `None` — писать узлы как есть,
+    /// сохраняя пробельные текстовые узлы модели.
+    pub indent: Option<String>,
+    /// Писать пустые элементы как `<Button/>`.
+    pub self_close_empty: bool,
+    /// Добавлять `<?xml version="1.0" encoding="utf-8"?>`.
+    pub xml_declaration: bool,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        WriteOptions {
+            indent: Some("    ".to_string()),
+            self_close_empty: true,
+            xml_declaration: false,
+        }
+    }
+}
+
+impl XamlDocument {
+    /// Записывает документ в XAML.
+    pub fn to_xaml(&self, options: &WriteOptions) -> String {
+        write_xaml(&self.root, options)
+    }
+}
+
+/// Записывает элемент и его содержимое в XAML. Объявления пространств имён
+/// добавляются там, где префикс элемента или атрибута ещё не объявлен.
+pub fn write_xaml(root: &XamlElement, options: &WriteOptions) -> String {
+    let mut writer = Writer {
+        out: String::new(),
+        options,
+        scopes: Vec::new(),
+    };
+    if options.xml_declaration {
+        writer.out.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>");
+        if options.indent.is_some() {
+            writer.out.push('\n');
+        }
+    }
+    writer.write_element(root, 0);
+    writer.out
+}
+
+struct Writer<'a> {
+    out: String,
+    options: &'a WriteOptions,
+    // Объявленные пары (префикс, URI), внутренние области видимости в конце
+    scopes: Vec<(Option<String>, String)>,
+}
+
+// Открытый элемент, содержимое которого ещё пишется
+struct Frame<'a> {
+    name: String,
+    content: std::vec::IntoIter<Content<'a>>,
+    depth: usize,
+    pretty: bool,
+    written: bool,
+    scope_len: usize,
+}
+
+impl<'a> Writer<'_> {
+    // Обход со своим стеком: глубина дерева не ограничена стеком потока
+    fn write_element(&mut self, root: &'a XamlElement, depth: usize) {
+        let mut stack: Vec<Frame<'a>> = self.open_element(root, depth).into_iter().collect();
+        while let Some(frame) = stack.last_mut() {
+            let Some(item) = frame.content.next() else {
+                let frame = stack.pop().expect("stack is not empty");
+                self.close_element(frame);
+                continue;
+            };
+            let depth = frame.depth + 1;
+            if frame.pretty {
+                self.newline(depth);
+            }
+            frame.written = true;
+            let element = match item {
+                Content::Property(element) | Content::Node(XamlNode::Element(element)) => element,
+                Content::Node(node) => {
+                    self.write_node(node);
+                    continue;
+                }
+            };
+            stack.extend(self.open_element(element, depth));
+        }
+    }
+
+    // Пишет открывающий тег; None, если элемент записан целиком как пустой
+    fn open_element(&mut self, element: &'a XamlElement, depth: usize) -> Option<Frame<'a>> {
+        let scope_len = self.scopes.len();
+        let mut declarations = Vec::new();
+        // Объявления из модели сохраняются, даже если сам элемент их не использует
+        // (xmlns:d для mc:Ignorable, пространства имён для значений x:Type)
+        for namespace in &element.namespaces {
+            let prefix = namespace.prefix.as_deref();
+            if self.lookup(prefix).unwrap_or_default() != namespace.uri {
+                self.declare(prefix, &namespace.uri, &mut declarations);
+            }
+        }
+        let prefix = self.bind(element.prefix.as_deref(), element.namespace.as_deref(), &mut declarations);
+
+        let name = qualify(prefix.as_deref(), &element.name);
+        self.out.push('<');
+        self.out.push_str(&name);
+        let mut attributes = Vec::with_capacity(element.attributes.len());
+        for attr in &element.attributes {
+            // Атрибуты не попадают в пространство имён по умолчанию, им нужен префикс
+            let prefix = match (&attr.namespace, attr.prefix.as_deref()) {
+                (Some(ns), Some(prefix)) => self.bind(Some(prefix), Some(ns), &mut declarations),
+                (Some(ns), None) => match self.lookup_prefix(ns) {
+                    Some(prefix) => Some(prefix),
+                    None => self.bind(Some("ns"), Some(ns), &mut declarations),
+                },
+                (None, _) => None,
+            };
+            attributes.push((qualify(prefix.as_deref(), &attr.name), &attr.value));
+        }
+        for (prefix, uri) in &declarations {
+            match prefix {
+                Some(prefix) => self.out.push_str(&format!(" xmlns:{prefix}=\"")),
+                None => self.out.push_str(" xmlns=\""),
+            }
+            escape_attribute(uri, &mut self.out);
+            self.out.push('"');
+        }
+        for (name, value) in attributes {
+            self.out.push(' ');
+            self.out.push_str(&name);
+            self.out.push_str("=\"");
+            escape_attribute(value, &mut self.out);
+            self.out.push('"');
+        }
+
+        let pretty = self.options.indent.is_some() && !has_significant_text(element);
+        let content: Vec<Content> = ordered_content(element)
+            .into_iter()
+            .filter(|item| !(pretty && matches!(item, Content::Node(node) if is_whitespace_text(node))))
+            .collect();
+
+        if content.is_empty() && self.options.self_close_empty {
+            self.out.push_str("/>");
+            self.scopes.truncate(scope_len);
+            return None;
+        }
+
+        self.out.push('>');
+        Some(Frame {
+            name,
+            content: content.into_iter(),
+            depth,
+            pretty,
+            written: false,
+            scope_len,
+        })
+    }
+
+    fn close_element(&mut self, frame: Frame) {
+        if frame.pretty && frame.written {
+            self.newline(frame.depth);
+        }
+        self.out.push_str("</");
+        self.out.push_str(&frame.name);
+        self.out.push('>');
+        self.scopes.truncate(frame.scope_len);
+    }
+
+    // Узлы кроме элементов, их пишет write_element
+    fn write_node(&mut self, node: &XamlNode) {
+        match node {
+            XamlNode::Element(_) => unreachable!("elements are written by write_element"),
+            XamlNode::Text(text) => escape_text(text, &mut self.out),
+            XamlNode::CData(text) => {
+                self.out.push_str("<![CDATA[");
+                self.out.push_str(&text.replace("]]>", "]]]]><![CDATA[>"));
+                self.out.push_str("]]>");
+            }
+            XamlNode::Comment(text) => {
+                self.out.push_str("<!--");
+                self.out.push_str(text);
+                self.out.push_str("-->");
+            }
+            XamlNode::ProcessingInstruction { target, value } => {
+                self.out.push_str("<?");
+                self.out.push_str(target);
+                if let Some(value) = value {
+                    self.out.push(' ');
+                    self.out.push_str(value);
+                }
+                self.out.push_str("?>");
+            }
+        }
+    }
+
+    // Возвращает префикс, под которым URI доступен, при необходимости объявляя его
+    fn bind(
+        &mut self,
+        prefix: Option<&str>,
+        namespace: Option<&str>,
+        declarations: &mut Vec<(Option<String>, String)>,
+    ) -> Option<String> {
+        let Some(namespace) = namespace else {
+            // Элемент без пространства имён внутри xmlns="..." требует сброса
+            if prefix.is_none() && self.lookup(None).is_some_and(|uri| !uri.is_empty()) {
+                self.declare(None, "", declarations);
+            }
+            return None;
+        };
+
+        if self.lookup(prefix) == Some(namespace) {
+            return prefix.map(str::to_string);
+        }
+
+        let prefix = match prefix {
+            // Префикс уже объявлен на этом элементе с другим URI
+            Some(p) if declarations.iter().any(|(d, _)| d.as_deref() == Some(p)) => Some(self.unique_prefix(p)),
+            Some(p) => Some(p.to_string()),
+            None if declarations.iter().any(|(d, _)| d.is_none()) => Some(self.unique_prefix("ns")),
+            None => None,
+        };
+        self.declare(prefix.as_deref(), namespace, declarations);
+        prefix
+    }
+
+    fn declare(&mut self, prefix: Option<&str>, namespace: &str, declarations: &mut Vec<(Option<String>, String)>) {
+        let prefix = prefix.map(str::to_string);
+        declarations.push((prefix.clone(), namespace.to_string()));
+        self.scopes.push((prefix, namespace.to_string()));
+    }
+
+    fn lookup(&self, prefix: Option<&str>) -> Option<&str> {
+        self.scopes
+            .iter()
+            .rev()
+            .find(|(p, _)| p.as_deref() == prefix)
+            .map(|(_, uri)| uri.as_str())
+    }
+
+    fn lookup_prefix(&self, namespace: &str) -> Option<String> {
+        self.scopes
+            .iter()
+            .rev()
+            .filter_map(|(p, uri)| p.as_deref().filter(|_| uri == namespace))
+            .find(|p| self.lookup(Some(p)) == Some(namespace))
+            .map(str::to_string)
+    }
+
+    fn unique_prefix(&self, base: &str) -> String {
+        (1..)
+            .map(|i| format!("{base}{i}"))
+            .find(|p| self.lookup(Some(p)).is_none())
+            .unwrap()
+    }
+
+    fn newline(&mut self, depth: usize) {
+        self.out.push('\n');
+        if let Some(indent) = &self.options.indent {
+            for _ in 0..depth {
+                self.out.push_str(indent);
+            }
+        }
+    }
+}
+
+enum Content<'a> {
+    Property(&'a XamlElement),
+    Node(&'a XamlNode),
+}
+
+// Элементы свойств вынесены из children модели; они возвращаются на свои места
+// по XamlPropertyElement::index
+fn ordered_content(element: &XamlElement) -> Vec<Content<'_>> {
+    let mut content = Vec::with_capacity(element.children.len() + element.properties.len());
+    let mut properties = element.properties.iter().peekable();
+    for (i, child) in element.children.iter().enumerate() {
+        while let Some(property) = properties.next_if(|p| p.index <= i) {
+            content.push(Content::Property(&property.element));
+        }
+        content.push(Content::Node(child));
+    }
+    content.extend(properties.map(|p| Content::Property(&p.element)));
+    content
+}
+
+fn qualify(prefix: Option<&str>, name: &str) -> String {
+    match prefix {
+        Some(prefix) => format!("{prefix}:{name}"),
+        None => name.to_string(),
+    }
+}
+
+fn is_whitespace_text(node: &XamlNode) -> bool {
+    matches!(node, XamlNode::Text(text) if text.trim().is_empty())
+}
+
+// Смешанное содержимое нельзя переформатировать без изменения текста
+fn has_significant_text(element: &XamlElement) -> bool {
+    element.children.iter().any(|child| match child {
+        XamlNode::Text(text) => !text.trim().is_empty(),
+        XamlNode::CData(_) => true,
+        _ => false,
+    })
+}
+
+fn escape_text(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '\r' => out.push_str("&#xD;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+fn escape_attribute(value: &str, out: &mut String) {
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '"' => out.push_str("&quot;"),
+            '\t' => out.push_str("&#x9;"),
+            '\n' => out.push_str("&#xA;"),
+            '\r' => out.push_str("&#xD;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::XamlAttribute;
+    use crate::options::ParseOptions;
+
+    #[test]
+    fn writes_indented_document_with_namespaces() {
+        let text = r#"<Window xmlns="http://schemas.microsoft.com/winfx/2006/xaml/presentation" xmlns:x="http://schemas.microsoft.com/winfx/2006/xaml" x:Class="App.Main">
+  <Grid.RowDefinitions><RowDefinition Height="Auto"/></Grid.RowDefinitions>
+  <TextBlock Text="a &amp; &quot;b&quot;">Hello <Run>big</Run> world</TextBlock>
+  <!-- note -->
+  <Button/>
+</Window>"#;
+        let doc = XamlDocument::parse(text).unwrap();
+        let written = doc.to_xaml(&WriteOptions::default());
+        assert_eq!(
+            written,
+            r#"<Window xmlns="http://schemas.microsoft.com/winfx/2006/xaml/presentation" xmlns:x="http://schemas.microsoft.com/winfx/2006/xaml" x:Class="App.Main">
+    <Grid.RowDefinitions>
+        <RowDefinition Height="Auto"/>
+    </Grid.RowDefinitions>
+    <TextBlock Text="a &amp; &quot;b&quot;">Hello <Run>big</Run> world</TextBlock>
+    <!-- note -->
+    <Button/>
+</Window>"#
+        );
+
+        let reparsed = XamlDocument::parse(&written).unwrap();
+        assert_eq!(reparsed.to_xaml(&WriteOptions::default()), written);
+    }
+
+    #[test]
+    fn keeps_declared_namespaces() {
+        let text = "<Page xmlns=\"urn:ui\" xmlns:d=\"urn:d\" xmlns:mc=\"urn:mc\" mc:Ignorable=\"d\">\
+                    <Grid xmlns:d=\"urn:d\"><Plain xmlns=\"\"/></Grid></Page>";
+        let doc = XamlDocument::parse(text).unwrap();
+        let options = WriteOptions {
+            indent: None,
+            ..WriteOptions::default()
+        };
+        assert_eq!(
+            doc.to_xaml(&options),
+            "<Page xmlns=\"urn:ui\" xmlns:d=\"urn:d\" xmlns:mc=\"urn:mc\" mc:Ignorable=\"d\">\
+             <Grid><Plain xmlns=\"\"/></Grid></Page>"
+        );
+    }
+
+    #[test]
+    fn preserves_whitespace_without_indent() {
+        let text = "<a>\n  <b>x</b>\n  <c></c>\n</a>";
+        let doc = XamlDocument::parse(text).unwrap();
+        let options = WriteOptions {
+            indent: None,
+            self_close_empty: false,
+            xml_declaration: true,
+        };
+        assert_eq!(
+            doc.to_xaml(&options),
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?><a>\n  <b>x</b>\n  <c></c>\n</a>"
+        );
+    }
+
+    #[test]
+    fn keeps_property_elements_in_place() {
+        let options = WriteOptions {
+            indent: None,
+            ..WriteOptions::default()
+        };
+        for text in [
+            "<Grid>\n  <Button/>\n  <Grid.RowDefinitions/>\n</Grid>",
+            "<Grid>\n  <Grid.RowDefinitions/>\n  <Grid.ColumnDefinitions/><Grid.Tag/>\n  <Button/>\n</Grid>",
+            "<Grid><!-- c --><Grid.RowDefinitions><RowDefinition/></Grid.RowDefinitions>text<Button/></Grid>",
+            "<Grid>a<Grid.Tag/>b<Grid.Style/>c</Grid>",
+            "<Grid>text<Grid.Tag/><!--c--></Grid>",
+            "<Grid><?a?><Grid.Tag/><?b?></Grid>",
+            "<Grid>x<![CDATA[y]]><Grid.Tag/>z</Grid>",
+        ] {
+            let doc = XamlDocument::parse(text).unwrap();
+            assert_eq!(doc.to_xaml(&options), text);
+        }
+
+        let text = "<Grid>\n  <Button/>\n  <Grid.RowDefinitions/>\n</Grid>";
+        let doc = XamlDocument::parse(text).unwrap();
+        assert_eq!(
+            doc.to_xaml(&WriteOptions::default()),
+            "<Grid>\n    <Button/>\n    <Grid.RowDefinitions/>\n</Grid>"
+        );
+    }
+
+    #[test]
+    fn writes_deep_tree() {
+        let depth = 100_000;
+        let text = format!("{}<a/>{}", "<a>\n".repeat(depth - 1), "\n</a>".repeat(depth - 1));
+        let options = ParseOptions {
+            max_depth: depth,
+            ..ParseOptions::default()
+        };
+        let doc = XamlDocument::parse_with_options(&text, &options).unwrap();
+        let options = WriteOptions {
+            indent: None,
+            ..WriteOptions::default()
+        };
+        assert_eq!(doc.to_xaml(&options), text);
+    }
+
+    #[test]
+    fn declares_namespaces_for_built_tree() {
+        let mut root = XamlElement::new("Grid");
+        root.namespace = Some("urn:ui".to_string());
+        let mut button = XamlElement::new("Button");
+        button.namespace = Some("urn:ui".to_string());
+        button.attributes.push(XamlAttribute {
+            namespace: Some("urn:x".to_string()),
+            prefix: Some("x".to_string()),
+            ..XamlAttribute::new("Name", "ok")
+        });
+        button.attributes.push(XamlAttribute {
+            namespace: Some("urn:other".to_string()),
+            ..XamlAttribute::new("Tag", "<1>\n")
+        });
+        let mut plain = XamlElement::new("Plain");
+        plain.children.push(XamlNode::CData("a]]>b".to_string()));
+        button.children.push(XamlNode::Element(plain));
+        root.children.push(XamlNode::Element(button));
+
+        let options = WriteOptions {
+            indent: Some("  ".to_string()),
+            ..WriteOptions::default()
+        };
+        assert_eq!(
+            write_xaml(&root, &options),
+            "<Grid xmlns=\"urn:ui\">\n  <Button xmlns:x=\"urn:x\" xmlns:ns=\"urn:other\" x:Name=\"ok\" ns:Tag=\"&lt;1>&#xA;\">\n    \
+             <Plain xmlns=\"\"><![CDATA[a]]]]><![CDATA[>b]]></Plain>\n  </Button>\n</Grid>"
+        );
+    }
+}
@@ -1,3 +1,6 @@
+﻿using System.Runtime.InteropServices;
+using xaml_parser.Structures;
+
 namespace xaml_parser.Native;
 
 /// <summary>
@@ -18,4 +21,53 @@ public static class Interop
     /// или определение имени библиотеки во время выполнения.
     /// </remarks>
     public const string NativeLib = "xaml_parser_native.dll";
-}
\ No newline at end of file
+
+    /// <summary>
+    /// Версия ABI нативной библиотеки, под которую написаны структуры Native*.
+    /// </summary>
+    /// <remarks>
+    /// Совпадает с XAML_ABI_VERSION в include/xaml_parser.h.
+    /// </remarks>
+    public const uint AbiVersion = 4;
+
+    [DllImport(NativeLib, EntryPoint = "xaml_parser_check_layout", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int CheckLayoutNative(uint abiVersion, nuint[] sizes, nuint len);
+
+    /// <summary>
+    /// Проверяет, что загруженная библиотека совместима с копиями структур в .NET.
+    /// </summary>
+    /// <remarks>
+    /// Размеры передаются по индексам XAML_LAYOUT_* из заголовка; 0 — структура
+    /// здесь не используется. При несовпадении библиотека не должна использоваться.
+    /// </remarks>
+    /// <exception cref="InvalidOperationException">Версия ABI или раскладка структур не совпадают.</exception>
+    public static unsafe void EnsureCompatible()
+    {
+        nuint[] sizes =
+        [
+            (nuint)Marshal.SizeOf<NativeTextSpan>(),
+            (nuint)Marshal.SizeOf<NativeXamlAttribute>(),
+            0,
+            (nuint)Marshal.SizeOf<NativeXamlElement>(),
+            (nuint)Marshal.SizeOf<NativeXamlPropertyElement>(),
+            (nuint)Marshal.SizeOf<NativeXamlParseError>(),
+            0,
+            0,
+            0,
+            0,
+            (nuint)Marshal.SizeOf<NativeXamlBatchResult>(),
+            (nuint)Marshal.SizeOf<NativeXamlStr>(),
+            (nuint)Marshal.SizeOf<NativeXamlFlatNode>(),
+            (nuint)Marshal.SizeOf<NativeXamlFlatAttribute>(),
+            (nuint)Marshal.SizeOf<NativeXamlFlatDocument>(),
+            (nuint)Marshal.SizeOf<NativeXamlStreamEvent>(),
+            (nuint)sizeof(NativeXamlEventHandler),
+            (nuint)Marshal.SizeOf<NativeXamlParseOptions>(),
+            (nuint)Marshal.SizeOf<NativeXamlNamespace>(),
+            (nuint)Marshal.SizeOf<NativeXamlDirectives>(),
+        ];
+        var result = CheckLayoutNative(AbiVersion, sizes, (nuint)sizes.Length);
+        if (result != 0)
+            throw new InvalidOperationException($"Native library {NativeLib} is incompatible with this build (ABI version {AbiVersion}, code {result})");
+    }
+}
@@ -0,0 +1,178 @@
+using System.Runtime.InteropServices;
+using System.Text;
+using xaml_parser.Structures;
+
+namespace xaml_parser.Native;
+
+/// <summary>
+/// Разобранный нативной частью документ с доступом к узлам по требованию.
+/// </summary>
+/// <remarks>
+/// Узлы адресуются индексами (корень — 0), в .NET строки преобразуются только
+/// для запрошенных узлов. Подходит, когда из документа нужно несколько значений.
+/// </remarks>
+public sealed class NativeXamlDocHandle : SafeHandle
+{
+    #region Native Methods
+
+    [DllImport(Interop.NativeLib, EntryPoint = "parse_xaml_handle_utf16", CallingConvention = CallingConvention.Cdecl, CharSet = CharSet.Unicode)]
+    private static extern int ParseXamlHandleUtf16Native(string xml, nuint len, out NativeXamlDocHandle result, nint error);
+
+    [DllImport(Interop.NativeLib, EntryPoint = "parse_xaml_handle_utf16_with_options", CallingConvention = CallingConvention.Cdecl, CharSet = CharSet.Unicode)]
+    private static extern int ParseXamlHandleUtf16WithOptionsNative(string xml, nuint len, ref NativeXamlParseOptions options, out NativeXamlDocHandle result, nint error);
+
+    [DllImport(Interop.NativeLib, EntryPoint = "free_xaml_doc_handle", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int FreeXamlDocHandleNative(nint doc);
+
+    [DllImport(Interop.NativeLib, EntryPoint = "xaml_node_kind", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int NodeKindNative(NativeXamlDocHandle doc, uint node, out int kind);
+
+    [DllImport(Interop.NativeLib, EntryPoint = "xaml_node_name", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int NodeNameNative(NativeXamlDocHandle doc, uint node, out nint name, out nuint len);
+
+    [DllImport(Interop.NativeLib, EntryPoint = "xaml_node_prefix", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int NodePrefixNative(NativeXamlDocHandle doc, uint node, out nint prefix, out nuint len);
+
+    [DllImport(Interop.NativeLib, EntryPoint = "xaml_node_namespace", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int NodeNamespaceNative(NativeXamlDocHandle doc, uint node, out nint uri, out nuint len);
+
+    [DllImport(Interop.NativeLib, EntryPoint = "xaml_node_child_count", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int NodeChildCountNative(NativeXamlDocHandle doc, uint node, out nuint count);
+
+    [DllImport(Interop.NativeLib, EntryPoint = "xaml_node_child", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int NodeChildNative(NativeXamlDocHandle doc, uint node, nuint index, out uint child);
+
+    [DllImport(Interop.NativeLib, EntryPoint = "xaml_node_attribute", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int NodeAttributeNative(NativeXamlDocHandle doc, uint node, [MarshalAs(UnmanagedType.LPUTF8Str)] string name, out nint value, out nuint len);
+
+    [DllImport(Interop.NativeLib, EntryPoint = "xaml_node_text", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int NodeTextNative(NativeXamlDocHandle doc, uint node, out nint text, out nuint len);
+
+    [DllImport(Interop.NativeLib, EntryPoint = "xaml_node_span", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int NodeSpanNative(NativeXamlDocHandle doc, uint node, out NativeTextSpan span);
+
+    #endregion
+
+    /// <summary>
+    /// Индекс корневого элемента.
+    /// </summary>
+    public const uint Root = 0;
+
+    private NativeXamlDocHandle() : base(0, true)
+    {
+    }
+
+    public override bool IsInvalid => handle == 0;
+
+    /// <summary>
+    /// Разбирает XAML; при ошибке разбора возвращает null.
+    /// </summary>
+    public static NativeXamlDocHandle? Parse(string xml)
+    {
+        var result = ParseXamlHandleUtf16Native(xml, (nuint)xml.Length, out var doc, 0);
+        if (result == 0 && !doc.IsInvalid)
+            return doc;
+        doc.Dispose();
+        return null;
+    }
+
+    /// <summary>
+    /// Разбирает XAML с ограничениями из options; при ошибке разбора возвращает null.
+    /// </summary>
+    /// <exception cref="InvalidDataException">Документ превысил одно из ограничений.</exception>
+    public static NativeXamlDocHandle? Parse(string xml, NativeXamlParseOptions options)
+    {
+        options.Size = (nuint)Marshal.SizeOf<NativeXamlParseOptions>();
+        var result = ParseXamlHandleUtf16WithOptionsNative(xml, (nuint)xml.Length, ref options, out var doc, 0);
+        if (result == 0 && !doc.IsInvalid)
+            return doc;
+        doc.Dispose();
+        // -10: превышено ограничение из options
+        if (result == -10)
+            throw new InvalidDataException("XAML document exceeds parse limits");
+        return null;
+    }
+
+    /// <summary>
+    /// Вид узла — значение NodeKind нативной части (0 — элемент).
+    /// </summary>
+    public int GetKind(uint node)
+    {
+        Check(NodeKindNative(this, node, out var kind));
+        return kind;
+    }
+
+    public string? GetName(uint node)
+    {
+        Check(NodeNameNative(this, node, out var name, out var len));
+        return ToString(name, len);
+    }
+
+    /// <summary>
+    /// Префикс имени элемента в записи документа; null, если его нет.
+    /// </summary>
+    public string? GetPrefix(uint node)
+    {
+        Check(NodePrefixNative(this, node, out var prefix, out var len));
+        return ToString(prefix, len);
+    }
+
+    /// <summary>
+    /// URI пространства имён элемента; null, если его нет.
+    /// </summary>
+    public string? GetNamespace(uint node)
+    {
+        Check(NodeNamespaceNative(this, node, out var uri, out var len));
+        return ToString(uri, len);
+    }
+
+    public int GetChildCount(uint node)
+    {
+        Check(NodeChildCountNative(this, node, out var count));
+        return (int)count;
+    }
+
+    public uint GetChild(uint node, int index)
+    {
+        Check(NodeChildNative(this, node, (nuint)index, out var child));
+        return child;
+    }
+
+    /// <summary>
+    /// Значение атрибута по имени в записи документа ("Width", "x:Name").
+    /// </summary>
+    public string? GetAttribute(uint node, string name)
+    {
+        Check(NodeAttributeNative(this, node, name, out var value, out var len));
+        return ToString(value, len);
+    }
+
+    public string? GetText(uint node)
+    {
+        Check(NodeTextNative(this, node, out var text, out var len));
+        return ToString(text, len);
+    }
+
+    public NativeTextSpan GetSpan(uint node)
+    {
+        Check(NodeSpanNative(this, node, out var span));
+        return span;
+    }
+
+    protected override bool ReleaseHandle()
+    {
+        return FreeXamlDocHandleNative(handle) == 0;
+    }
+
+    private static unsafe string? ToString(nint ptr, nuint len) =>
+        ptr == 0 ? null : Encoding.UTF8.GetString((byte*)ptr, (int)len);
+
+    private static void Check(int result)
+    {
+        // -7: индекс узла вне диапазона
+        if (result == -7)
+            throw new ArgumentOutOfRangeException("node");
+        if (result != 0)
+            throw new InvalidOperationException($"Native XAML accessor failed with code {result}");
+    }
+}
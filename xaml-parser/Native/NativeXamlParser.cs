@@ -5,10 +5,50 @@ namespace xaml_parser.Native;
 
 public static class NativeXamlParser
 {
+    // Несовместимая библиотека обнаруживается при первом обращении, а не порчей памяти
+    static NativeXamlParser()
+    {
+        Interop.EnsureCompatible();
+    }
+
     #region Native Methods
 
-    [DllImport(Interop.NativeLib, EntryPoint = "parse_xaml", CallingConvention = CallingConvention.Cdecl)]
-    private static extern int ParseXamlNative([MarshalAs(UnmanagedType.LPStr)] string xml, out nint result);
+    // Строка передаётся как UTF-16 без перекодирования (LPStr портил бы не-ASCII символы).
+    // Дерево строится в арене и освобождается одним вызовом free_xaml_arena
+    [DllImport(Interop.NativeLib, EntryPoint = "parse_xaml_arena_utf16", CallingConvention = CallingConvention.Cdecl, CharSet = CharSet.Unicode)]
+    private static extern int ParseXamlArenaUtf16Native(string xml, nuint len, uint flags, out nint arena, out nint root, nint error);
+
+    [DllImport(Interop.NativeLib, EntryPoint = "free_xaml_arena", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int FreeXamlArenaNative(nint arena);
+
+    // Кодировка определяется нативной частью по BOM и XML-объявлению
+    [DllImport(Interop.NativeLib, EntryPoint = "parse_xaml_bytes", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int ParseXamlBytesNative(byte[] bytes, nuint len, uint flags, out nint result, out int encoding, nint error);
+
+    // Файл читается нативной частью, текст не проходит через .NET строку
+    [DllImport(Interop.NativeLib, EntryPoint = "parse_xaml_file", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int ParseXamlFileNative([MarshalAs(UnmanagedType.LPUTF8Str)] string path, uint flags, out nint result, out int encoding, out nint error);
+
+    [DllImport(Interop.NativeLib, EntryPoint = "free_xaml_parse_error", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int FreeXamlParseErrorNative(nint error);
+
+    // Файлы каталога разбираются параллельно за один вызов
+    [DllImport(Interop.NativeLib, EntryPoint = "parse_xaml_directory", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int ParseXamlDirectoryNative(
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string directory,
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string pattern,
+        [MarshalAs(UnmanagedType.U1)] bool recursive,
+        uint flags,
+        nuint threads,
+        out nint results,
+        out nuint resultsLen);
+
+    [DllImport(Interop.NativeLib, EntryPoint = "free_xaml_batch", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int FreeXamlBatchNative(nint results, nuint resultsLen);
+
+    // Разбор с ограничениями для недоверенного ввода; текст передаётся в UTF-8
+    [DllImport(Interop.NativeLib, EntryPoint = "parse_xaml_with_options", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int ParseXamlWithOptionsNative(byte[] xml, nuint len, ref NativeXamlParseOptions options, out nint result, nint error);
 
     [DllImport(Interop.NativeLib, EntryPoint = "free_xaml_element", CallingConvention = CallingConvention.Cdecl)]
     private static extern int FreeXamlElementNative(nint element);
@@ -21,6 +61,150 @@ public static class NativeXamlParser
         return wrapper.Element;
     }
 
+    public static XamlElement? ParseXamlBytes(byte[] bytes)
+    {
+        using var wrapper = new XamlElementWrapper(bytes);
+        return wrapper.Element;
+    }
+
+    /// <summary>
+    /// Читает и разбирает файл в нативной библиотеке.
+    /// </summary>
+    /// <exception cref="UnauthorizedAccessException">Нет прав на чтение файла.</exception>
+    /// <exception cref="IOException">Файл не удалось прочитать.</exception>
+    /// <exception cref="XamlParseException">Документ не удалось декодировать или распарсить.</exception>
+    public static XamlElement? ParseXamlFile(string path)
+    {
+        var result = ParseXamlFileNative(path, 0, out var elementPtr, out _, out var errorPtr);
+        if (errorPtr != 0)
+        {
+            var error = MarshalParseError(errorPtr);
+            // -6: файл не удалось прочитать; 15 — XAML_ERROR_KIND_ACCESS_DENIED
+            if (error.Kind == 15)
+                throw new UnauthorizedAccessException($"Access denied to XAML file: {path}", error);
+            if (result == -6)
+                throw new IOException($"Failed to read XAML file: {path}", error);
+            throw error;
+        }
+        if (result != 0 || elementPtr == 0)
+            return null;
+
+        try
+        {
+            return MarshalXamlElement(elementPtr);
+        }
+        finally
+        {
+            FreeXamlElementNative(elementPtr);
+        }
+    }
+
+    /// <summary>
+    /// Разбирает XAML с ограничениями размера, числа узлов, атрибутов и раскрытия сущностей.
+    /// </summary>
+    /// <exception cref="InvalidDataException">Документ превысил одно из ограничений.</exception>
+    public static XamlElement? ParseXaml(string xml, NativeXamlParseOptions options)
+    {
+        var bytes = System.Text.Encoding.UTF8.GetBytes(xml);
+        options.Size = (nuint)Marshal.SizeOf<NativeXamlParseOptions>();
+        var result = ParseXamlWithOptionsNative(bytes, (nuint)bytes.Length, ref options, out var elementPtr, 0);
+        // -10: превышено ограничение из options
+        if (result == -10)
+            throw new InvalidDataException("XAML document exceeds parse limits");
+        if (result != 0 || elementPtr == 0)
+            return null;
+
+        try
+        {
+            return MarshalXamlElement(elementPtr);
+        }
+        finally
+        {
+            FreeXamlElementNative(elementPtr);
+        }
+    }
+
+    /// <summary>
+    /// Разбирает XAML с правилами Markup Compatibility: элементы и атрибуты непонятых
+    /// пространств имён из mc:Ignorable удаляются, из mc:AlternateContent выбирается ветка.
+    /// </summary>
+    /// <param name="understoodNamespaces">URI пространств имён, которые понимает хост.</param>
+    public static XamlElement? ParseXaml(string xml, NativeXamlParseOptions options, IReadOnlyList<string> understoodNamespaces)
+    {
+        var strings = new nint[understoodNamespaces.Count];
+        var array = Marshal.AllocHGlobal(nint.Size * Math.Max(strings.Length, 1));
+        try
+        {
+            for (var i = 0; i < strings.Length; i++)
+                strings[i] = Marshal.StringToCoTaskMemUTF8(understoodNamespaces[i]);
+            Marshal.Copy(strings, 0, array, strings.Length);
+
+            options.MarkupCompatibility = true;
+            options.UnderstoodNamespaces = array;
+            options.UnderstoodNamespacesLen = (nuint)strings.Length;
+            return ParseXaml(xml, options);
+        }
+        finally
+        {
+            foreach (var ptr in strings)
+                Marshal.FreeCoTaskMem(ptr);
+            Marshal.FreeHGlobal(array);
+        }
+    }
+
+    /// <summary>
+    /// Разбирает файлы каталога, подходящие под шаблон, на нативном пуле потоков.
+    /// Файлы, которые не удалось прочитать или распарсить, пропускаются.
+    /// </summary>
+    public static List<XamlElement> ParseXamlDirectory(string directoryPath, string searchPattern)
+    {
+        var result = ParseXamlDirectoryNative(directoryPath, searchPattern, false, 0, 0, out var results, out var resultsLen);
+        // -6: каталог не удалось прочитать
+        if (result == -6)
+            throw new IOException($"Failed to read directory: {directoryPath}");
+
+        var elements = new List<XamlElement>();
+        if (result != 0 || results == 0)
+            return elements;
+
+        try
+        {
+            for (int i = 0; i < (int)resultsLen; i++)
+            {
+                var itemPtr = results + i * Marshal.SizeOf<NativeXamlBatchResult>();
+                var item = Marshal.PtrToStructure<NativeXamlBatchResult>(itemPtr);
+                if (item.Code == 0 && item.Element != 0)
+                {
+                    elements.Add(MarshalXamlElement(item.Element));
+                }
+            }
+        }
+        finally
+        {
+            FreeXamlBatchNative(results, resultsLen);
+        }
+        return elements;
+    }
+
+    // Копирует ошибку в исключение и освобождает нативную структуру
+    private static XamlParseException MarshalParseError(nint ptr)
+    {
+        try
+        {
+            var native = Marshal.PtrToStructure<NativeXamlParseError>(ptr);
+            return new XamlParseException(
+                native.Kind,
+                Marshal.PtrToStringUTF8(native.Message) ?? string.Empty,
+                native.Line,
+                native.Column,
+                native.FilePath != 0 ? Marshal.PtrToStringUTF8(native.FilePath) : null);
+        }
+        finally
+        {
+            FreeXamlParseErrorNative(ptr);
+        }
+    }
+
     internal static XamlElement MarshalXamlElement(nint ptr)
     {
         var native = Marshal.PtrToStructure<NativeXamlElement>(ptr);
@@ -36,37 +220,106 @@ public static class NativeXamlParser
                 var attrPtr = native.Attributes + i * Marshal.SizeOf<NativeXamlAttribute>();
                 var attr = Marshal.PtrToStructure<NativeXamlAttribute>(attrPtr);
                 var key = Marshal.PtrToStringUTF8(attr.Key) ?? string.Empty;
+                if (attr.Prefix != 0)
+                {
+                    key = $"{Marshal.PtrToStringUTF8(attr.Prefix)}:{key}";
+                }
                 var value = Marshal.PtrToStringUTF8(attr.Value) ?? string.Empty;
                 attributes[key] = value;
             }
         }
 
-        var children = new List<XamlElement>();
-        if (native.Children != 0 && native.ChildrenLen > 0)
+        // Элементы свойств (Grid.RowDefinitions) нативная часть отдаёт отдельно
+        // в порядке документа; ChildIndex ставит каждый перед Children[ChildIndex]
+        var properties = new List<NativeXamlPropertyElement>();
+        if (native.Properties != 0 && native.PropertiesLen > 0)
         {
-            for (int i = 0; i < (int)native.ChildrenLen; i++)
+            for (int i = 0; i < (int)native.PropertiesLen; i++)
             {
-                var childPtr = Marshal.ReadIntPtr(native.Children + i * nint.Size);
-                if (childPtr != 0)
+                var propertyPtr = native.Properties + i * Marshal.SizeOf<NativeXamlPropertyElement>();
+                var property = Marshal.PtrToStructure<NativeXamlPropertyElement>(propertyPtr);
+                if (property.Element != 0)
                 {
-                    children.Add(MarshalXamlElement(childPtr));
+                    properties.Add(property);
                 }
             }
         }
 
-        return new XamlElement(name, ns, attributes, children, textContent);
+        var elementPtrs = new List<nint>();
+        var nextProperty = 0;
+        var childrenLen = native.Children != 0 ? (int)native.ChildrenLen : 0;
+        for (int i = 0; i < childrenLen; i++)
+        {
+            while (nextProperty < properties.Count && properties[nextProperty].ChildIndex <= (nuint)i)
+            {
+                elementPtrs.Add(properties[nextProperty++].Element);
+            }
+
+            var childPtr = Marshal.ReadIntPtr(native.Children + i * nint.Size);
+            if (childPtr != 0)
+            {
+                elementPtrs.Add(childPtr);
+            }
+        }
+
+        while (nextProperty < properties.Count)
+        {
+            elementPtrs.Add(properties[nextProperty++].Element);
+        }
+
+        var children = elementPtrs.Select(MarshalXamlElement).ToList();
+
+        var namespaces = new List<XamlNamespace>();
+        for (int i = 0; i < (int)native.NamespacesLen; i++)
+        {
+            var namespacePtr = native.Namespaces + i * Marshal.SizeOf<NativeXamlNamespace>();
+            var declaration = Marshal.PtrToStructure<NativeXamlNamespace>(namespacePtr);
+            namespaces.Add(new XamlNamespace(
+                declaration.Prefix != 0 ? Marshal.PtrToStringUTF8(declaration.Prefix) : null,
+                Marshal.PtrToStringUTF8(declaration.Uri) ?? string.Empty,
+                declaration.ClrNamespace != 0 ? Marshal.PtrToStringUTF8(declaration.ClrNamespace) : null,
+                declaration.Assembly != 0 ? Marshal.PtrToStringUTF8(declaration.Assembly) : null));
+        }
+
+        var d = native.Directives;
+        string? Utf8(nint ptr) => ptr != 0 ? Marshal.PtrToStringUTF8(ptr) : null;
+        var directives = new XamlDirectives(
+            Utf8(d.Class),
+            Utf8(d.Subclass),
+            Utf8(d.Name),
+            Utf8(d.Key),
+            Utf8(d.Uid),
+            Utf8(d.FieldModifier),
+            Utf8(d.TypeArguments),
+            d.Shared < 0 ? null : d.Shared != 0);
+
+        return new XamlElement(name, ns, attributes, children, textContent)
+        {
+            Namespaces = namespaces,
+            Directives = directives,
+        };
     }
 
     internal sealed class XamlElementWrapper : IDisposable
     {
         private nint _elementPtr;
+        private nint _arenaPtr;
         private bool _disposed;
 
         public XamlElement? Element { get; }
 
         public XamlElementWrapper(string xml)
         {
-            var result = ParseXamlNative(xml, out _elementPtr);
+            var result = ParseXamlArenaUtf16Native(xml, (nuint)xml.Length, 0, out _arenaPtr, out var root, 0);
+            if (result == 0 && root != 0)
+            {
+                Element = MarshalXamlElement(root);
+            }
+        }
+
+        public XamlElementWrapper(byte[] bytes)
+        {
+            var result = ParseXamlBytesNative(bytes, (nuint)bytes.Length, 0, out _elementPtr, out _, 0);
             if (result == 0 && _elementPtr != 0)
             {
                 Element = MarshalXamlElement(_elementPtr);
@@ -75,6 +328,12 @@ public static class NativeXamlParser
 
         public void Dispose()
         {
+            if (!_disposed && _arenaPtr != 0)
+            {
+                FreeXamlArenaNative(_arenaPtr);
+                _arenaPtr = 0;
+                _disposed = true;
+            }
             if (!_disposed && _elementPtr != 0)
             {
                 FreeXamlElementNative(_elementPtr);
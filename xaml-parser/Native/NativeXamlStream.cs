@@ -0,0 +1,128 @@
+using System.Runtime.CompilerServices;
+using System.Runtime.InteropServices;
+using System.Text;
+using xaml_parser.Structures;
+
+namespace xaml_parser.Native;
+
+/// <summary>
+/// Получатель событий потокового разбора.
+/// </summary>
+public interface IXamlStreamHandler
+{
+    void OnStartElement(string name, string? ns, int depth);
+    void OnAttribute(string name, string? ns, string value);
+    void OnEndElement(string name, string? ns, int depth);
+    void OnText(string text);
+}
+
+/// <summary>
+/// Потоковый разбор XAML без построения дерева.
+/// </summary>
+/// <remarks>
+/// Подходит для больших сгенерированных файлов (словари ресурсов, наборы иконок):
+/// нативная часть не держит копию документа, события передаются по мере чтения.
+/// </remarks>
+public static unsafe class NativeXamlStream
+{
+    [DllImport(Interop.NativeLib, EntryPoint = "parse_xaml_stream", CallingConvention = CallingConvention.Cdecl)]
+    private static extern int ParseXamlStreamNative(byte* xml, nuint len, NativeXamlEventHandler* handler, nint error);
+
+    // Состояние вызова: получатель и исключение, прервавшее разбор
+    private sealed class State(IXamlStreamHandler handler)
+    {
+        public readonly IXamlStreamHandler Handler = handler;
+        public Exception? Exception;
+    }
+
+    /// <summary>
+    /// Разбирает UTF-8 текст, вызывая методы handler в порядке документа.
+    /// </summary>
+    /// <returns>false, если документ некорректен.</returns>
+    public static bool Parse(ReadOnlySpan<byte> utf8, IXamlStreamHandler handler)
+    {
+        var state = new State(handler);
+        var stateHandle = GCHandle.Alloc(state);
+        try
+        {
+            var table = new NativeXamlEventHandler
+            {
+                UserData = GCHandle.ToIntPtr(stateHandle),
+                StartElement = &OnStartElement,
+                Attribute = &OnAttribute,
+                EndElement = &OnEndElement,
+                Text = &OnText,
+                CData = &OnText,
+            };
+            int result;
+            fixed (byte* xml = utf8)
+            {
+                result = ParseXamlStreamNative(xml, (nuint)utf8.Length, &table, 0);
+            }
+            // -9: разбор прерван исключением в обработчике
+            if (result == -9 && state.Exception != null)
+                throw new InvalidOperationException("XAML stream handler failed", state.Exception);
+            return result == 0;
+        }
+        finally
+        {
+            stateHandle.Free();
+        }
+    }
+
+    public static bool Parse(string xml, IXamlStreamHandler handler) =>
+        Parse(Encoding.UTF8.GetBytes(xml), handler);
+
+    private static string? ToString(nint ptr, nuint len) =>
+        ptr == 0 ? null : Encoding.UTF8.GetString((byte*)ptr, (int)len);
+
+    // Исключение не должно пересекать нативные кадры: оно сохраняется, разбор прерывается
+    private static int Dispatch(nint userData, Action<IXamlStreamHandler> action)
+    {
+        var state = (State)GCHandle.FromIntPtr(userData).Target!;
+        try
+        {
+            action(state.Handler);
+            return 0;
+        }
+        catch (Exception e)
+        {
+            state.Exception = e;
+            return 1;
+        }
+    }
+
+    [UnmanagedCallersOnly(CallConvs = [typeof(CallConvCdecl)])]
+    private static int OnStartElement(nint userData, NativeXamlStreamEvent* e)
+    {
+        var name = ToString(e->Name, e->NameLen) ?? string.Empty;
+        var ns = ToString(e->Namespace, e->NamespaceLen);
+        var depth = (int)e->Depth;
+        return Dispatch(userData, h => h.OnStartElement(name, ns, depth));
+    }
+
+    [UnmanagedCallersOnly(CallConvs = [typeof(CallConvCdecl)])]
+    private static int OnAttribute(nint userData, NativeXamlStreamEvent* e)
+    {
+        var name = ToString(e->Name, e->NameLen) ?? string.Empty;
+        var ns = ToString(e->Namespace, e->NamespaceLen);
+        var value = ToString(e->Value, e->ValueLen) ?? string.Empty;
+        return Dispatch(userData, h => h.OnAttribute(name, ns, value));
+    }
+
+    [UnmanagedCallersOnly(CallConvs = [typeof(CallConvCdecl)])]
+    private static int OnEndElement(nint userData, NativeXamlStreamEvent* e)
+    {
+        var name = ToString(e->Name, e->NameLen) ?? string.Empty;
+        var ns = ToString(e->Namespace, e->NamespaceLen);
+        var depth = (int)e->Depth;
+        return Dispatch(userData, h => h.OnEndElement(name, ns, depth));
+    }
+
+    [UnmanagedCallersOnly(CallConvs = [typeof(CallConvCdecl)])]
+    private static int OnText(nint userData, NativeXamlStreamEvent* e)
+    {
+        var text = ToString(e->Value, e->ValueLen) ?? string.Empty;
+        return Dispatch(userData, h => h.OnText(text));
+    }
+}
@@ -0,0 +1,21 @@
+using System.Runtime.InteropServices;
+
+namespace xaml_parser.Structures;
+
+/// <summary>
+/// Нативная структура диапазона в исходном тексте.
+/// </summary>
+/// <remarks>
+/// Смещения указаны в байтах UTF-8, строки и столбцы считаются с 1,
+/// столбец — в символах.
+/// </remarks>
+[StructLayout(LayoutKind.Sequential)]
+public struct NativeTextSpan
+{
+    public nuint Start;
+    public nuint End;
+    public uint StartLine;
+    public uint StartColumn;
+    public uint EndLine;
+    public uint EndColumn;
+}
@@ -7,7 +7,7 @@ namespace xaml_parser.Structures;
 /// </summary>
 /// <remarks>
 /// Используется для маршалинга данных между C# и Rust.
-/// Оба поля являются указателями на UTF-8 строки в неуправляемой памяти.
+/// Строковые поля являются указателями на UTF-8 строки в неуправляемой памяти.
 /// </remarks>
 [StructLayout(LayoutKind.Sequential)]
 public struct NativeXamlAttribute
@@ -21,4 +21,40 @@ public struct NativeXamlAttribute
     /// Указатель на UTF-8 строку со значением атрибута.
     /// </summary>
     public nint Value;
+
+    /// <summary>
+    /// Указатель на UTF-8 строку с URI пространства имён атрибута (0, если его нет).
+    /// </summary>
+    public nint Namespace;
+
+    /// <summary>
+    /// Указатель на UTF-8 строку с исходным префиксом атрибута (0, если его нет).
+    /// </summary>
+    public nint Prefix;
+
+    /// <summary>
+    /// Диапазон всего атрибута в исходном тексте.
+    /// </summary>
+    public NativeTextSpan Span;
+
+    /// <summary>
+    /// Диапазон значения атрибута без кавычек.
+    /// </summary>
+    public NativeTextSpan ValueSpan;
+
+    /// <summary>
+    /// Вид атрибута: 0 — обычное свойство, 1 — присоединённое свойство (Grid.Row),
+    /// 2 — директива языка XAML (x:Name).
+    /// </summary>
+    public int Kind;
+
+    /// <summary>
+    /// Указатель на UTF-8 строку с типом-владельцем для имён с точкой (0, если точки нет).
+    /// </summary>
+    public nint OwnerType;
+
+    /// <summary>
+    /// Указатель на UTF-8 строку с именем члена без типа-владельца.
+    /// </summary>
+    public nint Member;
 }
\ No newline at end of file
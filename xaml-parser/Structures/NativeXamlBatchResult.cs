@@ -0,0 +1,20 @@
+using System.Runtime.InteropServices;
+
+namespace xaml_parser.Structures;
+
+/// <summary>
+/// Нативная структура результата разбора одного файла в пакете.
+/// </summary>
+/// <remarks>
+/// При Code == 0 заполнен Element, иначе Error. Encoding равен -1,
+/// если файл не удалось прочитать или декодировать.
+/// </remarks>
+[StructLayout(LayoutKind.Sequential)]
+public struct NativeXamlBatchResult
+{
+    public nint Path;
+    public int Code;
+    public int Encoding;
+    public nint Element;
+    public nint Error;
+}
@@ -0,0 +1,38 @@
+using System.Runtime.InteropServices;
+
+namespace xaml_parser.Structures;
+
+/// <summary>
+/// Нативная структура директив языка XAML (x:Class, x:Name, ...) элемента.
+/// </summary>
+/// <remarks>
+/// Строки равны 0, если директивы нет. Shared: -1 — x:Shared нет, 0 — false, 1 — true.
+/// </remarks>
+[StructLayout(LayoutKind.Sequential)]
+public struct NativeXamlDirectives
+{
+    public nint Class;
+    public nint Subclass;
+    public nint Name;
+    public nint Key;
+    public nint Uid;
+    public nint FieldModifier;
+    public nint TypeArguments;
+    public int Shared;
+}
+
+/// <summary>
+/// Директивы x: элемента; null — директивы нет. TypeArguments в записи документа.
+/// </summary>
+public record XamlDirectives(
+    string? Class,
+    string? Subclass,
+    string? Name,
+    string? Key,
+    string? Uid,
+    string? FieldModifier,
+    string? TypeArguments,
+    bool? Shared)
+{
+    public static XamlDirectives Empty { get; } = new(null, null, null, null, null, null, null, null);
+}
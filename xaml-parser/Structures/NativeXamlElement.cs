@@ -13,6 +13,16 @@ public struct NativeXamlElement
     public nint Children;
     public nuint ChildrenLen;
     public nint TextContent;
+    public nint Prefix;
+    public nint Nodes;
+    public nuint NodesLen;
+    public NativeTextSpan Span;
+    public NativeTextSpan StartTagSpan;
+    public nint Properties;
+    public nuint PropertiesLen;
+    public nint Namespaces;
+    public nuint NamespacesLen;
+    public NativeXamlDirectives Directives;
 }
 public record XamlElement(
     string Name, 
@@ -22,6 +32,16 @@ public record XamlElement(
     string? TextContent
 )
 {
+    /// <summary>
+    /// Объявления xmlns на самом элементе в порядке документа.
+    /// </summary>
+    public IReadOnlyList<XamlNamespace> Namespaces { get; init; } = [];
+
+    /// <summary>
+    /// Директивы x: элемента (x:Class, x:Name, x:Key, ...).
+    /// </summary>
+    public XamlDirectives Directives { get; init; } = XamlDirectives.Empty;
+
     public string? GetAttribute(string name) => 
         Attributes.GetValueOrDefault(name);
 
@@ -0,0 +1,22 @@
+using System.Runtime.InteropServices;
+
+namespace xaml_parser.Structures;
+
+/// <summary>
+/// Нативная таблица обработчиков parse_xaml_stream.
+/// </summary>
+/// <remarks>
+/// Нулевой указатель — событие пропускается. Ненулевой результат прерывает разбор.
+/// </remarks>
+[StructLayout(LayoutKind.Sequential)]
+public unsafe struct NativeXamlEventHandler
+{
+    public nint UserData;
+    public delegate* unmanaged[Cdecl]<nint, NativeXamlStreamEvent*, int> StartElement;
+    public delegate* unmanaged[Cdecl]<nint, NativeXamlStreamEvent*, int> Attribute;
+    public delegate* unmanaged[Cdecl]<nint, NativeXamlStreamEvent*, int> EndElement;
+    public delegate* unmanaged[Cdecl]<nint, NativeXamlStreamEvent*, int> Text;
+    public delegate* unmanaged[Cdecl]<nint, NativeXamlStreamEvent*, int> CData;
+    public delegate* unmanaged[Cdecl]<nint, NativeXamlStreamEvent*, int> Comment;
+    public delegate* unmanaged[Cdecl]<nint, NativeXamlStreamEvent*, int> ProcessingInstruction;
+}
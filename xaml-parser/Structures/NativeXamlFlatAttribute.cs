@@ -0,0 +1,21 @@
+using System.Runtime.InteropServices;
+
+namespace xaml_parser.Structures;
+
+/// <summary>
+/// Нативная структура атрибута плоского документа.
+/// </summary>
+[StructLayout(LayoutKind.Sequential)]
+public struct NativeXamlFlatAttribute
+{
+    public uint Element;
+    public int Kind;
+    public NativeXamlStr Name;
+    public NativeXamlStr Value;
+    public NativeXamlStr Namespace;
+    public NativeXamlStr Prefix;
+    public NativeXamlStr OwnerType;
+    public NativeXamlStr Member;
+    public NativeTextSpan Span;
+    public NativeTextSpan ValueSpan;
+}
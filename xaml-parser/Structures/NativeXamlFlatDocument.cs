@@ -0,0 +1,44 @@
+using System.Runtime.InteropServices;
+using System.Text;
+
+namespace xaml_parser.Structures;
+
+/// <summary>
+/// Нативный заголовок плоского документа; корень — Nodes[0].
+/// </summary>
+/// <remarks>
+/// Массивы читаются как Span без копирования. Срезы строк указывают в буфер,
+/// переданный в parse_xaml_flat, поэтому он должен жить до free_xaml_flat.
+/// </remarks>
+[StructLayout(LayoutKind.Sequential)]
+public unsafe struct NativeXamlFlatDocument
+{
+    public nint Nodes;
+    public nuint NodesLen;
+    public nint Attributes;
+    public nuint AttributesLen;
+    public nuint InputLen;
+    public nint Extra;
+    public nuint ExtraLen;
+
+    public readonly ReadOnlySpan<NativeXamlFlatNode> NodeSpan =>
+        new((void*)Nodes, (int)NodesLen);
+
+    public readonly ReadOnlySpan<NativeXamlFlatAttribute> AttributeSpan =>
+        new((void*)Attributes, (int)AttributesLen);
+
+    /// <summary>
+    /// Байты UTF-8 среза; input — исходный буфер документа.
+    /// </summary>
+    public readonly ReadOnlySpan<byte> GetBytes(ReadOnlySpan<byte> input, NativeXamlStr str)
+    {
+        if (!str.HasValue)
+            return default;
+        if (str.Offset < InputLen)
+            return input.Slice((int)str.Offset, (int)str.Len);
+        return new ReadOnlySpan<byte>((void*)Extra, (int)ExtraLen).Slice((int)(str.Offset - InputLen), (int)str.Len);
+    }
+
+    public readonly string? GetString(ReadOnlySpan<byte> input, NativeXamlStr str) =>
+        str.HasValue ? Encoding.UTF8.GetString(GetBytes(input, str)) : null;
+}
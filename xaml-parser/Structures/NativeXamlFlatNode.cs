@@ -0,0 +1,28 @@
+using System.Runtime.InteropServices;
+
+namespace xaml_parser.Structures;
+
+/// <summary>
+/// Нативная структура узла плоского документа.
+/// </summary>
+/// <remarks>
+/// Связи заданы индексами в массиве узлов, uint.MaxValue — узла нет.
+/// Атрибуты элемента занимают FirstAttribute..FirstAttribute + AttributesLen.
+/// </remarks>
+[StructLayout(LayoutKind.Sequential)]
+public struct NativeXamlFlatNode
+{
+    public int Kind;
+    public uint Parent;
+    public uint FirstChild;
+    public uint NextSibling;
+    public uint FirstAttribute;
+    public uint AttributesLen;
+    public NativeXamlStr Name;
+    public NativeXamlStr Namespace;
+    public NativeXamlStr Prefix;
+    public NativeXamlStr Text;
+    public NativeXamlStr OwnerType;
+    public NativeXamlStr Member;
+    public NativeTextSpan Span;
+}
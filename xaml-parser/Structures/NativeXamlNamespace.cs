@@ -0,0 +1,24 @@
+using System.Runtime.InteropServices;
+
+namespace xaml_parser.Structures;
+
+/// <summary>
+/// Нативная структура объявления xmlns на элементе.
+/// </summary>
+/// <remarks>
+/// ClrNamespace и Assembly разобраны из URI clr-namespace: и using:, для остальных URI равны 0.
+/// </remarks>
+[StructLayout(LayoutKind.Sequential)]
+public struct NativeXamlNamespace
+{
+    public nint Prefix;
+    public nint Uri;
+    public nint ClrNamespace;
+    public nint Assembly;
+}
+
+/// <summary>
+/// Объявление пространства имён: Prefix равен null для пространства имён по умолчанию,
+/// ClrNamespace и Assembly заполнены для URI clr-namespace: и using:.
+/// </summary>
+public record XamlNamespace(string? Prefix, string Uri, string? ClrNamespace, string? Assembly);
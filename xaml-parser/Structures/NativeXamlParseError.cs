@@ -0,0 +1,21 @@
+using System.Runtime.InteropServices;
+
+namespace xaml_parser.Structures;
+
+/// <summary>
+/// Нативная структура ошибки разбора XamlParseError.
+/// </summary>
+/// <remarks>
+/// Line и Column считаются с 1, для ошибок чтения файла равны 0.
+/// FilePath заполнен только для ошибок parse_xaml_file. Освобождается через free_xaml_parse_error.
+/// </remarks>
+[StructLayout(LayoutKind.Sequential)]
+public struct NativeXamlParseError
+{
+    public int Kind;
+    public uint Line;
+    public uint Column;
+    public nuint Offset;
+    public nint Message;
+    public nint FilePath;
+}
@@ -0,0 +1,32 @@
+using System.Runtime.InteropServices;
+
+namespace xaml_parser.Structures;
+
+/// <summary>
+/// Нативная структура параметров parse_xaml_with_options.
+/// </summary>
+/// <remarks>
+/// Size заполняется размером структуры; нулевые поля означают значения по умолчанию:
+/// глубина 1024, остальные ограничения отсутствуют. Превышение ограничения — код -10.
+/// UnderstoodNamespaces — массив указателей на строки UTF-8 длиной UnderstoodNamespacesLen,
+/// читается только при MarkupCompatibility.
+/// Флаг 2 в Flags включает проверку размещения директив x:; нарушение — код -3.
+/// </remarks>
+[StructLayout(LayoutKind.Sequential)]
+public struct NativeXamlParseOptions
+{
+    public nuint Size;
+    public uint Flags;
+    public nuint MaxDepth;
+    public nuint MaxInputSize;
+    public uint MaxNodes;
+    public nuint MaxAttributes;
+    public nuint MaxAttributeValueLen;
+    public nuint MaxEntityExpansion;
+    [MarshalAs(UnmanagedType.U1)]
+    public bool AllowDtd;
+    [MarshalAs(UnmanagedType.U1)]
+    public bool MarkupCompatibility;
+    public nint UnderstoodNamespaces;
+    public nuint UnderstoodNamespacesLen;
+}
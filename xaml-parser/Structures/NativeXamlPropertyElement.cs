@@ -0,0 +1,21 @@
+using System.Runtime.InteropServices;
+
+namespace xaml_parser.Structures;
+
+/// <summary>
+/// Нативная структура элемента свойства (например, Grid.RowDefinitions).
+/// </summary>
+/// <remarks>
+/// OwnerType и Member указывают на UTF-8 строки, Element — на NativeXamlElement
+/// с содержимым свойства. NodeIndex и ChildIndex — место элемента свойства в документе:
+/// перед Nodes[NodeIndex] и Children[ChildIndex] владельца (индекс, равный длине, — в конце).
+/// </remarks>
+[StructLayout(LayoutKind.Sequential)]
+public struct NativeXamlPropertyElement
+{
+    public nint OwnerType;
+    public nint Member;
+    public nint Element;
+    public nuint NodeIndex;
+    public nuint ChildIndex;
+}
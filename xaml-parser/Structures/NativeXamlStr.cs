@@ -0,0 +1,19 @@
+using System.Runtime.InteropServices;
+
+namespace xaml_parser.Structures;
+
+/// <summary>
+/// Нативная структура среза строки плоского документа.
+/// </summary>
+/// <remarks>
+/// Offset меньше длины входа указывает в исходный буфер UTF-8, иначе в буфер
+/// Extra со смещением Offset - InputLen. Offset == nuint.MaxValue — значения нет.
+/// </remarks>
+[StructLayout(LayoutKind.Sequential)]
+public struct NativeXamlStr
+{
+    public nuint Offset;
+    public nuint Len;
+
+    public readonly bool HasValue => Offset != nuint.MaxValue;
+}
@@ -0,0 +1,26 @@
+using System.Runtime.InteropServices;
+
+namespace xaml_parser.Structures;
+
+/// <summary>
+/// Нативная структура события потокового разбора.
+/// </summary>
+/// <remarks>
+/// Строки — указатель и длина в UTF-8, действительны только во время обработчика;
+/// нулевой указатель — значения нет.
+/// </remarks>
+[StructLayout(LayoutKind.Sequential)]
+public struct NativeXamlStreamEvent
+{
+    public nint Prefix;
+    public nuint PrefixLen;
+    public nint Name;
+    public nuint NameLen;
+    public nint Namespace;
+    public nuint NamespaceLen;
+    public nint Value;
+    public nuint ValueLen;
+    public nuint Start;
+    public nuint End;
+    public uint Depth;
+}
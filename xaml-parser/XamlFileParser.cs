@@ -1,4 +1,5 @@
 using System.Text;
+using xaml_parser.Native;
 
 namespace xaml_parser;
 
@@ -28,14 +29,15 @@ public sealed class XamlFileParser : IDisposable
     #region Public Methods
 
     /// <summary>
-    /// Парсит XAML файл с использованием UTF-8 кодировки.
+    /// Парсит XAML файл, определяя кодировку по BOM и XML-объявлению.
     /// </summary>
     /// <param name="filePath">Путь к XAML файлу.</param>
-    /// <returns>Распарсенный XAML документ или null, если парсинг не удался.</returns>
+    /// <returns>Распарсенный XAML документ.</returns>
     /// <exception cref="ObjectDisposedException">Выбрасывается, если парсер уже освобожден.</exception>
     /// <exception cref="ArgumentNullException">Выбрасывается, если filePath равен null.</exception>
     /// <exception cref="FileNotFoundException">Выбрасывается, если файл не найден.</exception>
     /// <exception cref="InvalidOperationException">Выбрасывается при ошибках чтения файла.</exception>
+    /// <exception cref="XamlParseException">Выбрасывается с путем и позицией ошибки, если документ не удалось распарсить.</exception>
     public XamlDocument? ParseFile(string filePath)
     {
         ObjectDisposedException.ThrowIf(_disposed, this);
@@ -46,8 +48,7 @@ public sealed class XamlFileParser : IDisposable
 
         try
         {
-            var xamlContent = File.ReadAllText(filePath, Encoding.UTF8);
-            return _parser.ParseDocument(xamlContent);
+            return _parser.ParseDocumentFromFile(Path.GetFullPath(filePath));
         }
         catch (IOException ex)
         {
@@ -60,7 +61,7 @@ public sealed class XamlFileParser : IDisposable
     }
 
     /// <summary>
-    /// Асинхронно парсит XAML файл с использованием UTF-8 кодировки.
+    /// Асинхронно парсит XAML файл, определяя кодировку по BOM и XML-объявлению.
     /// </summary>
     /// <param name="filePath">Путь к XAML файлу.</param>
     /// <param name="cancellationToken">Токен отмены операции.</param>
@@ -79,8 +80,8 @@ public sealed class XamlFileParser : IDisposable
 
         try
         {
-            var xamlContent = await File.ReadAllTextAsync(filePath, Encoding.UTF8, cancellationToken);
-            return _parser.ParseDocument(xamlContent);
+            var xamlBytes = await File.ReadAllBytesAsync(filePath, cancellationToken);
+            return _parser.ParseDocument(xamlBytes);
         }
         catch (IOException ex)
         {
@@ -148,24 +149,12 @@ public sealed class XamlFileParser : IDisposable
         if (!Directory.Exists(directoryPath))
             throw new DirectoryNotFoundException($"Directory not found: {directoryPath}");
 
-        var xamlFiles = Directory.GetFiles(directoryPath, searchPattern, SearchOption.TopDirectoryOnly);
-        
-        foreach (var file in xamlFiles)
-        {
-            XamlDocument? document = null;
-            try
-            {
-                document = ParseFile(file);
-            }
-            catch
-            {
-                // Пропускаем файлы, которые не удалось распарсить
-                continue;
-            }
-            
-            if (document is not null)
-                yield return document;
-        }
+        // Файлы разбираются параллельно одним нативным вызовом;
+        // не распарсенные файлы в результат не попадают
+        var elements = NativeXamlParser.ParseXamlDirectory(Path.GetFullPath(directoryPath), searchPattern);
+
+        foreach (var element in elements)
+            yield return _parser.AddDocument(element);
     }
 
     /// <summary>
@@ -0,0 +1,48 @@
+namespace xaml_parser;
+
+/// <summary>
+/// Ошибка разбора XAML документа с позицией, полученной из нативной библиотеки.
+/// </summary>
+/// <remarks>
+/// Line и Column считаются с 1. Kind совпадает с XAML_ERROR_KIND_* из include/xaml_parser.h.
+/// </remarks>
+public sealed class XamlParseException : Exception
+{
+    public XamlParseException(int kind, string reason, uint line, uint column, string? filePath)
+        : base(Format(reason, line, column, filePath))
+    {
+        Kind = kind;
+        Reason = reason;
+        Line = line;
+        Column = column;
+        FilePath = filePath;
+    }
+
+    /// <summary>
+    /// Вид ошибки (XAML_ERROR_KIND_*).
+    /// </summary>
+    public int Kind { get; }
+
+    /// <summary>
+    /// Описание ошибки без пути и позиции.
+    /// </summary>
+    public string Reason { get; }
+
+    public uint Line { get; }
+
+    public uint Column { get; }
+
+    /// <summary>
+    /// Путь к файлу, если документ читался из файла.
+    /// </summary>
+    public string? FilePath { get; }
+
+    // Та же запись, что у ParseError в нативной библиотеке: путь:строка:столбец: описание
+    private static string Format(string reason, uint line, uint column, string? filePath) =>
+        (filePath, line) switch
+        {
+            (not null, > 0) => $"{filePath}:{line}:{column}: {reason}",
+            (not null, _) => $"{filePath}: {reason}",
+            _ => reason,
+        };
+}
@@ -51,6 +51,51 @@ public sealed class XamlParser : IDisposable
         return document;
     }
 
+    /// <summary>
+    /// Парсит XAML документ из байтов, определяя кодировку по BOM и XML-объявлению.
+    /// </summary>
+    /// <param name="xamlBytes">XAML контент в виде байтов файла.</param>
+    /// <returns>Распарсенный XAML документ или null, если парсинг не удался.</returns>
+    /// <exception cref="ObjectDisposedException">Выбрасывается, если парсер уже освобожден.</exception>
+    /// <exception cref="ArgumentNullException">Выбрасывается, если xamlBytes равен null.</exception>
+    public XamlDocument? ParseDocument(byte[] xamlBytes)
+    {
+        ObjectDisposedException.ThrowIf(_disposed, this);
+        ArgumentNullException.ThrowIfNull(xamlBytes);
+
+        var element = NativeXamlParser.ParseXamlBytes(xamlBytes);
+        if (element is null)
+            return null;
+
+        var document = new XamlDocument(element);
+        _documents.Add(document);
+        return document;
+    }
+
+    /// <summary>
+    /// Парсит XAML файл, читая его в нативной библиотеке.
+    /// </summary>
+    /// <param name="filePath">Путь к XAML файлу.</param>
+    /// <returns>Распарсенный XAML документ.</returns>
+    /// <exception cref="ObjectDisposedException">Выбрасывается, если парсер уже освобожден.</exception>
+    /// <exception cref="ArgumentNullException">Выбрасывается, если filePath равен null.</exception>
+    /// <exception cref="UnauthorizedAccessException">Выбрасывается, если нет прав на чтение файла.</exception>
+    /// <exception cref="IOException">Выбрасывается, если файл не удалось прочитать.</exception>
+    /// <exception cref="XamlParseException">Выбрасывается с позицией ошибки, если документ не удалось распарсить.</exception>
+    public XamlDocument? ParseDocumentFromFile(string filePath)
+    {
+        ObjectDisposedException.ThrowIf(_disposed, this);
+        ArgumentNullException.ThrowIfNull(filePath);
+
+        var element = NativeXamlParser.ParseXamlFile(filePath);
+        if (element is null)
+            return null;
+
+        var document = new XamlDocument(element);
+        _documents.Add(document);
+        return document;
+    }
+
     /// <summary>
     /// Парсит XAML документ из потока данных.
     /// </summary>
@@ -88,6 +133,18 @@ public sealed class XamlParser : IDisposable
         return ParseDocument(xamlContent);
     }
 
+    /// <summary>
+    /// Регистрирует документ, полученный пакетным разбором, для освобождения вместе с парсером.
+    /// </summary>
+    /// <param name="element">Корневой элемент документа.</param>
+    /// <returns>Созданный XAML документ.</returns>
+    internal XamlDocument AddDocument(XamlElement element)
+    {
+        var document = new XamlDocument(element);
+        _documents.Add(document);
+        return document;
+    }
+
     #endregion
 
     #region IDisposable Implementation